@@ -0,0 +1,107 @@
+// Compares allocations made by `LookupVec` for an item whose key is borrowed
+// through `#[derive(Lookup)]` against one that only implements `Lookup::key()`
+// and so clones its key every time it is looked at.
+//
+// Run with `cargo bench --bench key_allocations`.
+
+use lookupvec::Lookup;
+use lookupvec::LookupVec;
+
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::hint::black_box;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const N: usize = 100_000;
+
+#[derive(Clone, lookupvec_derive::Lookup)]
+struct Borrowed {
+    #[lookup_key]
+    id: String,
+}
+
+#[derive(Clone)]
+struct Cloned {
+    id: String,
+}
+
+impl Lookup for Cloned {
+    type Key = String;
+    fn key(&self) -> String {
+        self.id.clone()
+    }
+}
+
+fn measure<F: FnOnce()>(f: F) -> (usize, Duration) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    f();
+    (ALLOCATIONS.load(Ordering::Relaxed) - allocations, start.elapsed())
+}
+
+fn bench<T, F>(name: &str, make: F)
+where
+    T: Lookup<Key = String> + Clone,
+    F: Fn(String) -> T,
+{
+    let items: Vec<T> = (0..N).map(|i| make(format!("item-{i}"))).collect();
+    let replacements = items.clone();
+    let insertions = items.clone();
+    let probes = items.clone();
+
+    let mut vec = LookupVec::<T>::with_capacity(N);
+    let (push_new, push_new_time) = measure(|| {
+        for item in items {
+            vec.push(item);
+        }
+    });
+    let (push_replace, push_replace_time) = measure(|| {
+        for item in replacements {
+            black_box(vec.push(item));
+        }
+    });
+    let (insert_replace, insert_replace_time) = measure(|| {
+        for (index, item) in insertions.into_iter().enumerate() {
+            black_box(vec.insert(index, item));
+        }
+    });
+    let (contains, contains_time) = measure(|| {
+        for item in &probes {
+            black_box(vec.contains(item));
+        }
+    });
+
+    println!("{name}:");
+    println!("  push (new key):      {push_new:>7} allocations, {push_new_time:?}");
+    println!("  push (existing key): {push_replace:>7} allocations, {push_replace_time:?}");
+    println!("  insert (existing):   {insert_replace:>7} allocations, {insert_replace_time:?}");
+    println!("  contains:            {contains:>7} allocations, {contains_time:?}");
+}
+
+fn main() {
+    println!("{N} items with String keys\n");
+    bench("derived (borrowed key)", |id| Borrowed { id });
+    bench("manual key() (cloned key)", |id| Cloned { id });
+}
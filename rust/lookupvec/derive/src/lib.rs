@@ -1,22 +1,90 @@
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, DeriveInput, Data, Fields, Type, Error};
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro2::TokenTree;
+use proc_macro2::Span;
+use quote::{format_ident, quote, quote_spanned};
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, DeriveInput, Data, Fields, Generics, Type, Error, Visibility};
 
-#[proc_macro_derive(Lookup, attributes(lookup_key))]
+use std::collections::HashSet;
+
+#[proc_macro_derive(Lookup, attributes(lookup, lookup_key, lookup_index))]
 pub fn derive_lookup(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
-    let name = input.ident;
 
-    match validate_and_get_key_field(&input.data) {
-        Ok((key_field, key_type)) => {
-            let expanded = quote! {
-                impl Lookup for #name {
-                    type Key = #key_type;
-                    
-                    fn key(&self) -> Self::Key {
-                        self.#key_field.clone()
+    let attrs = match ContainerAttrs::parse(&input.attrs) {
+        Ok(attrs) => attrs,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let target = Target {
+        name: &input.ident,
+        generics: &input.generics,
+        krate: attrs.krate,
+    };
+    let krate = &target.krate;
+
+    let indexes = match index_fields(&input.data) {
+        Ok(indexes) => indexes,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    match validate_and_get_key_source(attrs.key, &input.data) {
+        Ok(key_source) => {
+            let key_impls = match key_source {
+                KeySource::Struct(key_fields) => match key_fields.as_slice() {
+                    [KeyField { member, ty, with: None, name }] => {
+                        let key_impls = expand_key(&target, ty, quote! { &self.#member });
+                        match name {
+                            Some(name) => {
+                                let impl_key_field = target.impl_for(quote! { #krate::KeyField });
+                                quote! {
+                                    #key_impls
+
+                                    #impl_key_field {
+                                        const KEY_FIELD: &'static str = #name;
+                                    }
+                                }
+                            },
+                            None => key_impls,
+                        }
+                    },
+                    [field @ KeyField { member, ty, with: Some(_), .. }] => {
+                        expand_computed_key(&target, ty, field.value(quote! { &self.#member }))
+                    },
+                    _ => expand_composite_key(&target, &input.vis, &key_fields),
+                },
+                KeySource::Enum { ty, variants } => {
+                    if variants.iter().all(|(_, field)| field.with.is_none()) {
+                        let arms = variants.iter().enumerate().map(|(i, (variant, field))| {
+                            let member = &field.member;
+                            let key = assert_key_type(i, &field.ty, quote_spanned! {ty.span()=> &#ty }, quote! { key });
+                            quote! {
+                                Self::#variant { #member: key, .. } => #key,
+                            }
+                        });
+                        expand_key(&target, &ty, quote! { match self { #(#arms)* } })
+                    } else {
+                        let arms = variants.iter().enumerate().map(|(i, (variant, field))| {
+                            let member = &field.member;
+                            let value = field.value(quote! { key });
+                            let value = assert_key_type(i, &field.ty, quote! { #ty }, value);
+                            quote! {
+                                Self::#variant { #member: key, .. } => #value,
+                            }
+                        });
+                        expand_computed_key(&target, &ty, quote! { match self { #(#arms)* } })
                     }
-                }
+                },
+                KeySource::Computed { expr, ty } => {
+                    expand_computed_key(&target, &ty, assert_key(expr.span(), quote! { #expr }))
+                },
+            };
+            let index_impls = expand_indexes(&target, &indexes);
+            let expanded = quote! {
+                #key_impls
+
+                #index_impls
             };
             TokenStream::from(expanded)
         }
@@ -24,46 +92,539 @@ pub fn derive_lookup(input: TokenStream) -> TokenStream {
     }
 }
 
-fn validate_and_get_key_field(data: &Data) -> Result<(syn::Ident, Type), Error> {
+/// Options from `#[lookup(...)]` on the struct or enum.
+struct ContainerAttrs {
+    /// Path to the `lookupvec` crate, from `crate = "..."`.
+    krate: syn::Path,
+    /// Computed key expression and type, from `key = ...` and `key_type = ...`.
+    key: Option<(syn::Expr, Type)>,
+}
+
+impl ContainerAttrs {
+    fn parse(attrs: &[syn::Attribute]) -> Result<Self, Error> {
+        let mut krate = syn::parse_quote!(::lookupvec);
+        let mut computed = None;
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("lookup")) {
+            let mut key = None;
+            let mut key_type = None;
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("key") {
+                    key = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("key_type") {
+                    key_type = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("crate") {
+                    let path: syn::LitStr = meta.value()?.parse()?;
+                    krate = path.parse()?;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `key`, `key_type` or `crate`"))
+                }
+            })?;
+            match (key, key_type) {
+                (Some(key), Some(key_type)) => computed = Some((key, key_type)),
+                (Some(_), None) => return Err(Error::new_spanned(
+                    attr,
+                    "#[lookup(key = ...)] also requires `key_type = ...`"
+                )),
+                (None, Some(_)) => return Err(Error::new_spanned(
+                    attr,
+                    "#[lookup(key_type = ...)] is only allowed together with `key = ...`"
+                )),
+                (None, None) => {},
+            }
+        }
+        Ok(ContainerAttrs { krate, key: computed })
+    }
+}
+
+/// The type being derived on.
+struct Target<'a> {
+    name: &'a syn::Ident,
+    generics: &'a Generics,
+    krate: syn::Path,
+}
+
+impl Target<'_> {
+    /// Returns `impl<...> #trait_path for Name<...> where ...`.
+    fn impl_for(&self, trait_path: TokenStream2) -> TokenStream2 {
+        let name = self.name;
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+        quote! { impl #impl_generics #trait_path for #name #ty_generics #where_clause }
+    }
+}
+
+struct KeyField {
+    member: syn::Member,
+    /// The key type, which is the field's type unless overridden with
+    /// `#[lookup_key(key_type = ...)]`.
+    ty: Type,
+    /// Function from `#[lookup_key(with = ...)]` that computes the key from a
+    /// reference to the field.
+    with: Option<syn::Path>,
+    /// The field's name as serde sees it, for named fields.
+    name: Option<String>,
+}
+
+impl KeyField {
+    /// Returns an expression for the owned key, given an expression for a
+    /// reference to the field.
+    fn value(&self, field_ref: TokenStream2) -> TokenStream2 {
+        match &self.with {
+            Some(with) => assert_key(with.span(), quote_spanned! {with.span()=> #with(#field_ref) }),
+            None => quote! { ::core::clone::Clone::clone(#field_ref) },
+        }
+    }
+}
+
+/// Where an item's key comes from.
+enum KeySource {
+    /// One or more `#[lookup_key]` fields of a struct.
+    Struct(Vec<KeyField>),
+    /// One `#[lookup_key]` field per variant, all of the same key type.
+    Enum {
+        ty: Box<Type>,
+        variants: Vec<(syn::Ident, KeyField)>,
+    },
+    /// An expression from `#[lookup(key = ..., key_type = ...)]`.
+    Computed {
+        expr: Box<syn::Expr>,
+        ty: Box<Type>,
+    },
+}
+
+/// Wraps `expr` in a check that its type can be a key, so that the error for
+/// one that can't points at `span` rather than at the derive.
+fn assert_key(span: Span, expr: TokenStream2) -> TokenStream2 {
+    quote_spanned! {span=>
+        {
+            fn assert_key<K>(key: K) -> K
+            where K: ::core::hash::Hash + ::core::cmp::Eq + ::core::clone::Clone + ::core::fmt::Debug {
+                key
+            }
+            assert_key(#expr)
+        }
+    }
+}
+
+/// Checks that `expr`, the key of the enum variant at `index`, has the type
+/// `expected` of the first variant's key, so that a mismatch points at the
+/// variant's key type `ty`.
+fn assert_key_type(index: usize, ty: &Type, expected: TokenStream2, expr: TokenStream2) -> TokenStream2 {
+    if index == 0 {
+        return expr;
+    }
+    quote_spanned! {ty.span()=>
+        {
+            let key = #expr;
+            let key: #expected = key;
+            key
+        }
+    }
+}
+
+/// Implements `Lookup` for a key stored in a single field. `key_ref`
+/// evaluates to a reference to the field.
+fn expand_key(target: &Target, key_type: &Type, key_ref: TokenStream2) -> TokenStream2 {
+    let krate = &target.krate;
+    let impl_lookup = target.impl_for(quote! { #krate::Lookup });
+    quote! {
+        #impl_lookup {
+            type Key = #key_type;
+            
+            fn key(&self) -> Self::Key {
+                ::core::clone::Clone::clone(#key_ref)
+            }
+
+            fn borrow_key(&self) -> ::core::option::Option<&Self::Key> {
+                ::core::option::Option::Some(#key_ref)
+            }
+        }
+    }
+}
+
+/// Implements `Lookup` for a key computed by `key_expr`, which may refer to
+/// `self`.
+fn expand_computed_key(target: &Target, key_type: &Type, key_expr: TokenStream2) -> TokenStream2 {
+    let krate = &target.krate;
+    let impl_lookup = target.impl_for(quote! { #krate::Lookup });
+    quote! {
+        #impl_lookup {
+            type Key = #key_type;
+
+            fn key(&self) -> Self::Key {
+                #key_expr
+            }
+        }
+    }
+}
+
+/// Collects every identifier in `tokens`, including those of lifetimes.
+fn collect_idents(tokens: TokenStream2, idents: &mut HashSet<String>) {
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => {
+                idents.insert(ident.to_string());
+            },
+            TokenTree::Group(group) => collect_idents(group.stream(), idents),
+            _ => {},
+        }
+    }
+}
+
+/// Returns the generic parameters of `generics` that are used by `types`.
+fn used_generics(generics: &Generics, types: &[&Type]) -> Generics {
+    let mut idents = HashSet::new();
+    for ty in types {
+        collect_idents(quote! { #ty }, &mut idents);
+    }
+    let params = generics.params.iter()
+        .filter(|param| {
+            let ident = match param {
+                syn::GenericParam::Lifetime(param) => &param.lifetime.ident,
+                syn::GenericParam::Type(param) => &param.ident,
+                syn::GenericParam::Const(param) => &param.ident,
+            };
+            idents.contains(&ident.to_string())
+        })
+        .cloned()
+        .collect();
+    Generics { params, ..Default::default() }
+}
+
+/// Generates a `<Name>Key` struct holding every `#[lookup_key]` field. It is
+/// a tuple struct if the item is, and only has the item's generic parameters
+/// that the key fields use.
+///
+/// The key hashes like a tuple of its fields, and tuples of references to
+/// the fields' borrowed forms are `Equivalent` to it, so items can be looked
+/// up with e.g. `vec.get(&("acme", "foo"))` without allocating.
+fn expand_composite_key(
+    target: &Target,
+    vis: &Visibility,
+    key_fields: &[KeyField],
+) -> TokenStream2 {
+    let krate = &target.krate;
+    let name = target.name;
+    let key_name = format_ident!("{}Key", name);
+    let item_members: Vec<_> = key_fields.iter().map(|field| &field.member).collect();
+    let types: Vec<_> = key_fields.iter().map(|field| &field.ty).collect();
+    let values: Vec<_> = key_fields.iter()
+        .map(|field| {
+            let member = &field.member;
+            field.value(quote! { &self.#member })
+        })
+        .collect();
+    let positions: Vec<_> = (0..key_fields.len()).map(syn::Index::from).collect();
+    let params: Vec<_> = (0..key_fields.len()).map(|i| format_ident!("Q{}", i)).collect();
+    let doc = format!("Key of [`{name}`], made up of its `#[lookup_key]` fields.");
+
+    let key_generics = used_generics(target.generics, &types);
+    let (key_impl_generics, key_ty_generics, _) = key_generics.split_for_impl();
+    let key_params = key_generics.params.iter();
+    let key_type = quote! { #key_name #key_ty_generics };
+
+    let (definition, key_members): (TokenStream2, Vec<syn::Member>) = match key_fields[0].member {
+        syn::Member::Named(_) => {
+            let definition = quote! {
+                #vis struct #key_name #key_generics {
+                    #(pub #item_members: #types,)*
+                }
+            };
+            let key_members = item_members.iter().map(|member| (*member).clone()).collect();
+            (definition, key_members)
+        },
+        syn::Member::Unnamed(_) => {
+            let definition = quote! {
+                #vis struct #key_name #key_generics (#(pub #types),*);
+            };
+            let key_members = positions.iter().map(|i| syn::Member::Unnamed(i.clone())).collect();
+            (definition, key_members)
+        },
+    };
+    let impl_lookup = target.impl_for(quote! { #krate::Lookup });
+
+    quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        #definition
+
+        impl #key_impl_generics ::core::hash::Hash for #key_type
+        where #(#types: ::core::hash::Hash),*
+        {
+            fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+                // Hash like the equivalent tuple
+                #(::core::hash::Hash::hash(&self.#key_members, state);)*
+            }
+        }
+
+        impl<#(#key_params,)* #(#params: ?Sized + #krate::Equivalent<#types>),*>
+            #krate::Equivalent<#key_type> for (#(&#params,)*)
+        {
+            fn equivalent(&self, key: &#key_type) -> bool {
+                #(#krate::Equivalent::equivalent(self.#positions, &key.#key_members))&&*
+            }
+        }
+
+        impl #key_impl_generics ::core::convert::From<(#(#types,)*)> for #key_type {
+            fn from(key: (#(#types,)*)) -> Self {
+                #key_name {
+                    #(#key_members: key.#positions,)*
+                }
+            }
+        }
+
+        impl #key_impl_generics ::core::convert::From<#key_type> for (#(#types,)*) {
+            fn from(key: #key_type) -> Self {
+                (#(key.#key_members,)*)
+            }
+        }
+
+        #impl_lookup {
+            type Key = #key_type;
+
+            fn key(&self) -> Self::Key {
+                #key_name {
+                    #(#key_members: #values,)*
+                }
+            }
+        }
+    }
+}
+
+fn is_key_field(field: &syn::Field) -> bool {
+    field.attrs.iter().any(|attr| attr.path().is_ident("lookup_key"))
+}
+
+/// Returns the `#[lookup_key]` fields of `fields`, named or positional.
+fn key_fields(fields: &Fields) -> Result<Vec<KeyField>, Error> {
+    let mut key_fields = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("lookup_key")) {
+            let mut key_field = KeyField {
+                member: match &field.ident {
+                    Some(ident) => syn::Member::Named(ident.clone()),
+                    None => syn::Member::Unnamed(syn::Index::from(i)),
+                },
+                ty: field.ty.clone(),
+                with: None,
+                name: serde_name(field),
+            };
+            if !matches!(attr.meta, syn::Meta::Path(_)) {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("with") {
+                        key_field.with = Some(meta.value()?.parse()?);
+                        Ok(())
+                    } else if meta.path.is_ident("key_type") {
+                        key_field.ty = meta.value()?.parse()?;
+                        Ok(())
+                    } else {
+                        Err(meta.error("expected `with` or `key_type`"))
+                    }
+                })?;
+            }
+            key_fields.push(key_field);
+        }
+    }
+    Ok(key_fields)
+}
+
+/// Returns the name serde uses for a named field, following a plain
+/// `#[serde(rename = "...")]`.
+fn serde_name(field: &syn::Field) -> Option<String> {
+    let mut name = field.ident.as_ref()?.unraw().to_string();
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        // Other serde options are skipped, and any errors in them are left for
+        // serde to report
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.input.peek(syn::Token![=]) {
+                let value = meta.value()?;
+                if meta.path.is_ident("rename") {
+                    name = value.parse::<syn::LitStr>()?.value();
+                } else {
+                    value.parse::<syn::Expr>()?;
+                }
+            } else if meta.input.peek(syn::token::Paren) {
+                let content;
+                syn::parenthesized!(content in meta.input);
+                content.parse::<TokenStream2>()?;
+            }
+            Ok(())
+        });
+    }
+    Some(name)
+}
+
+fn validate_and_get_key_source(
+    computed: Option<(syn::Expr, Type)>,
+    data: &Data,
+) -> Result<KeySource, Error> {
+    if let Some((expr, ty)) = computed {
+        let fields: Vec<&syn::Field> = match data {
+            Data::Struct(data_struct) => data_struct.fields.iter().collect(),
+            Data::Enum(data_enum) => data_enum.variants.iter().flat_map(|v| &v.fields).collect(),
+            Data::Union(data_union) => data_union.fields.named.iter().collect(),
+        };
+        if let Some(field) = fields.into_iter().find(|field| is_key_field(field)) {
+            return Err(Error::new_spanned(
+                field,
+                "#[lookup_key] cannot be combined with #[lookup(key = ...)]"
+            ));
+        }
+        return Ok(KeySource::Computed { expr: Box::new(expr), ty: Box::new(ty) });
+    }
+
     match data {
         Data::Struct(data_struct) => {
-            match &data_struct.fields {
-                Fields::Named(fields) => {
-                    let key_fields: Vec<_> = fields.named.iter()
-                        .filter(|field| {
-                            field.attrs.iter()
-                                .any(|attr| attr.path().is_ident("lookup_key"))
-                        })
-                        .collect();
-
-                    match key_fields.len() {
-                        0 => Err(Error::new_spanned(
-                            fields,
-                            "struct must have exactly one field marked with #[lookup_key]"
-                        )),
-                        1 => Ok((
-                            key_fields[0].ident.clone().unwrap(),
-                            key_fields[0].ty.clone()
-                        )),
-                        _ => Err(Error::new_spanned(
-                            fields,
-                            "multiple #[lookup_key] attributes found, only one is allowed"
-                        )),
-                    }
-                },
-                _ => Err(Error::new_spanned(
-                    data_struct.struct_token,
-                    "only named fields are supported"
-                )),
+            let key_fields = key_fields(&data_struct.fields)?;
+            if key_fields.is_empty() {
+                let err = "struct must have at least one field marked with #[lookup_key]";
+                return Err(match &data_struct.fields {
+                    Fields::Unit => Error::new_spanned(data_struct.struct_token, err),
+                    fields => Error::new_spanned(fields, err),
+                });
             }
+            Ok(KeySource::Struct(key_fields))
+        },
+        Data::Enum(data_enum) => {
+            if data_enum.variants.is_empty() {
+                return Err(Error::new_spanned(
+                    data_enum.enum_token,
+                    "enum must have at least one variant"
+                ));
+            }
+            let mut variants = Vec::new();
+            for variant in &data_enum.variants {
+                let ident = &variant.ident;
+                let mut key_fields = key_fields(&variant.fields)?;
+                match key_fields.len() {
+                    0 => return Err(Error::new_spanned(
+                        variant,
+                        format!("variant `{ident}` must have a field marked with #[lookup_key]")
+                    )),
+                    1 => {},
+                    _ => return Err(Error::new_spanned(
+                        variant,
+                        format!("variant `{ident}` has multiple #[lookup_key] fields, only one is allowed")
+                    )),
+                }
+                variants.push((ident.clone(), key_fields.remove(0)));
+            }
+            // The key types of the other variants are checked against the
+            // first in the expansion, so that aliases and paths compare equal
+            let ty = Box::new(variants[0].1.ty.clone());
+            Ok(KeySource::Enum { ty, variants })
         },
-        Data::Enum(data_enum) => Err(Error::new_spanned(
-            data_enum.enum_token,
-            "Lookup can only be derived for structs"
-        )),
         Data::Union(data_union) => Err(Error::new_spanned(
             data_union.union_token,
-            "Lookup can only be derived for structs"
+            "Lookup can only be derived for structs and enums"
         )),
     }
 }
+
+struct IndexField {
+    ident: syn::Ident,
+    ty: Type,
+    vis: Visibility,
+    unique: bool,
+}
+
+/// Collects the fields marked with `#[lookup_index]` or
+/// `#[lookup_index(unique)]`.
+fn index_fields(data: &Data) -> Result<Vec<IndexField>, Error> {
+    let is_index = |attr: &&syn::Attribute| attr.path().is_ident("lookup_index");
+    let fields = match data {
+        Data::Struct(data_struct) => &data_struct.fields,
+        Data::Enum(data_enum) => {
+            let field = data_enum.variants.iter()
+                .flat_map(|variant| &variant.fields)
+                .find(|field| field.attrs.iter().any(|attr| is_index(&attr)));
+            return match field {
+                Some(field) => Err(Error::new_spanned(
+                    field,
+                    "#[lookup_index] is only supported on structs"
+                )),
+                None => Ok(Vec::new()),
+            };
+        },
+        Data::Union(_) => return Ok(Vec::new()),
+    };
+    let mut indexes = Vec::new();
+    for field in fields {
+        for attr in field.attrs.iter().filter(is_index) {
+            let Some(ident) = &field.ident else {
+                return Err(Error::new_spanned(
+                    attr,
+                    "#[lookup_index] is only supported on named fields"
+                ));
+            };
+            let mut unique = false;
+            if !matches!(attr.meta, syn::Meta::Path(_)) {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("unique") {
+                        unique = true;
+                        Ok(())
+                    } else {
+                        Err(meta.error("expected `unique`"))
+                    }
+                })?;
+            }
+            indexes.push(IndexField {
+                ident: ident.clone(),
+                ty: field.ty.clone(),
+                vis: field.vis.clone(),
+                unique,
+            });
+        }
+    }
+    Ok(indexes)
+}
+
+/// Implements `LookupIndexes` and adds a `<FIELD>_INDEX` const per index.
+fn expand_indexes(target: &Target, indexes: &[IndexField]) -> TokenStream2 {
+    if indexes.is_empty() {
+        return TokenStream2::new();
+    }
+    let krate = &target.krate;
+    let count = indexes.len();
+    let infos = indexes.iter().enumerate().map(|(id, index)| {
+        let IndexField { ident, unique, .. } = index;
+        let field_name = ident.to_string();
+        quote! {
+            #id => #krate::index::IndexInfo {
+                name: #field_name,
+                unique: #unique,
+                hash: |item, mut state| ::core::hash::Hash::hash(&item.#ident, &mut state),
+                eq: |a, b| a.#ident == b.#ident,
+            },
+        }
+    });
+    let consts = indexes.iter().enumerate().map(|(id, index)| {
+        let IndexField { ident, ty, vis, .. } = index;
+        let const_name = format_ident!("{}_INDEX", ident.to_string().to_uppercase());
+        quote! {
+            #vis const #const_name: #krate::index::IndexField<Self, #ty> =
+                #krate::index::IndexField::new(#id, |item| &item.#ident);
+        }
+    });
+    let impl_indexes = target.impl_for(quote! { #krate::index::LookupIndexes });
+    let name = target.name;
+    let (impl_generics, ty_generics, where_clause) = target.generics.split_for_impl();
+    quote! {
+        #impl_indexes {
+            const INDEX_COUNT: usize = #count;
+
+            fn index_info(id: usize) -> #krate::index::IndexInfo<Self> {
+                match id {
+                    #(#infos)*
+                    _ => ::core::panic!("no secondary index {id}"),
+                }
+            }
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            #(#consts)*
+        }
+    }
+}
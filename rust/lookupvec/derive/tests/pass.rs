@@ -0,0 +1,5 @@
+#[test]
+fn pass() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/pass/*.rs");
+}
@@ -0,0 +1,27 @@
+use lookupvec::LookupVec;
+
+mod reexport {
+    pub use lookupvec::*;
+}
+
+mod items {
+    #[derive(Debug, lookupvec_derive::Lookup)]
+    pub struct Tag {
+        #[lookup_key]
+        pub id: u32,
+    }
+
+    #[derive(Debug, lookupvec_derive::Lookup)]
+    #[lookup(crate = "crate::reexport")]
+    pub struct Renamed {
+        #[lookup_key]
+        pub id: u32,
+    }
+}
+
+fn main() {
+    let vec = LookupVec::from([items::Tag { id: 1 }]);
+    assert!(vec.contains_key(&1));
+    let vec = LookupVec::from([items::Renamed { id: 2 }]);
+    assert!(vec.contains_key(&2));
+}
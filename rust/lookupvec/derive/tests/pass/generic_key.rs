@@ -0,0 +1,47 @@
+use lookupvec::Lookup;
+use lookupvec::LookupVec;
+
+#[derive(Debug, Clone, PartialEq, Lookup)]
+struct Node<M> {
+    #[lookup_key]
+    id: u64,
+    meta: M,
+}
+
+#[derive(Debug, Clone, PartialEq, Lookup)]
+struct Borrowed<'a, T: ?Sized + core::fmt::Debug> {
+    #[lookup_key]
+    name: &'a str,
+    value: &'a T,
+}
+
+#[derive(Debug, Clone, PartialEq, Lookup)]
+struct Fixed<const N: usize> {
+    #[lookup_key]
+    id: [u8; N],
+}
+
+#[derive(Debug, Clone, PartialEq, Lookup)]
+struct Edge<'a, M> where M: Clone {
+    #[lookup_key]
+    from: &'a str,
+    #[lookup_key]
+    to: &'a str,
+    meta: M,
+}
+
+fn main() {
+    let vec = LookupVec::from([Node { id: 1, meta: "a" }, Node { id: 2, meta: "b" }]);
+    assert_eq!(vec.get(&2).unwrap().meta, "b");
+
+    let value = String::from("x");
+    let vec = LookupVec::from([Borrowed { name: "foo", value: value.as_str() }]);
+    assert_eq!(vec.get("foo").unwrap().value, "x");
+
+    let vec = LookupVec::from([Fixed { id: [1, 2] }, Fixed { id: [3, 4] }]);
+    assert_eq!(vec.get_index_of(&[3, 4]), Some(1));
+
+    let vec = LookupVec::from([Edge { from: "a", to: "b", meta: 1 }]);
+    assert_eq!(vec.get(&(&"a", &"b")).unwrap().meta, 1);
+    assert_eq!(vec[0].key(), EdgeKey { from: "a", to: "b" });
+}
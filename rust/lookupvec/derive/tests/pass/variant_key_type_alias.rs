@@ -0,0 +1,20 @@
+use lookupvec::Lookup;
+
+type Id = String;
+
+#[derive(Lookup)]
+enum Resource {
+    Disk {
+        #[lookup_key]
+        id: String,
+    },
+    Network(#[lookup_key] Id),
+    Volume(#[lookup_key] std::string::String),
+}
+
+fn main() {
+    let network = Resource::Network("eth0".to_owned());
+    assert_eq!(network.key(), "eth0");
+    assert_eq!(Resource::Volume("vol".to_owned()).key(), "vol");
+    assert_eq!(Resource::Disk { id: "sda".to_owned() }.key(), "sda");
+}
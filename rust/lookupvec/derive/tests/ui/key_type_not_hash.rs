@@ -0,0 +1,19 @@
+use lookupvec::Lookup;
+
+#[derive(Lookup)]
+#[lookup(key = self.weight * 2.0, key_type = f64)]
+struct Computed {
+    weight: f64,
+}
+
+fn round(value: &f64) -> f64 {
+    value.round()
+}
+
+#[derive(Lookup)]
+struct With {
+    #[lookup_key(with = round, key_type = f64)]
+    weight: f64,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use lookupvec::Lookup;
+
+#[derive(Lookup)]
+enum Resource {
+    Disk {
+        #[lookup_key]
+        id: String,
+    },
+    Network(#[lookup_key] u32),
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use lookupvec::Lookup;
+
+#[derive(Lookup)]
+enum Resource {
+    Disk {
+        #[lookup_key]
+        id: String,
+    },
+    Network {
+        id: String,
+    },
+}
+
+fn main() {}
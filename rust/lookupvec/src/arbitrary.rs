@@ -0,0 +1,79 @@
+use crate::core::Lookup;
+use crate::vec::LookupVec;
+
+use arbitrary::Arbitrary;
+use arbitrary::Result;
+use arbitrary::Unstructured;
+
+use core::hash::BuildHasher;
+
+/// Items with the same key as an earlier item are skipped, so that the vec
+/// holds every generated key once.
+impl<'a, T, S> Arbitrary<'a> for LookupVec<T, S>
+where
+    T: Lookup + Arbitrary<'a>,
+    S: BuildHasher + Default,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        collect_distinct(u.arbitrary_iter()?)
+    }
+
+    fn arbitrary_take_rest(u: Unstructured<'a>) -> Result<Self> {
+        collect_distinct(u.arbitrary_take_rest_iter()?)
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (0, None)
+    }
+}
+
+fn collect_distinct<T, S, I>(items: I) -> Result<LookupVec<T, S>>
+where
+    T: Lookup,
+    S: BuildHasher + Default,
+    I: Iterator<Item = Result<T>>,
+{
+    let mut vec = LookupVec::with_hasher(S::default());
+    for item in items {
+        let item = item?;
+        if !vec.contains(&item) {
+            vec.push(item);
+        }
+    }
+    Ok(vec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use lookupvec_derive::Lookup;
+
+    use alloc::vec::Vec;
+
+    #[derive(Debug, PartialEq, Lookup)]
+    struct Small {
+        #[lookup_key]
+        id: u8,
+        value: u8,
+    }
+
+    impl<'a> Arbitrary<'a> for Small {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(Small { id: u.int_in_range(0..=3)?, value: u.arbitrary()? })
+        }
+    }
+
+    #[test]
+    fn test_arbitrary_distinct_keys() {
+        // Each item is preceded by a byte saying whether to continue
+        let data = [1, 0, 10, 1, 1, 20, 1, 0, 30, 0];
+        let expected = vec![Small { id: 0, value: 10 }, Small { id: 1, value: 20 }];
+
+        let vec: LookupVec<Small> = Unstructured::new(&data).arbitrary().unwrap();
+        assert_eq!(vec.into_iter().collect::<Vec<_>>(), expected);
+
+        let vec = LookupVec::<Small>::arbitrary_take_rest(Unstructured::new(&data)).unwrap();
+        assert_eq!(vec.into_iter().collect::<Vec<_>>(), expected);
+    }
+}
@@ -0,0 +1,459 @@
+//! A fixed-capacity `LookupVec` that stores its items inline, for targets
+//! without an allocator.
+
+use crate::core::Lookup;
+use crate::core::with_key;
+
+use equivalent::Equivalent;
+
+use core::fmt;
+use core::hash::BuildHasher;
+use core::hash::Hash;
+use core::mem::MaybeUninit;
+use core::ops::Index;
+use core::ptr;
+use core::slice;
+
+/// Marks a slot of the hash table that holds no item.
+const EMPTY: usize = usize::MAX;
+
+/// A `LookupVec` that holds at most `N` items, without allocating.
+///
+/// Items are stored in an inline array, in order, alongside an
+/// open-addressing hash table of `N` slots that maps keys to indices. Pushing
+/// a new key onto a full vec returns a `CapacityError` with the item.
+///
+/// There is no mutable access to items, so keys can't change while in the
+/// vec.
+///
+/// Lookups probe the table linearly, so they take O(N) in the worst case:
+/// when the vec is close to full, or when many keys hash to nearby slots.
+/// Choose `N` with some headroom above the number of items to keep them
+/// short.
+pub struct ArrayLookupVec<T: Lookup, const N: usize, S = FxBuildHasher> {
+    items: [MaybeUninit<T>; N],
+    len: usize,
+    /// Index into `items` of the item whose key hashes to each slot, found by
+    /// linear probing.
+    slots: [usize; N],
+    hasher: S,
+}
+
+impl<T: Lookup, const N: usize> ArrayLookupVec<T, N> {
+    pub const fn new() -> Self {
+        Self::with_hasher(FxBuildHasher)
+    }
+}
+
+impl<T: Lookup, const N: usize, S> ArrayLookupVec<T, N, S> {
+    pub const fn with_hasher(hasher: S) -> Self {
+        ArrayLookupVec {
+            items: [const { MaybeUninit::uninit() }; N],
+            len: 0,
+            slots: [EMPTY; N],
+            hasher,
+        }
+    }
+
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub const fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hasher
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: the first `len` items are initialized
+        unsafe { slice::from_raw_parts(self.items.as_ptr().cast(), self.len) }
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<&T> {
+        self.as_slice().get(index)
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.as_slice().first()
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.as_slice().last()
+    }
+
+    pub fn clear(&mut self) {
+        let items: *mut [T] = ptr::slice_from_raw_parts_mut(self.items.as_mut_ptr().cast(), self.len);
+        // Forget the items first, in case dropping one panics
+        self.len = 0;
+        self.slots = [EMPTY; N];
+        // SAFETY: the items were initialized and are no longer reachable
+        unsafe { ptr::drop_in_place(items) }
+    }
+}
+
+impl<T: Lookup, const N: usize, S: BuildHasher> ArrayLookupVec<T, N, S> {
+    /// Pushes the item, replacing the item with the same key in place if
+    /// there is one. Returns the replaced item.
+    ///
+    /// ***Fails*** if the key is new and the vec is full.
+    pub fn push(&mut self, value: T) -> Result<Option<T>, CapacityError<T>> {
+        let hash = with_key(&value, |key| self.hasher.hash_one(key));
+        match with_key(&value, |key| self.probe(hash, key)) {
+            Ok(slot) => {
+                let index = self.slots[slot];
+                // SAFETY: slots only hold indices of initialized items
+                let existing = unsafe { self.items[index].assume_init_mut() };
+                Ok(Some(core::mem::replace(existing, value)))
+            },
+            Err(Some(slot)) if self.len < N => {
+                self.items[self.len].write(value);
+                self.slots[slot] = self.len;
+                self.len += 1;
+                Ok(None)
+            },
+            Err(_) => Err(CapacityError(value)),
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&T>
+    where Q: ?Sized + Hash + Equivalent<T::Key> {
+        self.get_index_of(key).map(|index| &self.as_slice()[index])
+    }
+
+    pub fn get_index_of<Q>(&self, key: &Q) -> Option<usize>
+    where Q: ?Sized + Hash + Equivalent<T::Key> {
+        let slot = self.probe(self.hasher.hash_one(key), key).ok()?;
+        Some(self.slots[slot])
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where Q: ?Sized + Hash + Equivalent<T::Key> {
+        self.get_index_of(key).is_some()
+    }
+
+    /// Removes the item with this key, shifting the items after it down.
+    pub fn shift_remove<Q>(&mut self, key: &Q) -> Option<T>
+    where Q: ?Sized + Hash + Equivalent<T::Key> {
+        let index = self.remove_key(key)?;
+        // SAFETY: the item is initialized and its slot was removed, and the
+        // items after it are moved down over it
+        let item = unsafe {
+            let base: *mut T = self.items.as_mut_ptr().cast();
+            let item = ptr::read(base.add(index));
+            ptr::copy(base.add(index + 1), base.add(index), self.len - index - 1);
+            item
+        };
+        self.len -= 1;
+        for slot in self.slots.iter_mut().filter(|slot| **slot != EMPTY && **slot > index) {
+            *slot -= 1;
+        }
+        Some(item)
+    }
+
+    /// Removes the item with this key, moving the last item into its place.
+    pub fn swap_remove<Q>(&mut self, key: &Q) -> Option<T>
+    where Q: ?Sized + Hash + Equivalent<T::Key> {
+        let index = self.remove_key(key)?;
+        let last = self.len - 1;
+        if index != last {
+            let moved = self.slots.iter().position(|&slot| slot == last).unwrap();
+            self.slots[moved] = index;
+        }
+        // SAFETY: the item is initialized and its slot was removed, and the
+        // last item is moved over it
+        let item = unsafe {
+            let base: *mut T = self.items.as_mut_ptr().cast();
+            let item = ptr::read(base.add(index));
+            ptr::copy(base.add(last), base.add(index), usize::from(index != last));
+            item
+        };
+        self.len -= 1;
+        Some(item)
+    }
+
+    /// Returns the slot holding `key`, or else the empty slot where it would
+    /// go, if any.
+    fn probe<Q>(&self, hash: u64, key: &Q) -> Result<usize, Option<usize>>
+    where Q: ?Sized + Equivalent<T::Key> {
+        let mut slot = Self::home_slot(hash);
+        for _ in 0..N {
+            match self.slots[slot] {
+                EMPTY => return Err(Some(slot)),
+                index if with_key(&self.as_slice()[index], |k| key.equivalent(k)) => return Ok(slot),
+                _ => slot = (slot + 1) % N,
+            }
+        }
+        Err(None)
+    }
+
+    /// Removes the slot of the item with this key, and returns its index.
+    fn remove_key<Q>(&mut self, key: &Q) -> Option<usize>
+    where Q: ?Sized + Hash + Equivalent<T::Key> {
+        let mut hole = self.probe(self.hasher.hash_one(key), key).ok()?;
+        let index = self.slots[hole];
+        self.slots[hole] = EMPTY;
+
+        // Move later slots of the same probe run back, so that lookups don't
+        // stop at the hole
+        let mut slot = (hole + 1) % N;
+        while self.slots[slot] != EMPTY {
+            let item = &self.as_slice()[self.slots[slot]];
+            let home = Self::home_slot(with_key(item, |k| self.hasher.hash_one(k)));
+            if (hole + N - home) % N < (slot + N - home) % N {
+                self.slots[hole] = self.slots[slot];
+                self.slots[slot] = EMPTY;
+                hole = slot;
+            }
+            slot = (slot + 1) % N;
+        }
+        Some(index)
+    }
+
+    /// The slot a hash probes first. Uses the high bits, which are the best
+    /// mixed for multiplicative hashes.
+    fn home_slot(hash: u64) -> usize {
+        ((hash as u128 * N as u128) >> 64) as usize
+    }
+}
+
+impl<T: Lookup, const N: usize, S> Drop for ArrayLookupVec<T, N, S> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T: Lookup, const N: usize, S: Default> Default for ArrayLookupVec<T, N, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<T: Lookup + Clone, const N: usize, S: Clone> Clone for ArrayLookupVec<T, N, S> {
+    fn clone(&self) -> Self {
+        let mut vec = Self::with_hasher(self.hasher.clone());
+        for item in self.iter() {
+            vec.items[vec.len].write(item.clone());
+            vec.len += 1;
+        }
+        vec.slots = self.slots;
+        vec
+    }
+}
+
+impl<T: Lookup + fmt::Debug, const N: usize, S> fmt::Debug for ArrayLookupVec<T, N, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, const N: usize, S, const M: usize, S2> PartialEq<ArrayLookupVec<T, M, S2>> for ArrayLookupVec<T, N, S>
+where T: Lookup + PartialEq {
+    fn eq(&self, other: &ArrayLookupVec<T, M, S2>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Lookup + Eq, const N: usize, S> Eq for ArrayLookupVec<T, N, S> {}
+
+impl<T: Lookup, const N: usize, S> Index<usize> for ArrayLookupVec<T, N, S> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.as_slice()[index]
+    }
+}
+
+impl<'a, T: Lookup, const N: usize, S> IntoIterator for &'a ArrayLookupVec<T, N, S> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An item was pushed onto a full `ArrayLookupVec`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct CapacityError<T>(pub T);
+
+impl<T> CapacityError<T> {
+    /// Returns the item that didn't fit.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Debug for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CapacityError")
+    }
+}
+
+impl<T> fmt::Display for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ArrayLookupVec is full")
+    }
+}
+
+impl<T> core::error::Error for CapacityError<T> {}
+
+/// The default hasher of `ArrayLookupVec`. Fast and deterministic, but not
+/// resistant to keys chosen to collide; use a keyed `BuildHasher` for
+/// untrusted keys.
+pub use rustc_hash::FxBuildHasher;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use lookupvec_derive::Lookup;
+
+    use core::hash::BuildHasherDefault;
+    use core::hash::Hasher;
+
+    #[derive(Debug, Clone, PartialEq, Lookup)]
+    struct Sensor {
+        #[lookup_key]
+        id: u16,
+        reading: i32,
+    }
+
+    fn sensor(id: u16, reading: i32) -> Sensor {
+        Sensor { id, reading }
+    }
+
+    fn ids<const N: usize>(vec: &ArrayLookupVec<Sensor, N>) -> ([u16; N], usize) {
+        let mut ids = [0; N];
+        for (id, item) in ids.iter_mut().zip(vec) {
+            *id = item.id;
+        }
+        (ids, vec.len())
+    }
+
+    #[test]
+    fn test_push_and_get() {
+        let mut vec = ArrayLookupVec::<Sensor, 3>::new();
+        assert_eq!(vec.push(sensor(7, 1)), Ok(None));
+        assert_eq!(vec.push(sensor(3, 2)), Ok(None));
+        assert_eq!(vec.push(sensor(7, 3)), Ok(Some(sensor(7, 1))));
+        assert_eq!(vec.push(sensor(9, 4)), Ok(None));
+        assert!(vec.is_full());
+
+        assert_eq!(vec.push(sensor(1, 5)), Err(CapacityError(sensor(1, 5))));
+        assert_eq!(vec.push(sensor(3, 6)), Ok(Some(sensor(3, 2))));
+        assert_eq!(vec.get(&7), Some(&sensor(7, 3)));
+        assert_eq!(vec.get(&1), None);
+        assert_eq!(vec.get_index_of(&9), Some(2));
+        assert_eq!(vec.get_index(1), Some(&sensor(3, 6)));
+        assert_eq!(vec[0], sensor(7, 3));
+        assert_eq!(ids(&vec), ([7, 3, 9], 3));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut vec = ArrayLookupVec::<Sensor, 8>::new();
+        for id in 0..8 {
+            vec.push(sensor(id, 0)).unwrap();
+        }
+        assert_eq!(vec.shift_remove(&2), Some(sensor(2, 0)));
+        assert_eq!(vec.shift_remove(&2), None);
+        assert_eq!(vec.swap_remove(&0), Some(sensor(0, 0)));
+        assert_eq!(ids(&vec), ([7, 1, 3, 4, 5, 6, 0, 0], 6));
+        for (index, item) in vec.iter().enumerate() {
+            assert_eq!(vec.get_index_of(&item.id), Some(index));
+        }
+
+        vec.push(sensor(10, 0)).unwrap();
+        vec.push(sensor(11, 0)).unwrap();
+        assert!(vec.push(sensor(12, 0)).is_err());
+        assert_eq!(vec.swap_remove(&11), Some(sensor(11, 0)));
+        assert_eq!(vec.get_index_of(&10), Some(6));
+
+        vec.clear();
+        assert!(vec.is_empty());
+        assert_eq!(vec.get(&10), None);
+    }
+
+    #[test]
+    fn test_colliding_hashes() {
+        // Every key probes from the same slot
+        #[derive(Default)]
+        struct Constant;
+
+        impl Hasher for Constant {
+            fn write(&mut self, _bytes: &[u8]) {}
+            fn finish(&self) -> u64 {
+                0
+            }
+        }
+
+        let mut vec = ArrayLookupVec::<Sensor, 4, BuildHasherDefault<Constant>>::default();
+        for id in 0..4 {
+            vec.push(sensor(id, 0)).unwrap();
+        }
+        assert_eq!(vec.get_index_of(&4), None);
+        assert_eq!(vec.shift_remove(&1), Some(sensor(1, 0)));
+        assert_eq!(vec.get_index_of(&3), Some(2));
+        vec.push(sensor(4, 0)).unwrap();
+        assert_eq!(vec.swap_remove(&0), Some(sensor(0, 0)));
+        for (index, item) in vec.iter().enumerate() {
+            assert_eq!(vec.get_index_of(&item.id), Some(index));
+        }
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        let mut vec = ArrayLookupVec::<Sensor, 0>::new();
+        assert!(vec.push(sensor(1, 0)).is_err());
+        assert_eq!(vec.get(&1), None);
+        assert_eq!(vec.shift_remove(&1), None);
+    }
+
+    #[test]
+    fn test_clone_and_drop() {
+        use core::cell::Cell;
+
+        #[derive(Clone, Lookup)]
+        struct Counted<'a> {
+            #[lookup_key]
+            id: u8,
+            drops: &'a Cell<usize>,
+        }
+
+        impl Drop for Counted<'_> {
+            fn drop(&mut self) {
+                self.drops.set(self.drops.get() + 1);
+            }
+        }
+
+        let drops = Cell::new(0);
+        let mut vec = ArrayLookupVec::<Counted, 4>::new();
+        for id in 0..3 {
+            assert!(vec.push(Counted { id, drops: &drops }).is_ok());
+        }
+        let copy = vec.clone();
+        assert_eq!(copy.get_index_of(&2), Some(2));
+
+        drop(vec.shift_remove(&0));
+        assert_eq!(drops.get(), 1);
+        drop(vec);
+        assert_eq!(drops.get(), 3);
+        drop(copy);
+        assert_eq!(drops.get(), 6);
+    }
+}
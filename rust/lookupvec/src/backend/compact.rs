@@ -0,0 +1,708 @@
+use crate::backend::Backend;
+use crate::backend::store::RawSlice;
+use crate::backend::store::Storage;
+use crate::backend::store::Store;
+use crate::core::Lookup;
+use crate::core::with_key;
+use crate::guard::KeyCheck;
+use crate::guard::panicking;
+use crate::guard::simplify_range;
+use crate::vec::LookupVec;
+
+use ahash::random_state::RandomState;
+use hashbrown::HashTable;
+use indexmap::Equivalent;
+#[cfg(feature = "rayon")]
+use rayon::iter::IndexedParallelIterator;
+#[cfg(feature = "rayon")]
+use rayon::iter::IntoParallelIterator;
+#[cfg(feature = "rayon")]
+use rayon::iter::IntoParallelRefIterator;
+#[cfg(feature = "rayon")]
+use rayon::iter::IntoParallelRefMutIterator;
+#[cfg(feature = "rayon")]
+use rayon::iter::ParallelIterator;
+#[cfg(feature = "rayon")]
+use rayon::iter::RepeatN;
+#[cfg(feature = "rayon")]
+use rayon::iter::Zip;
+#[cfg(feature = "rayon")]
+use rayon::slice::ParallelSliceMut;
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::hash::BuildHasher;
+use core::hash::Hash;
+use core::iter;
+use core::iter::FusedIterator;
+use core::ops::Range;
+use core::ops::RangeBounds;
+use core::slice;
+
+/// A `Backend` that stores each key only once: the items live in a plain
+/// `Vec<T>`, with a hash table of their indices and hashes, and keys are
+/// recomputed from the items whenever they are needed.
+///
+/// That halves the key memory for items with large keys, at the cost of
+/// calling `Lookup::key()` on each probe. Items whose `Lookup::borrow_key()`
+/// returns a key (including everything using `#[derive(Lookup)]`) are probed
+/// without cloning, and `keys()` lends those keys out.
+///
+/// Since no old key is kept, a key changed through a mutable borrow is only
+/// noticed if its hash changed too, which is all that matters for finding it
+/// again. Panic messages can't say what the key was before.
+pub enum Compact {}
+
+impl Backend for Compact {}
+
+/// A `LookupVec` with the `Compact` backend.
+pub type CompactLookupVec<T, S = RandomState> = LookupVec<T, S, Compact>;
+
+impl<T: Lookup, S> LookupVec<T, S, Compact> {
+    /// Converts into a `Vec`, dropping the hash table.
+    pub fn into_vec(self) -> Vec<T> {
+        self.store.items
+    }
+}
+
+#[derive(Clone)]
+pub struct CompactStore<T, S> {
+    items: Vec<T>,
+    table: HashTable<Bucket>,
+    hasher: S,
+}
+
+/// An entry in the hash table: the index of an item, and the hash it is filed
+/// under. Keeping the hash means the table can grow without recomputing keys,
+/// and that an item filed under an old key is never mistaken for one filed
+/// under its new key.
+#[derive(Clone, Copy)]
+pub struct Bucket {
+    index: usize,
+    hash: u64,
+}
+
+fn bucket_hash(bucket: &Bucket) -> u64 {
+    bucket.hash
+}
+
+fn hash_item<T: Lookup, S: BuildHasher>(hasher: &S, item: &T) -> u64 {
+    with_key(item, |key| hasher.hash_one(key))
+}
+
+fn key_of<T: Lookup>(item: &T) -> Cow<'_, T::Key> {
+    match item.borrow_key() {
+        Some(key) => Cow::Borrowed(key),
+        None => Cow::Owned(item.key()),
+    }
+}
+
+fn into_key<T: Lookup>(item: T) -> T::Key {
+    item.key()
+}
+
+/// Returns true if `item`, at `index`, is filed under the hash of its current
+/// key.
+fn is_filed<T: Lookup, S: BuildHasher>(table: &HashTable<Bucket>, hasher: &S, index: usize, item: &T) -> bool {
+    let hash = hash_item(hasher, item);
+    table.find(hash, |bucket| bucket.index == index && bucket.hash == hash).is_some()
+}
+
+impl Storage for Compact {
+    type Store<T: Lookup, S> = CompactStore<T, S>;
+    type Slice<T: Lookup> = [T];
+    type Slot<'a, T: Lookup + 'a, S: 'a> = (&'a HashTable<Bucket>, &'a S);
+    type Key<'a, T: Lookup + 'a> = Cow<'a, T::Key>;
+
+    type Iter<'a, T: Lookup + 'a> = slice::Iter<'a, T>;
+    type SliceIterMut<'a, T: Lookup + 'a> = slice::IterMut<'a, T>;
+    type IterMut<'a, T: Lookup + 'a, S: 'a> = SlotsMut<'a, T, S>;
+    type IntoIter<T: Lookup> = alloc::vec::IntoIter<T>;
+    type Keys<'a, T: Lookup + 'a> = iter::Map<slice::Iter<'a, T>, fn(&'a T) -> Cow<'a, T::Key>>;
+    type IntoKeys<T: Lookup> = iter::Map<alloc::vec::IntoIter<T>, fn(T) -> T::Key>;
+    type Drain<'a, T: Lookup + 'a> = alloc::vec::Drain<'a, T>;
+
+    #[cfg(feature = "rayon")]
+    type ParIter<'a, T: Lookup + Sync + 'a> = rayon::slice::Iter<'a, T>
+    where T::Key: Sync;
+    #[cfg(feature = "rayon")]
+    type ParIterMut<'a, T: Lookup + Send + 'a, S: Sync + 'a> = Zip<RepeatN<(&'a HashTable<Bucket>, &'a S)>, rayon::slice::IterMut<'a, T>>
+    where T::Key: Send + Sync;
+    #[cfg(feature = "rayon")]
+    type IntoParIter<T: Lookup + Send> = rayon::vec::IntoIter<T>
+    where T::Key: Send;
+    #[cfg(feature = "rayon")]
+    type ParKeys<'a, T: Lookup + Sync + 'a> = rayon::iter::Map<rayon::slice::Iter<'a, T>, fn(&'a T) -> Cow<'a, T::Key>>
+    where T::Key: Send + Sync;
+
+    fn slot_holds<T: Lookup, S: BuildHasher>((table, hasher): (&HashTable<Bucket>, &S), index: usize, item: &T) -> bool {
+        is_filed(table, hasher, index, item)
+    }
+
+    fn slot_key<'a, T: Lookup + 'a, S: 'a>(_slot: (&'a HashTable<Bucket>, &'a S)) -> Option<&'a T::Key> {
+        None
+    }
+}
+
+impl<T: Lookup, S: BuildHasher> CompactStore<T, S> {
+    /// Probes the hash table, comparing against the items' current keys.
+    fn find_filed<Q>(&self, hash: u64, key: &Q) -> Option<usize>
+    where Q: ?Sized + Equivalent<T::Key> {
+        let items = &self.items;
+        self.table
+            .find(hash, |bucket| bucket.hash == hash && with_key(&items[bucket.index], |k| key.equivalent(k)))
+            .map(|bucket| bucket.index)
+    }
+
+    /// Returns the slot holding `index`, looking under the item's current key
+    /// first and scanning the table if it isn't there.
+    fn slot_mut(&mut self, index: usize) -> Option<&mut Bucket> {
+        let hash = hash_item(&self.hasher, &self.items[index]);
+        if self.table.find(hash, |bucket| bucket.index == index).is_some() {
+            self.table.find_mut(hash, |bucket| bucket.index == index)
+        } else {
+            self.table.iter_mut().find(|bucket| bucket.index == index)
+        }
+    }
+
+    /// Removes the slot holding `index`, filed under `hash` if known.
+    fn remove_slot(&mut self, index: usize, hash: Option<u64>) {
+        let hash = hash.unwrap_or_else(|| hash_item(&self.hasher, &self.items[index]));
+        let removed = match self.table.find_entry(hash, |bucket| bucket.index == index) {
+            Ok(entry) => {
+                entry.remove();
+                true
+            },
+            Err(_) => false,
+        };
+        if !removed {
+            self.table.retain(|bucket| bucket.index != index);
+        }
+    }
+
+    /// Files every item under its current key, leaving out items whose key
+    /// is already filed. Returns the index of the first one left out.
+    fn reindex(&mut self) -> Option<usize> {
+        let CompactStore { items, table, hasher } = self;
+        table.clear();
+        table.reserve(items.len(), bucket_hash);
+        let mut first_duplicate = None;
+        for (index, item) in items.iter().enumerate() {
+            let hash = hash_item(hasher, item);
+            let duplicate = with_key(item, |key| {
+                table.find(hash, |bucket| bucket.hash == hash && with_key(&items[bucket.index], |k| k == key)).is_some()
+            });
+            if duplicate {
+                first_duplicate.get_or_insert(index);
+                continue;
+            }
+            table.insert_unique(hash, Bucket { index, hash }, bucket_hash);
+        }
+        first_duplicate
+    }
+}
+
+impl<T: Lookup, S> Store<T, S, Compact> for CompactStore<T, S> {
+    fn with_capacity_and_hasher(n: usize, hasher: S) -> Self {
+        CompactStore {
+            items: Vec::with_capacity(n),
+            table: HashTable::with_capacity(n),
+            hasher,
+        }
+    }
+
+    fn clone_store(&self) -> Self
+    where T: Clone, S: Clone {
+        self.clone()
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn capacity(&self) -> usize {
+        self.items.capacity().min(self.table.capacity())
+    }
+
+    fn hasher(&self) -> &S {
+        &self.hasher
+    }
+
+    fn as_slice(&self) -> &[T] {
+        &self.items
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.items
+    }
+
+    fn into_boxed_slice(self) -> Box<[T]> {
+        self.items.into_boxed_slice()
+    }
+
+    fn into_iter(self) -> alloc::vec::IntoIter<T> {
+        self.items.into_iter()
+    }
+
+    fn into_keys(self) -> <Compact as Storage>::IntoKeys<T> {
+        self.items.into_iter().map(into_key as fn(T) -> T::Key)
+    }
+
+    fn iter_mut(&mut self) -> SlotsMut<'_, T, S> {
+        let CompactStore { items, table, hasher } = self;
+        SlotsMut { iter: items.iter_mut(), table, hasher }
+    }
+
+    fn stored_key(&self, _index: usize) -> Option<&T::Key> {
+        None
+    }
+
+    fn slot_hash(&self, index: usize) -> Option<u64>
+    where S: BuildHasher {
+        Some(hash_item(&self.hasher, &self.items[index]))
+    }
+
+    fn is_stored(&self, index: usize) -> bool
+    where S: BuildHasher {
+        // A changed key with an identical hash can't be detected without a
+        // copy of the old key, but it also doesn't need repairing unless it
+        // now duplicates another key
+        let item = &self.items[index];
+        is_filed(&self.table, &self.hasher, index, item)
+            && with_key(item, |key| self.find_filed(hash_item(&self.hasher, item), key)) == Some(index)
+    }
+
+    fn get_index_of<Q>(&self, key: &Q) -> Option<usize>
+    where S: BuildHasher, Q: ?Sized + Hash + Equivalent<T::Key> {
+        self.find_filed(self.hasher.hash_one(key), key)
+    }
+
+    fn find_hashed(&self, hash: u64, key: &T::Key) -> Option<usize>
+    where S: BuildHasher {
+        self.find_filed(hash, key)
+    }
+
+    fn push_unique(&mut self, hash: u64, key: Option<T::Key>, value: T) -> Option<T::Key>
+    where S: BuildHasher {
+        self.table.insert_unique(hash, Bucket { index: self.items.len(), hash }, bucket_hash);
+        self.items.push(value);
+        key
+    }
+
+    fn rekey_index(&mut self, index: usize, key: T::Key, hash: Option<u64>)
+    where S: BuildHasher {
+        self.remove_slot(index, hash);
+        let hash = self.hasher.hash_one(&key);
+        self.table.insert_unique(hash, Bucket { index, hash }, bucket_hash);
+    }
+
+    fn rebuild(&mut self, keys: Vec<(u64, T::Key)>)
+    where S: BuildHasher {
+        self.table.clear();
+        self.table.reserve(keys.len(), bucket_hash);
+        for (index, (hash, _)) in keys.into_iter().enumerate() {
+            self.table.insert_unique(hash, Bucket { index, hash }, bucket_hash);
+        }
+    }
+
+    fn truncate(&mut self, len: usize) {
+        if len < self.items.len() {
+            self.table.retain(|bucket| bucket.index < len);
+            self.items.truncate(len);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.table.clear();
+        self.items.clear();
+    }
+
+    fn reserve(&mut self, additional: usize)
+    where S: BuildHasher {
+        self.items.reserve(additional);
+        self.table.reserve(additional, bucket_hash);
+    }
+
+    fn reserve_exact(&mut self, additional: usize)
+    where S: BuildHasher {
+        self.items.reserve_exact(additional);
+        self.table.reserve(additional, bucket_hash);
+    }
+
+    fn shrink_to(&mut self, min_capacity: usize)
+    where S: BuildHasher {
+        self.items.shrink_to(min_capacity);
+        self.table.shrink_to(min_capacity, bucket_hash);
+    }
+
+    fn shrink_to_fit(&mut self)
+    where S: BuildHasher {
+        self.items.shrink_to_fit();
+        self.table.shrink_to_fit(bucket_hash);
+    }
+
+    fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> alloc::vec::Drain<'_, T> {
+        let bounds = (range.start_bound().cloned(), range.end_bound().cloned());
+        if let Some(Range { start, end }) = simplify_range(bounds, self.items.len()) {
+            let removed = end - start;
+            self.table.retain(|bucket| {
+                if bucket.index >= end {
+                    bucket.index -= removed;
+                    true
+                } else {
+                    bucket.index < start
+                }
+            });
+        }
+        self.items.drain(range)
+    }
+
+    fn split_off(&mut self, at: usize) -> Self
+    where S: BuildHasher + Clone {
+        let mut other = CompactStore {
+            items: self.items.split_off(at),
+            table: HashTable::new(),
+            hasher: self.hasher.clone(),
+        };
+        self.table.retain(|bucket| bucket.index < at);
+        other.reindex();
+        other
+    }
+
+    fn shift_remove_index(&mut self, index: usize) -> Option<T>
+    where S: BuildHasher {
+        if index >= self.items.len() {
+            return None;
+        }
+        self.table.retain(|bucket| {
+            if bucket.index == index {
+                return false;
+            }
+            if bucket.index > index {
+                bucket.index -= 1;
+            }
+            true
+        });
+        Some(self.items.remove(index))
+    }
+
+    fn swap_remove_index(&mut self, index: usize) -> Option<T>
+    where S: BuildHasher {
+        if index >= self.items.len() {
+            return None;
+        }
+        self.remove_slot(index, None);
+        let last = self.items.len() - 1;
+        if index != last {
+            if let Some(bucket) = self.slot_mut(last) {
+                bucket.index = index;
+            }
+        }
+        Some(self.items.swap_remove(index))
+    }
+
+    fn retain<F>(&mut self, mut keep: F)
+    where F: FnMut(&mut T) -> bool {
+        // Where each item ends up, or `usize::MAX` if it is removed
+        let mut moved_to = Vec::with_capacity(self.items.len());
+        let mut kept = 0;
+        self.items.retain_mut(|item| {
+            let keep = keep(item);
+            moved_to.push(if keep { kept } else { usize::MAX });
+            kept += keep as usize;
+            keep
+        });
+        if kept != moved_to.len() {
+            self.table.retain(|bucket| {
+                bucket.index = moved_to[bucket.index];
+                bucket.index != usize::MAX
+            });
+        }
+    }
+
+    #[allow(private_interfaces)]
+    fn extract_if<'a, F>(
+        &'a mut self,
+        range: Range<usize>,
+        check: &'a mut KeyCheck,
+        pred: F,
+    ) -> impl Iterator<Item = T> + 'a
+    where S: BuildHasher, F: FnMut(&mut T) -> bool + 'a {
+        ExtractIf::new(self, range, check, pred)
+    }
+
+    fn move_index(&mut self, from: usize, to: usize) {
+        let len = self.items.len();
+        assert!(from < len && to < len, "index out of bounds: the len is {len} but the index is {}", from.max(to));
+        if from == to {
+            return;
+        }
+        for bucket in self.table.iter_mut() {
+            let i = bucket.index;
+            if i == from {
+                bucket.index = to;
+            } else if from < to && from < i && i <= to {
+                bucket.index -= 1;
+            } else if to < from && to <= i && i < from {
+                bucket.index += 1;
+            }
+        }
+        if from < to {
+            self.items[from..=to].rotate_left(1);
+        } else {
+            self.items[to..=from].rotate_right(1);
+        }
+    }
+
+    fn swap_indices(&mut self, a: usize, b: usize)
+    where S: BuildHasher {
+        let hashes = [hash_item(&self.hasher, &self.items[a]), hash_item(&self.hasher, &self.items[b])];
+        if a == b {
+            return;
+        }
+        // Look both slots up before changing either, since rewriting one could
+        // make it match the other's probe
+        let indices = [a, b];
+        let swapped = match self.table.get_many_mut(hashes, |n, bucket| bucket.index == indices[n]) {
+            [Some(bucket_a), Some(bucket_b)] => {
+                bucket_a.index = b;
+                bucket_b.index = a;
+                true
+            },
+            _ => false,
+        };
+        if !swapped {
+            for bucket in self.table.iter_mut() {
+                if bucket.index == a {
+                    bucket.index = b;
+                } else if bucket.index == b {
+                    bucket.index = a;
+                }
+            }
+        }
+        self.items.swap(a, b);
+    }
+
+    fn reverse(&mut self) {
+        let len = self.items.len();
+        for bucket in self.table.iter_mut() {
+            bucket.index = len - 1 - bucket.index;
+        }
+        self.items.reverse();
+    }
+
+    fn sort_by<F>(&mut self, cmp: F, stable: bool)
+    where S: BuildHasher, F: FnMut(&T, &T) -> Ordering {
+        if stable {
+            self.items.sort_by(cmp);
+        } else {
+            self.items.sort_unstable_by(cmp);
+        }
+        self.reindex();
+    }
+
+    fn sort_keys_by<F>(&mut self, mut cmp: F)
+    where S: BuildHasher, F: FnMut(&T::Key, &T::Key) -> Ordering {
+        self.items.sort_unstable_by(|a, b| with_key(a, |a| with_key(b, |b| cmp(a, b))));
+        self.reindex();
+    }
+
+    #[cfg(feature = "rayon")]
+    fn par_iter(&self) -> rayon::slice::Iter<'_, T>
+    where T: Sync, T::Key: Sync {
+        self.items.par_iter()
+    }
+
+    #[cfg(feature = "rayon")]
+    fn par_iter_mut(&mut self) -> <Compact as Storage>::ParIterMut<'_, T, S>
+    where T: Send, T::Key: Send + Sync, S: Sync {
+        let CompactStore { items, table, hasher } = self;
+        rayon::iter::repeat_n((&*table, &*hasher), items.len()).zip(items.par_iter_mut())
+    }
+
+    #[cfg(feature = "rayon")]
+    fn into_par_iter(self) -> rayon::vec::IntoIter<T>
+    where T: Send, T::Key: Send {
+        self.items.into_par_iter()
+    }
+
+    #[cfg(feature = "rayon")]
+    fn par_keys(&self) -> <Compact as Storage>::ParKeys<'_, T>
+    where T: Sync, T::Key: Send + Sync {
+        self.items.par_iter().map(key_of as fn(&T) -> Cow<'_, T::Key>)
+    }
+
+    #[cfg(feature = "rayon")]
+    fn par_sort_by<F>(&mut self, cmp: F)
+    where T: Send, T::Key: Send, S: BuildHasher, F: Fn(&T, &T) -> Ordering + Sync {
+        self.items.par_sort_by(cmp);
+        self.reindex();
+    }
+}
+
+impl<T: Lookup> RawSlice<T, Compact> for [T] {
+    fn empty<'a>() -> &'a Self {
+        &[]
+    }
+
+    fn empty_mut<'a>() -> &'a mut Self {
+        &mut []
+    }
+
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+
+    fn get(&self, index: usize) -> Option<&T> {
+        <[T]>::get(self, index)
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        <[T]>::get_mut(self, index)
+    }
+
+    fn range(&self, range: Range<usize>) -> &Self {
+        &self[range]
+    }
+
+    fn range_mut(&mut self, range: Range<usize>) -> &mut Self {
+        &mut self[range]
+    }
+
+    fn split_at(&self, index: usize) -> (&Self, &Self) {
+        <[T]>::split_at(self, index)
+    }
+
+    fn split_at_mut(&mut self, index: usize) -> (&mut Self, &mut Self) {
+        <[T]>::split_at_mut(self, index)
+    }
+
+    fn iter(&self) -> slice::Iter<'_, T> {
+        <[T]>::iter(self)
+    }
+
+    fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+        <[T]>::iter_mut(self)
+    }
+
+    fn keys(&self) -> <Compact as Storage>::Keys<'_, T> {
+        <[T]>::iter(self).map(key_of as fn(&T) -> Cow<'_, T::Key>)
+    }
+
+    fn into_iter(self: Box<Self>) -> alloc::vec::IntoIter<T> {
+        self.into_vec().into_iter()
+    }
+
+    fn into_keys(self: Box<Self>) -> <Compact as Storage>::IntoKeys<T> {
+        self.into_vec().into_iter().map(into_key as fn(T) -> T::Key)
+    }
+
+    fn binary_search_by<F>(&self, f: F) -> Result<usize, usize>
+    where F: FnMut(&T) -> Ordering {
+        <[T]>::binary_search_by(self, f)
+    }
+
+    fn partition_point<P>(&self, pred: P) -> usize
+    where P: FnMut(&T) -> bool {
+        <[T]>::partition_point(self, pred)
+    }
+}
+
+/// The items of a `CompactStore`, each with the hash table to check its key
+/// against.
+pub struct SlotsMut<'a, T, S> {
+    iter: slice::IterMut<'a, T>,
+    table: &'a HashTable<Bucket>,
+    hasher: &'a S,
+}
+
+impl<'a, T, S> Iterator for SlotsMut<'a, T, S> {
+    type Item = ((&'a HashTable<Bucket>, &'a S), &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|item| ((self.table, self.hasher), item))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.iter.nth(n).map(|item| ((self.table, self.hasher), item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+impl<T, S> DoubleEndedIterator for SlotsMut<'_, T, S> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|item| ((self.table, self.hasher), item))
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.iter.nth_back(n).map(|item| ((self.table, self.hasher), item))
+    }
+}
+impl<T, S> ExactSizeIterator for SlotsMut<'_, T, S> {
+    fn len(&self) -> usize { self.iter.len() }
+}
+impl<T, S> FusedIterator for SlotsMut<'_, T, S> {}
+
+/// Takes the items after the start of the range out of the store while it is
+/// iterated, and refills the hash table once it is dropped.
+struct ExtractIf<'a, T: Lookup, S: BuildHasher, F> {
+    store: &'a mut CompactStore<T, S>,
+    check: &'a mut KeyCheck,
+    rest: alloc::vec::IntoIter<T>,
+    tail: Vec<T>,
+    pred: F,
+}
+
+impl<'a, T: Lookup, S: BuildHasher, F> ExtractIf<'a, T, S, F> {
+    fn new(store: &'a mut CompactStore<T, S>, range: Range<usize>, check: &'a mut KeyCheck, pred: F) -> Self {
+        let tail = store.items.split_off(range.end);
+        let rest = store.items.split_off(range.start).into_iter();
+        store.table.clear();
+        ExtractIf { store, check, rest, tail, pred }
+    }
+}
+
+impl<T: Lookup, S: BuildHasher, F> Iterator for ExtractIf<'_, T, S, F>
+where F: FnMut(&mut T) -> bool {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        for mut item in self.rest.by_ref() {
+            let hash = hash_item(&self.store.hasher, &item);
+            if (self.pred)(&mut item) {
+                return Some(item);
+            }
+            let index = self.store.items.len();
+            self.store.items.push(item);
+            // The table is refilled on drop, which re-keys changed items
+            let item = &self.store.items[index];
+            if self.check.policy.is_checked() && hash_item(&self.store.hasher, item) != hash {
+                self.check.item_changed(index, None, item);
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.rest.len()))
+    }
+}
+
+impl<T: Lookup, S: BuildHasher, F> Drop for ExtractIf<'_, T, S, F> {
+    fn drop(&mut self) {
+        self.store.items.extend(self.rest.by_ref());
+        self.store.items.append(&mut self.tail);
+        match self.store.reindex() {
+            // Leave a duplicate key for the next repair to report
+            Some(_) if panicking() => self.check.mark(..),
+            Some(index) => {
+                panic!("duplicate key {:?} after changing item keys", self.store.items[index].key());
+            },
+            None => {},
+        }
+    }
+}
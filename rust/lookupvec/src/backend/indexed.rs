@@ -0,0 +1,369 @@
+use crate::backend::Backend;
+use crate::backend::store::RawSlice;
+use crate::backend::store::Storage;
+use crate::backend::store::Store;
+use crate::core::Lookup;
+use crate::core::with_key;
+use crate::guard::KeyCheck;
+
+use indexmap::Equivalent;
+use indexmap::IndexMap;
+use indexmap::map;
+use indexmap::map::RawEntryApiV1;
+use indexmap::map::raw_entry_v1::RawEntryMut;
+#[cfg(feature = "rayon")]
+use indexmap::map::rayon as par_map;
+#[cfg(feature = "rayon")]
+use rayon::iter::IntoParallelIterator;
+#[cfg(feature = "rayon")]
+use rayon::iter::ParallelIterator;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::hash::BuildHasher;
+use core::hash::Hash;
+use core::iter;
+use core::ops::Range;
+use core::ops::RangeBounds;
+
+/// The default `Backend`: an `IndexMap` that keeps a copy of each item's key
+/// next to the item.
+///
+/// Since the old key is kept, any change to an item's key through a mutable
+/// borrow is noticed, and `keys()` can lend out the stored keys.
+pub enum Indexed {}
+
+impl Backend for Indexed {}
+
+/// The item's own key if it can lend it, otherwise the key it is stored under.
+fn current_key<'a, T: Lookup>((key, item): (&'a T::Key, &'a T)) -> &'a T::Key {
+    item.borrow_key().unwrap_or(key)
+}
+
+fn into_item<T: Lookup>((_, item): (T::Key, T)) -> T {
+    item
+}
+
+impl Storage for Indexed {
+    type Store<T: Lookup, S> = IndexMap<T::Key, T, S>;
+    type Slice<T: Lookup> = map::Slice<T::Key, T>;
+    type Slot<'a, T: Lookup + 'a, S: 'a> = &'a T::Key;
+    type Key<'a, T: Lookup + 'a> = &'a T::Key;
+
+    type Iter<'a, T: Lookup + 'a> = map::Values<'a, T::Key, T>;
+    type SliceIterMut<'a, T: Lookup + 'a> = map::ValuesMut<'a, T::Key, T>;
+    type IterMut<'a, T: Lookup + 'a, S: 'a> = map::IterMut<'a, T::Key, T>;
+    type IntoIter<T: Lookup> = map::IntoValues<T::Key, T>;
+    type Keys<'a, T: Lookup + 'a> = iter::Map<map::Iter<'a, T::Key, T>, fn((&'a T::Key, &'a T)) -> &'a T::Key>;
+    type IntoKeys<T: Lookup> = map::IntoKeys<T::Key, T>;
+    type Drain<'a, T: Lookup + 'a> = iter::Map<map::Drain<'a, T::Key, T>, fn((T::Key, T)) -> T>;
+
+    #[cfg(feature = "rayon")]
+    type ParIter<'a, T: Lookup + Sync + 'a> = par_map::ParValues<'a, T::Key, T>
+    where T::Key: Sync;
+    #[cfg(feature = "rayon")]
+    type ParIterMut<'a, T: Lookup + Send + 'a, S: Sync + 'a> = par_map::ParIterMut<'a, T::Key, T>
+    where T::Key: Send + Sync;
+    #[cfg(feature = "rayon")]
+    type IntoParIter<T: Lookup + Send> = rayon::iter::Map<par_map::IntoParIter<T::Key, T>, fn((T::Key, T)) -> T>
+    where T::Key: Send;
+    #[cfg(feature = "rayon")]
+    type ParKeys<'a, T: Lookup + Sync + 'a> = rayon::iter::Map<par_map::ParIter<'a, T::Key, T>, fn((&'a T::Key, &'a T)) -> &'a T::Key>
+    where T::Key: Send + Sync;
+
+    fn slot_holds<T: Lookup, S: BuildHasher>(key: &T::Key, _index: usize, item: &T) -> bool {
+        with_key(item, |k| k == key)
+    }
+
+    fn slot_key<'a, T: Lookup + 'a, S: 'a>(key: &'a T::Key) -> Option<&'a T::Key> {
+        Some(key)
+    }
+}
+
+impl<T: Lookup, S> Store<T, S, Indexed> for IndexMap<T::Key, T, S> {
+    fn with_capacity_and_hasher(n: usize, hasher: S) -> Self {
+        IndexMap::with_capacity_and_hasher(n, hasher)
+    }
+
+    fn clone_store(&self) -> Self
+    where T: Clone, S: Clone {
+        self.clone()
+    }
+
+    fn len(&self) -> usize {
+        IndexMap::len(self)
+    }
+
+    fn capacity(&self) -> usize {
+        IndexMap::capacity(self)
+    }
+
+    fn hasher(&self) -> &S {
+        IndexMap::hasher(self)
+    }
+
+    fn as_slice(&self) -> &map::Slice<T::Key, T> {
+        IndexMap::as_slice(self)
+    }
+
+    fn as_mut_slice(&mut self) -> &mut map::Slice<T::Key, T> {
+        IndexMap::as_mut_slice(self)
+    }
+
+    fn into_boxed_slice(self) -> Box<map::Slice<T::Key, T>> {
+        IndexMap::into_boxed_slice(self)
+    }
+
+    fn into_iter(self) -> map::IntoValues<T::Key, T> {
+        self.into_values()
+    }
+
+    fn into_keys(self) -> map::IntoKeys<T::Key, T> {
+        IndexMap::into_keys(self)
+    }
+
+    fn iter_mut(&mut self) -> map::IterMut<'_, T::Key, T> {
+        IndexMap::iter_mut(self)
+    }
+
+    fn stored_key(&self, index: usize) -> Option<&T::Key> {
+        self.get_index(index).map(|(key, _)| key)
+    }
+
+    fn slot_hash(&self, _index: usize) -> Option<u64> {
+        None
+    }
+
+    fn is_stored(&self, index: usize) -> bool {
+        let (key, item) = self.get_index(index).unwrap();
+        with_key(item, |k| k == key)
+    }
+
+    fn get_index_of<Q>(&self, key: &Q) -> Option<usize>
+    where S: BuildHasher, Q: ?Sized + Hash + Equivalent<T::Key> {
+        IndexMap::get_index_of(self, key)
+    }
+
+    fn find_hashed(&self, hash: u64, key: &T::Key) -> Option<usize> {
+        self.raw_entry_v1().index_from_hash(hash, |k| k == key)
+    }
+
+    fn push_unique(&mut self, hash: u64, key: Option<T::Key>, value: T) -> Option<T::Key> {
+        let key = key.unwrap_or_else(|| value.key());
+        match self.raw_entry_mut_v1().from_hash(hash, |_| false) {
+            RawEntryMut::Vacant(entry) => entry.insert_hashed_nocheck(hash, key, value),
+            RawEntryMut::Occupied(_) => unreachable!(),
+        };
+        None
+    }
+
+    fn rekey_index(&mut self, index: usize, key: T::Key, _hash: Option<u64>)
+    where S: BuildHasher {
+        // Swap the item out to the end, re-insert it under its new key, and
+        // swap it back into place
+        let (_, item) = IndexMap::swap_remove_index(self, index).unwrap();
+        let (last, _) = self.insert_full(key, item);
+        IndexMap::swap_indices(self, index, last);
+    }
+
+    fn rebuild(&mut self, keys: Vec<(u64, T::Key)>) {
+        let items: Vec<T> = IndexMap::drain(self, ..).map(|(_, item)| item).collect();
+        for ((hash, key), item) in keys.into_iter().zip(items) {
+            match self.raw_entry_mut_v1().from_hash(hash, |_| false) {
+                RawEntryMut::Vacant(entry) => entry.insert_hashed_nocheck(hash, key, item),
+                RawEntryMut::Occupied(_) => unreachable!(),
+            };
+        }
+    }
+
+    fn truncate(&mut self, len: usize) {
+        IndexMap::truncate(self, len)
+    }
+
+    fn clear(&mut self) {
+        IndexMap::clear(self)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        IndexMap::reserve(self, additional)
+    }
+
+    fn reserve_exact(&mut self, additional: usize) {
+        IndexMap::reserve_exact(self, additional)
+    }
+
+    fn shrink_to(&mut self, min_capacity: usize) {
+        IndexMap::shrink_to(self, min_capacity)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        IndexMap::shrink_to_fit(self)
+    }
+
+    fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> <Indexed as Storage>::Drain<'_, T> {
+        IndexMap::drain(self, range).map(into_item as fn((T::Key, T)) -> T)
+    }
+
+    fn split_off(&mut self, at: usize) -> Self
+    where S: Clone {
+        IndexMap::split_off(self, at)
+    }
+
+    fn shift_remove_index(&mut self, index: usize) -> Option<T> {
+        IndexMap::shift_remove_index(self, index).map(into_item)
+    }
+
+    fn swap_remove_index(&mut self, index: usize) -> Option<T> {
+        IndexMap::swap_remove_index(self, index).map(into_item)
+    }
+
+    fn retain<F>(&mut self, mut keep: F)
+    where F: FnMut(&mut T) -> bool {
+        IndexMap::retain(self, |_, item| keep(item))
+    }
+
+    #[allow(private_interfaces)]
+    fn extract_if<'a, F>(
+        &'a mut self,
+        range: Range<usize>,
+        check: &'a mut KeyCheck,
+        mut pred: F,
+    ) -> impl Iterator<Item = T> + 'a
+    where S: BuildHasher, F: FnMut(&mut T) -> bool + 'a {
+        let mut index = range.start;
+        IndexMap::extract_if(self, range, move |key, item| {
+            let extract = pred(item);
+            if !extract {
+                check.check_slot::<Indexed, T, S>(index, key, item);
+                index += 1;
+            }
+            extract
+        })
+        .map(into_item)
+    }
+
+    fn move_index(&mut self, from: usize, to: usize) {
+        IndexMap::move_index(self, from, to)
+    }
+
+    fn swap_indices(&mut self, a: usize, b: usize) {
+        IndexMap::swap_indices(self, a, b)
+    }
+
+    fn reverse(&mut self) {
+        IndexMap::reverse(self)
+    }
+
+    fn sort_by<F>(&mut self, mut cmp: F, stable: bool)
+    where F: FnMut(&T, &T) -> Ordering {
+        if stable {
+            IndexMap::sort_by(self, |_, v1, _, v2| cmp(v1, v2))
+        } else {
+            IndexMap::sort_unstable_by(self, |_, v1, _, v2| cmp(v1, v2))
+        }
+    }
+
+    fn sort_keys_by<F>(&mut self, mut cmp: F)
+    where F: FnMut(&T::Key, &T::Key) -> Ordering {
+        IndexMap::sort_unstable_by(self, |k1, _, k2, _| cmp(k1, k2))
+    }
+
+    #[cfg(feature = "rayon")]
+    fn par_iter(&self) -> par_map::ParValues<'_, T::Key, T>
+    where T: Sync, T::Key: Sync {
+        self.par_values()
+    }
+
+    #[cfg(feature = "rayon")]
+    fn par_iter_mut(&mut self) -> par_map::ParIterMut<'_, T::Key, T>
+    where T: Send, T::Key: Send + Sync {
+        IntoParallelIterator::into_par_iter(self)
+    }
+
+    #[cfg(feature = "rayon")]
+    fn into_par_iter(self) -> <Indexed as Storage>::IntoParIter<T>
+    where T: Send, T::Key: Send {
+        IntoParallelIterator::into_par_iter(self).map(into_item as fn((T::Key, T)) -> T)
+    }
+
+    #[cfg(feature = "rayon")]
+    fn par_keys(&self) -> <Indexed as Storage>::ParKeys<'_, T>
+    where T: Sync, T::Key: Send + Sync {
+        IntoParallelIterator::into_par_iter(self).map(current_key as fn(_) -> _)
+    }
+
+    #[cfg(feature = "rayon")]
+    fn par_sort_by<F>(&mut self, cmp: F)
+    where T: Send, T::Key: Send, F: Fn(&T, &T) -> Ordering + Sync {
+        IndexMap::par_sort_by(self, |_, v1, _, v2| cmp(v1, v2))
+    }
+}
+
+impl<T: Lookup> RawSlice<T, Indexed> for map::Slice<T::Key, T> {
+    fn empty<'a>() -> &'a Self {
+        map::Slice::new()
+    }
+
+    fn empty_mut<'a>() -> &'a mut Self {
+        map::Slice::new_mut()
+    }
+
+    fn len(&self) -> usize {
+        map::Slice::len(self)
+    }
+
+    fn get(&self, index: usize) -> Option<&T> {
+        self.get_index(index).map(|(_, item)| item)
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.get_index_mut(index).map(|(_, item)| item)
+    }
+
+    fn range(&self, range: Range<usize>) -> &Self {
+        &self[range]
+    }
+
+    fn range_mut(&mut self, range: Range<usize>) -> &mut Self {
+        &mut self[range]
+    }
+
+    fn split_at(&self, index: usize) -> (&Self, &Self) {
+        map::Slice::split_at(self, index)
+    }
+
+    fn split_at_mut(&mut self, index: usize) -> (&mut Self, &mut Self) {
+        map::Slice::split_at_mut(self, index)
+    }
+
+    fn iter(&self) -> map::Values<'_, T::Key, T> {
+        self.values()
+    }
+
+    fn iter_mut(&mut self) -> map::ValuesMut<'_, T::Key, T> {
+        self.values_mut()
+    }
+
+    fn keys(&self) -> <Indexed as Storage>::Keys<'_, T> {
+        map::Slice::iter(self).map(current_key as fn(_) -> _)
+    }
+
+    fn into_iter(self: Box<Self>) -> map::IntoValues<T::Key, T> {
+        map::Slice::into_values(self)
+    }
+
+    fn into_keys(self: Box<Self>) -> map::IntoKeys<T::Key, T> {
+        map::Slice::into_keys(self)
+    }
+
+    fn binary_search_by<F>(&self, mut f: F) -> Result<usize, usize>
+    where F: FnMut(&T) -> Ordering {
+        map::Slice::binary_search_by(self, |_, item| f(item))
+    }
+
+    fn partition_point<P>(&self, mut pred: P) -> usize
+    where P: FnMut(&T) -> bool {
+        map::Slice::partition_point(self, |_, item| pred(item))
+    }
+}
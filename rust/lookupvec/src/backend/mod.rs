@@ -0,0 +1,606 @@
+//! Storage backends for `LookupVec`.
+//!
+//! `LookupVec<T, S, B>` keeps its items in a `B::Store`, which pairs them with
+//! a hash table from key to index. The API is the same for every backend;
+//! they differ in what they keep in memory:
+//!
+//! - `Indexed`, the default, is an `IndexMap` that keeps a copy of each key
+//!   next to its item. Probes compare against the stored keys, and a key
+//!   changed through a mutable borrow is reported along with its old value.
+//! - `Compact`, behind the `compact` feature, keeps the items in a plain
+//!   `Vec<T>` plus a hash table of their indices and hashes, and recomputes
+//!   keys from the items whenever it needs them. That halves the key memory
+//!   for items with large keys, at the cost of calling `Lookup::key()` on
+//!   each probe.
+//!
+//! `Backend` is sealed: the operations it provides are an implementation
+//! detail of `LookupVec`.
+
+#[cfg(feature = "compact")]
+mod compact;
+mod indexed;
+pub(crate) mod store;
+
+#[cfg(feature = "compact")]
+pub use compact::Compact;
+#[cfg(feature = "compact")]
+pub use compact::CompactLookupVec;
+pub use indexed::Indexed;
+
+/// How a `LookupVec` stores its items and finds them by key. See the module
+/// docs for the choices.
+pub trait Backend: store::Storage {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use lookupvec_derive::Lookup;
+    use crate::duplicate::DuplicatePolicy;
+    use crate::entry::Entry;
+    use crate::guard::KeyPolicy;
+    use crate::merge::Merge;
+    use crate::merge::MergePosition;
+    use crate::test_util::TestItem;
+    use crate::test_util::item;
+    use crate::vec::LookupVec;
+
+    use ahash::random_state::RandomState;
+
+    // As of 7/2025 alloc::prelude is nightly-only
+    use alloc::borrow::ToOwned;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use core::hash::BuildHasherDefault;
+    use core::hash::Hasher;
+
+    type TestVec<B> = LookupVec<TestItem, RandomState, B>;
+
+    /// Items `"a"` to `"d"`, with values 1 to 4.
+    fn test_vec<B: Backend>() -> TestVec<B> {
+        crate::test_util::test_vec().into_iter().collect()
+    }
+
+    /// Also checks that every item is found under its current key.
+    macro_rules! assert_keys_eq {
+        ($vec:expr, $($key:expr),*) => {
+            crate::test_util::assert_keys_eq!($vec, $($key),*);
+            assert!($vec.validate().is_empty());
+        };
+    }
+
+    /// Runs each of the generic tests named with every backend.
+    macro_rules! backend_tests {
+        ($($(#[$attr:meta])* $name:ident,)*) => {
+            mod indexed {
+                $(
+                    #[test]
+                    $(#[$attr])*
+                    fn $name() {
+                        super::$name::<crate::backend::Indexed>();
+                    }
+                )*
+            }
+
+            #[cfg(feature = "compact")]
+            mod compact {
+                $(
+                    #[test]
+                    $(#[$attr])*
+                    fn $name() {
+                        super::$name::<crate::backend::Compact>();
+                    }
+                )*
+            }
+        };
+    }
+
+    backend_tests! {
+        test_push_and_get,
+        test_insert,
+        test_remove,
+        test_reorder,
+        test_drain_split_truncate,
+        test_append,
+        test_get_mut_rekeys,
+        #[should_panic(expected = "changed")]
+        test_get_mut_panic_policy,
+        #[should_panic(expected = "duplicate key")]
+        test_rekey_duplicate_panics,
+        test_iter_mut_rekeys,
+        test_index_mut_rekeys,
+        test_into_iter,
+        test_swap_indices_many_items,
+        test_entry,
+        test_indexed_entries,
+        test_entry_rekeys,
+        test_slices,
+        #[should_panic(expected = "changed")]
+        test_get_range_mut_panic_policy,
+        #[should_panic(expected = "changed")]
+        test_iter_mut_panic_policy,
+        test_duplicate_policy,
+        test_insert_duplicate_policy,
+        #[should_panic(expected = "duplicate key \"b\" of item at index 1")]
+        test_insert_duplicate_policy_error_panics,
+        #[should_panic(expected = "duplicate key \"b\" of item at index 1")]
+        test_entry_duplicate_policy_error_panics,
+        test_retain_mut,
+        #[should_panic(expected = "changed")]
+        test_retain_mut_panic_policy,
+        test_extract_if,
+        #[should_panic(expected = "changed")]
+        test_extract_if_panic_policy,
+        test_set_operations_and_eq,
+        test_merge_with,
+        #[cfg(feature = "rayon")]
+        test_rayon,
+    }
+
+    fn test_push_and_get<B: Backend>() {
+        let mut vec = TestVec::<B>::new();
+        assert!(vec.is_empty());
+        assert_eq!(vec.push(item("a", 1)), None);
+        assert_eq!(vec.push(item("b", 2)), None);
+        assert_eq!(vec.push_full(item("a", 10)), (0, Some(item("a", 1))));
+
+        assert_eq!(vec.len(), 2);
+        assert_eq!(vec.get("a").unwrap().value, 10);
+        assert_eq!(vec[1].value, 2);
+        assert!(vec.contains(&item("b", 0)));
+        assert!(!vec.contains_key("c"));
+        assert_keys_eq!(vec, "a", "b");
+    }
+
+    fn test_insert<B: Backend>() {
+        let mut vec = test_vec::<B>();
+        assert_eq!(vec.insert(1, item("e", 5)), (1, None));
+        assert_keys_eq!(vec, "a", "e", "b", "c", "d");
+
+        assert_eq!(vec.insert(4, item("a", 10)), (3, Some(item("a", 1))));
+        assert_keys_eq!(vec, "e", "b", "c", "a", "d");
+
+        assert_eq!(vec.shift_insert(0, item("d", 40)), Some(item("d", 4)));
+        assert_keys_eq!(vec, "d", "e", "b", "c", "a");
+    }
+
+    fn test_remove<B: Backend>() {
+        let mut vec = test_vec::<B>();
+        assert_eq!(vec.shift_remove("b"), Some(item("b", 2)));
+        assert_keys_eq!(vec, "a", "c", "d");
+
+        assert_eq!(vec.swap_remove("a"), Some(item("a", 1)));
+        assert_keys_eq!(vec, "d", "c");
+
+        assert_eq!(vec.pop(), Some(item("c", 3)));
+        assert_keys_eq!(vec, "d");
+        assert_eq!(vec.swap_remove("x"), None);
+    }
+
+    fn test_reorder<B: Backend>() {
+        let mut vec = test_vec::<B>();
+        vec.move_index(0, 2);
+        assert_keys_eq!(vec, "b", "c", "a", "d");
+
+        vec.move_index(3, 1);
+        assert_keys_eq!(vec, "b", "d", "c", "a");
+
+        vec.swap_indices(0, 3);
+        assert_keys_eq!(vec, "a", "d", "c", "b");
+
+        vec.reverse();
+        assert_keys_eq!(vec, "b", "c", "d", "a");
+
+        vec.sort();
+        assert_keys_eq!(vec, "a", "b", "c", "d");
+
+        vec.sort_by(|a, b| b.value.cmp(&a.value));
+        assert_keys_eq!(vec, "d", "c", "b", "a");
+    }
+
+    fn test_drain_split_truncate<B: Backend>() {
+        let mut vec = test_vec::<B>();
+        let drained: Vec<TestItem> = vec.drain(1..3).collect();
+        assert_eq!(drained, vec![item("b", 2), item("c", 3)]);
+        assert_keys_eq!(vec, "a", "d");
+
+        let mut vec = test_vec::<B>();
+        let other = vec.split_off(1);
+        assert_keys_eq!(vec, "a");
+        assert_keys_eq!(other, "b", "c", "d");
+
+        let mut vec = test_vec::<B>();
+        vec.truncate(2);
+        assert_keys_eq!(vec, "a", "b");
+        assert!(vec.get("c").is_none());
+
+        let mut vec = test_vec::<B>();
+        vec.retain(|item| item.value % 2 == 0);
+        assert_keys_eq!(vec, "b", "d");
+    }
+
+    fn test_append<B: Backend>() {
+        let mut vec = test_vec::<B>();
+        let mut other: TestVec<B> = [item("a", 10), item("e", 5)].into_iter().collect();
+        vec.append(&mut other);
+        assert!(other.is_empty());
+        assert_keys_eq!(vec, "a", "b", "c", "d", "e");
+        assert_eq!(vec[0].value, 10);
+    }
+
+    fn test_get_mut_rekeys<B: Backend>() {
+        let mut vec = test_vec::<B>();
+        vec.get_mut("b").unwrap().id = "x".to_owned();
+        vec.get_index_mut(2).unwrap().value = 30;
+        assert_keys_eq!(vec, "a", "x", "c", "d");
+        assert!(vec.get("b").is_none());
+        assert_eq!(vec.get("c").unwrap().value, 30);
+    }
+
+    fn test_get_mut_panic_policy<B: Backend>() {
+        let mut vec = test_vec::<B>().with_key_policy(KeyPolicy::Panic);
+        vec.get_mut("b").unwrap().id = "x".to_owned();
+    }
+
+    fn test_rekey_duplicate_panics<B: Backend>() {
+        let mut vec = test_vec::<B>();
+        vec.get_mut("b").unwrap().id = "a".to_owned();
+    }
+
+    fn test_iter_mut_rekeys<B: Backend>() {
+        let mut vec = test_vec::<B>();
+        for mut item in vec.iter_mut() {
+            item.id = item.id.to_uppercase();
+        }
+        assert_eq!(vec.get("C").unwrap().value, 3);
+        assert!(vec.get("c").is_none());
+
+        vec.push(item("e", 5));
+        assert_keys_eq!(vec, "A", "B", "C", "D", "e");
+    }
+
+    fn test_index_mut_rekeys<B: Backend>() {
+        let mut vec = test_vec::<B>();
+        let slice = &mut vec[0..2];
+        slice[0].id = "b".to_owned();
+        slice[1].id = "a".to_owned();
+        assert_eq!(vec.get("a").unwrap().value, 2);
+        assert_eq!(vec.rekey_all(), 2);
+        assert_keys_eq!(vec, "b", "a", "c", "d");
+    }
+
+    fn test_into_iter<B: Backend>() {
+        let items: Vec<TestItem> = test_vec::<B>().into_iter().collect();
+        assert_eq!(items.len(), 4);
+        let keys: Vec<String> = test_vec::<B>().into_keys().collect();
+        assert_eq!(keys, vec!["a", "b", "c", "d"]);
+        let sorted: Vec<TestItem> = test_vec::<B>().sorted_by(|a, b| b.id.cmp(&a.id)).collect();
+        assert_eq!(sorted[0].id, "d");
+    }
+
+    fn test_swap_indices_many_items<B: Backend>() {
+        #[derive(Debug, Clone, PartialEq, Lookup)]
+        struct Id(#[lookup_key] u32);
+
+        // Gives every key the same tag and one of 16 home buckets, so that
+        // items are pushed past each other's slots
+        #[derive(Default)]
+        struct CollidingHasher(u64);
+        impl Hasher for CollidingHasher {
+            fn finish(&self) -> u64 {
+                self.0 % 16
+            }
+            fn write(&mut self, bytes: &[u8]) {
+                bytes.iter().for_each(|&b| self.0 = self.0.wrapping_mul(31).wrapping_add(u64::from(b)));
+            }
+        }
+
+        let mut vec: LookupVec<Id, BuildHasherDefault<CollidingHasher>, B> = (0..200).map(Id).collect();
+        let mut model: Vec<u32> = (0..200).collect();
+        let mut seed = 0x2545_f491_u32;
+        let mut next = |n: usize| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as usize % n
+        };
+        for _ in 0..2000 {
+            let (a, b) = (next(200), next(200));
+            vec.swap_indices(a, b);
+            model.swap(a, b);
+        }
+        assert!(vec.validate().is_empty());
+        for (index, id) in model.iter().enumerate() {
+            assert_eq!(vec.get_index_of(id), Some(index));
+        }
+    }
+
+    fn test_entry<B: Backend>() {
+        let mut vec = test_vec::<B>();
+        vec.entry("a".to_owned()).and_modify(|item| item.value += 1).or_insert_with(|| item("a", 0));
+        vec.entry("e".to_owned()).or_insert_with_key(|key| item(key, 5)).value += 1;
+        assert_eq!(vec.entry("b".to_owned()).index(), 1);
+        assert_eq!(vec.entry("f".to_owned()).index(), 5);
+        assert_eq!(vec.entry("f".to_owned()).key(), "f");
+        assert_eq!(vec[0].value, 2);
+        assert_keys_eq!(vec, "a", "b", "c", "d", "e");
+        assert_eq!(vec[4].value, 6);
+
+        match vec.entry("c".to_owned()) {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.insert(item("c", 30)), item("c", 3));
+                entry.move_index(0);
+            },
+            Entry::Vacant(_) => panic!("expected occupied entry"),
+        }
+        assert_keys_eq!(vec, "c", "a", "b", "d", "e");
+
+        match vec.entry("f".to_owned()) {
+            Entry::Occupied(_) => panic!("expected vacant entry"),
+            Entry::Vacant(entry) => {
+                entry.shift_insert(1, item("f", 6));
+            },
+        }
+        assert_keys_eq!(vec, "c", "f", "a", "b", "d", "e");
+
+        if let Entry::Occupied(entry) = vec.entry("a".to_owned()) {
+            assert_eq!(entry.shift_remove(), item("a", 2));
+        }
+        if let Entry::Occupied(entry) = vec.entry("c".to_owned()) {
+            entry.swap_indices(4);
+        }
+        assert_keys_eq!(vec, "e", "f", "b", "d", "c");
+
+        let entry = vec.entry("g".to_owned()).insert_entry(item("g", 7));
+        assert_eq!(entry.index(), 5);
+        assert_keys_eq!(vec, "e", "f", "b", "d", "c", "g");
+    }
+
+    fn test_indexed_entries<B: Backend>() {
+        let mut vec = test_vec::<B>();
+        vec.first_entry().unwrap().get_mut().value = 10;
+        vec.last_entry().unwrap().get_mut().value = 40;
+        let entry = vec.get_index_entry(1).unwrap();
+        assert_eq!(entry.key().as_str(), "b");
+        assert_eq!(entry.swap_remove(), item("b", 2));
+        assert!(vec.get_index_entry(3).is_none());
+        assert_keys_eq!(vec, "a", "d", "c");
+        assert_eq!(vec[0].value, 10);
+        assert_eq!(vec[1].value, 40);
+
+        let mut empty = TestVec::<B>::new();
+        assert!(empty.first_entry().is_none());
+        assert!(empty.last_entry().is_none());
+    }
+
+    fn test_entry_rekeys<B: Backend>() {
+        let mut vec = test_vec::<B>();
+        vec.entry("a".to_owned()).and_modify(|item| item.id = "x".to_owned());
+        vec.push(item("a", 10));
+        assert_keys_eq!(vec, "x", "b", "c", "d", "a");
+
+        // A changed key is dropped with the item, or moved with it
+        vec.get_index_entry(1).unwrap().get_mut().id = "y".to_owned();
+        vec.get_index_entry(1).unwrap().shift_remove();
+        vec.get_index_entry(0).unwrap().into_mut().id = "z".to_owned();
+        vec.get_index_entry(0).unwrap().move_index(3);
+        assert_keys_eq!(vec, "c", "d", "a", "z");
+    }
+
+    fn test_slices<B: Backend>() {
+        let mut vec = test_vec::<B>();
+        assert_eq!(vec.as_slice().len(), 4);
+        assert_eq!(vec[1..3].first(), Some(&item("b", 2)));
+        assert_eq!(vec.get_range(2..).unwrap().get("d"), Some(&item("d", 4)));
+        assert!(vec.get_range(3..5).is_none());
+
+        vec[1..][1].id = "x".to_owned();
+        assert_eq!(vec.get("x").unwrap().value, 3);
+        assert!(vec.get("c").is_none());
+        assert_eq!(vec.rekey_all(), 1);
+        assert_keys_eq!(vec, "a", "b", "x", "d");
+
+        vec.get_range_mut(..2).unwrap()[0].id = "y".to_owned();
+        assert_keys_eq!(vec, "y", "b", "x", "d");
+        vec.as_mut_slice().iter_mut().for_each(|item| item.value *= 10);
+        assert_eq!(vec.get("d").unwrap().value, 40);
+
+        let slice = vec.into_boxed_slice();
+        assert_eq!(slice.keys().map(|key| key.as_str().to_owned()).collect::<Vec<_>>(), vec!["y", "b", "x", "d"]);
+        assert_eq!(slice.into_iter().map(|item| item.value).sum::<i32>(), 100);
+    }
+
+    fn test_get_range_mut_panic_policy<B: Backend>() {
+        let mut vec = test_vec::<B>().with_key_policy(KeyPolicy::Panic);
+        vec.get_range_mut(..2).unwrap()[0].id = "x".to_owned();
+    }
+
+    fn test_iter_mut_panic_policy<B: Backend>() {
+        let mut vec = test_vec::<B>().with_key_policy(KeyPolicy::Panic);
+        for mut item in vec.iter_mut() {
+            item.id = item.id.to_uppercase();
+        }
+    }
+
+    fn test_duplicate_policy<B: Backend>() {
+        let mut vec = test_vec::<B>().with_duplicate_policy(DuplicatePolicy::KeepFirst);
+        assert_eq!(vec.push_full(item("a", 10)), (0, Some(item("a", 10))));
+        assert_eq!(vec[0].value, 1);
+
+        vec.set_duplicate_policy(DuplicatePolicy::MoveToEnd);
+        assert_eq!(vec.push_full(item("a", 10)), (3, Some(item("a", 1))));
+        assert_keys_eq!(vec, "b", "c", "d", "a");
+
+        let err = vec.push_with(item("b", 20), DuplicatePolicy::Error).unwrap_err();
+        assert_eq!((err.index(), err.item()), (0, &item("b", 20)));
+        assert_eq!(vec.try_push(item("e", 5)).unwrap(), 4);
+
+        let err = vec.try_extend([item("f", 6), item("c", 30), item("f", 60)]).unwrap_err();
+        assert_eq!((err.index(), err.item()), (1, &item("c", 30)));
+        assert_eq!(vec.len(), 5);
+        vec.try_extend([item("f", 6), item("g", 7)]).unwrap();
+        assert_keys_eq!(vec, "b", "c", "d", "a", "e", "f", "g");
+
+        let err = TestVec::<B>::try_from_iter([item("a", 1), item("a", 2), item("a", 3)]).unwrap_err();
+        assert_eq!(err.index(), 0);
+        assert_eq!(err.keys(), &["a".to_owned()]);
+    }
+
+    fn test_insert_duplicate_policy<B: Backend>() {
+        let mut vec = test_vec::<B>();
+        assert_eq!(vec.insert(4, item("a", 10)), (3, Some(item("a", 1))));
+        assert_eq!(vec.shift_insert(0, item("d", 40)), Some(item("d", 4)));
+        assert_keys_eq!(vec, "d", "b", "c", "a");
+        assert_eq!(vec.entry("b".to_owned()).insert_entry(item("b", 20)).index(), 1);
+        assert_eq!(vec[1].value, 20);
+
+        let mut vec = test_vec::<B>().with_duplicate_policy(DuplicatePolicy::KeepFirst);
+        assert_eq!(vec.insert(4, item("a", 10)), (0, Some(item("a", 10))));
+        assert_eq!(vec.shift_insert(0, item("d", 40)), Some(item("d", 40)));
+        assert_eq!(vec.entry("b".to_owned()).insert_entry(item("b", 20)).get().value, 2);
+        assert_eq!(vec, test_vec::<B>());
+
+        let mut vec = test_vec::<B>().with_duplicate_policy(DuplicatePolicy::MoveToEnd);
+        assert_eq!(vec.insert(0, item("c", 30)), (0, Some(item("c", 3))));
+        assert_eq!(vec.entry("a".to_owned()).insert_entry(item("a", 10)).index(), 3);
+        assert_keys_eq!(vec, "c", "b", "d", "a");
+        assert_eq!(vec.get("a").unwrap().value, 10);
+    }
+
+    fn test_insert_duplicate_policy_error_panics<B: Backend>() {
+        let mut vec = test_vec::<B>().with_duplicate_policy(DuplicatePolicy::Error);
+        vec.shift_insert(0, item("b", 20));
+    }
+
+    fn test_entry_duplicate_policy_error_panics<B: Backend>() {
+        let mut vec = test_vec::<B>().with_duplicate_policy(DuplicatePolicy::Error);
+        vec.entry("b".to_owned()).insert_entry(item("b", 20));
+    }
+
+    fn test_retain_mut<B: Backend>() {
+        let mut vec = test_vec::<B>();
+        vec.retain_mut(|item| {
+            item.value *= 10;
+            if item.id == "c" {
+                item.id = "x".to_owned();
+            }
+            item.id != "b"
+        });
+        assert_eq!(vec.iter().map(|item| item.value).collect::<Vec<_>>(), vec![10, 30, 40]);
+        assert_keys_eq!(vec, "a", "x", "d");
+
+        vec.retain_keys(&["d".to_owned(), "a".to_owned()]);
+        assert_keys_eq!(vec, "a", "d");
+    }
+
+    fn test_retain_mut_panic_policy<B: Backend>() {
+        let mut vec = test_vec::<B>().with_key_policy(KeyPolicy::Panic);
+        vec.retain_mut(|item| {
+            item.id = item.id.to_uppercase();
+            true
+        });
+    }
+
+    fn test_extract_if<B: Backend>() {
+        let mut vec = test_vec::<B>();
+        let extracted: Vec<TestItem> = vec.extract_if(1.., |item| item.value % 2 == 0).collect();
+        assert_eq!(extracted, vec![item("b", 2), item("d", 4)]);
+        assert_keys_eq!(vec, "a", "c");
+
+        let mut vec = test_vec::<B>();
+        let extracted: Vec<TestItem> = vec.extract_if(.., |item| {
+            if item.id == "b" {
+                item.id = "x".to_owned();
+            }
+            item.value == 1
+        }).collect();
+        assert_eq!(extracted, vec![item("a", 1)]);
+        assert_eq!(vec.get("x").unwrap().value, 2);
+        vec.push(item("e", 5));
+        assert_keys_eq!(vec, "x", "c", "d", "e");
+
+        // Dropping the iterator early keeps the rest
+        let mut vec = test_vec::<B>();
+        assert_eq!(vec.extract_if(..3, |_| true).next(), Some(item("a", 1)));
+        assert_keys_eq!(vec, "b", "c", "d");
+    }
+
+    fn test_extract_if_panic_policy<B: Backend>() {
+        let mut vec = test_vec::<B>().with_key_policy(KeyPolicy::Panic);
+        vec.extract_if(.., |item| {
+            item.id = item.id.to_uppercase();
+            false
+        }).for_each(drop);
+    }
+
+    fn test_set_operations_and_eq<B: Backend>() {
+        let vec = |items: &[TestItem]| items.iter().cloned().collect::<TestVec<B>>();
+        let a = test_vec::<B>();
+        let b = vec(&[item("e", 5), item("c", 30), item("a", 10)]);
+        let ids = |iter: &mut dyn Iterator<Item = &TestItem>| iter.map(|item| item.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&mut a.difference(&b)), vec!["b", "d"]);
+        assert_eq!(ids(&mut a.intersection(&b)), vec!["a", "c"]);
+        assert_eq!(ids(&mut a.symmetric_difference(&b)), vec!["b", "d", "e"]);
+        assert_eq!(ids(&mut a.union(&b).rev()), vec!["e", "d", "c", "b", "a"]);
+        assert!(!a.is_disjoint(&b));
+        assert!(vec(&[item("c", 0)]).is_subset(&a));
+        assert!(a.is_superset(&vec(&[item("b", 0)])));
+
+        let mut reversed = a.clone();
+        reversed.reverse();
+        assert_eq!(a, a.clone());
+        assert_ne!(a, reversed);
+        assert!(a.eq_unordered(&reversed));
+        assert_eq!(a, crate::test_util::test_vec());
+    }
+
+    fn test_merge_with<B: Backend>() {
+        let mut vec = test_vec::<B>();
+        vec.merge_with([item("b", 20), item("e", 5)], |existing: &mut TestItem, incoming: TestItem| {
+            existing.value += incoming.value;
+        }).unwrap();
+        assert_keys_eq!(vec, "a", "b", "c", "d", "e");
+        assert_eq!(vec[1].value, 22);
+
+        vec.merge_with_position([item("a", 10)], MergePosition::Incoming, Merge::KeepRight).unwrap();
+        assert_keys_eq!(vec, "b", "c", "d", "e", "a");
+
+        let err = vec.merge_with([item("f", 6), item("c", 30)], Merge::Error).unwrap_err();
+        assert_eq!(err.index(), 1);
+        assert_keys_eq!(vec, "b", "c", "d", "e", "a");
+
+        vec.merge_with([item("f", 6), item("f", 60)], Merge::KeepLeft).unwrap();
+        let err = vec.merge_with([item("g", 7), item("g", 70)], Merge::Error).unwrap_err();
+        assert_eq!(err.index(), 6);
+        assert_eq!(err.into_item(), item("g", 70));
+        assert_keys_eq!(vec, "b", "c", "d", "e", "a", "f");
+    }
+
+    #[cfg(feature = "rayon")]
+    #[derive(Debug, PartialEq, Lookup)]
+    struct Id {
+        #[lookup_key]
+        id: u64,
+    }
+
+    #[cfg(feature = "rayon")]
+    fn test_rayon<B: Backend>()
+    where for<'a> B::Slot<'a, Id, RandomState>: Send, for<'a> B::Key<'a, Id>: Send {
+        use ::rayon::prelude::*;
+
+        let mut vec: LookupVec<Id, RandomState, B> = (0..1000u64).into_par_iter().map(|id| Id { id }).collect();
+        assert_eq!(vec.par_iter().map(|item| item.id).sum::<u64>(), 499500);
+        assert_eq!(vec.par_keys().map(|key| *key).max(), Some(999));
+
+        vec.par_iter_mut().for_each(|mut item| item.id += 1000);
+        assert_eq!(vec.get_index_of(&1500), Some(500));
+        assert!(!vec.contains_key(&500));
+
+        vec.par_sort_by(|a, b| b.id.cmp(&a.id));
+        assert_eq!(vec.get_index_of(&1999), Some(0));
+        assert!(vec.validate().is_empty());
+
+        let ids: Vec<u64> = vec.into_par_iter().map(|item| item.id).collect();
+        assert_eq!(&ids[..2], &[1999, 1998]);
+    }
+}
@@ -0,0 +1,211 @@
+//! The sealed traits behind `Backend`. `LookupVec` and its guards, entries and
+//! iterators are written against these, and each backend implements them.
+
+use crate::core::Lookup;
+use crate::guard::KeyCheck;
+
+use indexmap::Equivalent;
+#[cfg(feature = "rayon")]
+use rayon::iter::IndexedParallelIterator;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::hash::BuildHasher;
+use core::hash::Hash;
+use core::iter::FusedIterator;
+use core::ops::Deref;
+use core::ops::Range;
+use core::ops::RangeBounds;
+
+/// Shorthand for the iterator traits every backend iterator implements.
+pub trait ExactIterator: DoubleEndedIterator + ExactSizeIterator + FusedIterator {}
+
+impl<I: DoubleEndedIterator + ExactSizeIterator + FusedIterator> ExactIterator for I {}
+
+/// The types a backend keeps its items in and iterates them with.
+pub trait Storage: Sized {
+    /// The items and the hash table that finds them.
+    type Store<T: Lookup, S>: Store<T, S, Self>;
+    /// A slice of items, without the hash table.
+    type Slice<T: Lookup>: ?Sized + RawSlice<T, Self>;
+    /// What an `ItemMut` checks the item's key against when it is dropped.
+    type Slot<'a, T: Lookup + 'a, S: 'a>: Copy;
+    /// A key as reported by `keys`.
+    type Key<'a, T: Lookup + 'a>: Deref<Target = T::Key>;
+
+    type Iter<'a, T: Lookup + 'a>: ExactIterator<Item = &'a T>;
+    type SliceIterMut<'a, T: Lookup + 'a>: ExactIterator<Item = &'a mut T>;
+    type IterMut<'a, T: Lookup + 'a, S: 'a>: ExactIterator<Item = (Self::Slot<'a, T, S>, &'a mut T)>;
+    type IntoIter<T: Lookup>: ExactIterator<Item = T>;
+    type Keys<'a, T: Lookup + 'a>: ExactIterator<Item = Self::Key<'a, T>>;
+    type IntoKeys<T: Lookup>: ExactIterator<Item = T::Key>;
+    type Drain<'a, T: Lookup + 'a>: ExactIterator<Item = T>;
+
+    #[cfg(feature = "rayon")]
+    type ParIter<'a, T: Lookup + Sync + 'a>: IndexedParallelIterator<Item = &'a T>
+    where T::Key: Sync;
+    #[cfg(feature = "rayon")]
+    type ParIterMut<'a, T: Lookup + Send + 'a, S: Sync + 'a>: IndexedParallelIterator<Item = (Self::Slot<'a, T, S>, &'a mut T)>
+    where T::Key: Send + Sync;
+    #[cfg(feature = "rayon")]
+    type IntoParIter<T: Lookup + Send>: IndexedParallelIterator<Item = T>
+    where T::Key: Send;
+    #[cfg(feature = "rayon")]
+    type ParKeys<'a, T: Lookup + Sync + 'a>: IndexedParallelIterator<Item = Self::Key<'a, T>>
+    where T::Key: Send + Sync;
+
+    /// Returns true if `item`, at `index`, is still filed under `slot`.
+    fn slot_holds<T: Lookup, S: BuildHasher>(slot: Self::Slot<'_, T, S>, index: usize, item: &T) -> bool;
+
+    /// Returns the key that `slot` files its item under, if it keeps one.
+    fn slot_key<'a, T: Lookup + 'a, S: 'a>(slot: Self::Slot<'a, T, S>) -> Option<&'a T::Key>;
+}
+
+/// The operations `LookupVec` builds on. None of them apply the `KeyPolicy`:
+/// callers repair the hash table first where that matters.
+pub trait Store<T: Lookup, S, B: Storage>: Sized {
+    fn with_capacity_and_hasher(n: usize, hasher: S) -> Self;
+    fn clone_store(&self) -> Self
+    where T: Clone, S: Clone;
+
+    fn len(&self) -> usize;
+    fn capacity(&self) -> usize;
+    fn hasher(&self) -> &S;
+
+    fn as_slice(&self) -> &B::Slice<T>;
+    fn as_mut_slice(&mut self) -> &mut B::Slice<T>;
+    fn into_boxed_slice(self) -> Box<B::Slice<T>>;
+    fn into_iter(self) -> B::IntoIter<T>;
+    fn into_keys(self) -> B::IntoKeys<T>;
+    fn iter_mut(&mut self) -> B::IterMut<'_, T, S>;
+
+    /// Returns the key the item at `index` is stored under, if the store
+    /// keeps a copy.
+    fn stored_key(&self, index: usize) -> Option<&T::Key>;
+
+    /// Returns the hash the item at `index` is filed under, if the store needs
+    /// it to find the item's slot again after its key changes.
+    fn slot_hash(&self, index: usize) -> Option<u64>
+    where S: BuildHasher;
+
+    /// Returns true if the item at `index` is found under its current key.
+    fn is_stored(&self, index: usize) -> bool
+    where S: BuildHasher;
+
+    /// Looks up the index of `key` in the hash table.
+    fn get_index_of<Q>(&self, key: &Q) -> Option<usize>
+    where S: BuildHasher, Q: ?Sized + Hash + Equivalent<T::Key>;
+
+    /// Like `get_index_of`, for a key whose hash is known.
+    fn find_hashed(&self, hash: u64, key: &T::Key) -> Option<usize>
+    where S: BuildHasher;
+
+    /// Appends an item whose key, with hash `hash`, is known to be new. Takes
+    /// the item's key if the caller already computed it, and hands it back if
+    /// the store keeps no copy.
+    fn push_unique(&mut self, hash: u64, key: Option<T::Key>, value: T) -> Option<T::Key>
+    where S: BuildHasher;
+
+    /// Files the item at `index` under `key` instead of wherever it was. `hash`
+    /// is its old `slot_hash`, if known.
+    fn rekey_index(&mut self, index: usize, key: T::Key, hash: Option<u64>)
+    where S: BuildHasher;
+
+    /// Files every item under the given keys and hashes, which are distinct.
+    fn rebuild(&mut self, keys: Vec<(u64, T::Key)>)
+    where S: BuildHasher;
+
+    fn truncate(&mut self, len: usize);
+    fn clear(&mut self);
+    fn reserve(&mut self, additional: usize)
+    where S: BuildHasher;
+    fn reserve_exact(&mut self, additional: usize)
+    where S: BuildHasher;
+    fn shrink_to(&mut self, min_capacity: usize)
+    where S: BuildHasher;
+    fn shrink_to_fit(&mut self)
+    where S: BuildHasher;
+
+    fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> B::Drain<'_, T>;
+    fn split_off(&mut self, at: usize) -> Self
+    where S: BuildHasher + Clone;
+    fn shift_remove_index(&mut self, index: usize) -> Option<T>
+    where S: BuildHasher;
+    fn swap_remove_index(&mut self, index: usize) -> Option<T>
+    where S: BuildHasher;
+
+    /// Keeps the items for which `keep` returns true. Kept items stay filed
+    /// where they were, so that changed keys are still noticed.
+    fn retain<F>(&mut self, keep: F)
+    where F: FnMut(&mut T) -> bool;
+
+    /// Removes and yields the items in `range` for which `pred` returns true.
+    /// Kept items are checked against `check` as they are visited.
+    // `Store` is sealed, so `KeyCheck` never leaves the crate
+    #[allow(private_interfaces)]
+    fn extract_if<'a, F>(
+        &'a mut self,
+        range: Range<usize>,
+        check: &'a mut KeyCheck,
+        pred: F,
+    ) -> impl Iterator<Item = T> + 'a
+    where S: BuildHasher, F: FnMut(&mut T) -> bool + 'a;
+
+    fn move_index(&mut self, from: usize, to: usize)
+    where S: BuildHasher;
+    fn swap_indices(&mut self, a: usize, b: usize)
+    where S: BuildHasher;
+    fn reverse(&mut self);
+
+    fn sort_by<F>(&mut self, cmp: F, stable: bool)
+    where S: BuildHasher, F: FnMut(&T, &T) -> Ordering;
+    fn sort_keys_by<F>(&mut self, cmp: F)
+    where S: BuildHasher, F: FnMut(&T::Key, &T::Key) -> Ordering;
+
+    #[cfg(feature = "rayon")]
+    fn par_iter(&self) -> B::ParIter<'_, T>
+    where T: Sync, T::Key: Sync;
+    #[cfg(feature = "rayon")]
+    fn par_iter_mut(&mut self) -> B::ParIterMut<'_, T, S>
+    where T: Send, T::Key: Send + Sync, S: Sync;
+    #[cfg(feature = "rayon")]
+    fn into_par_iter(self) -> B::IntoParIter<T>
+    where T: Send, T::Key: Send;
+    #[cfg(feature = "rayon")]
+    fn par_keys(&self) -> B::ParKeys<'_, T>
+    where T: Sync, T::Key: Send + Sync;
+    #[cfg(feature = "rayon")]
+    fn par_sort_by<F>(&mut self, cmp: F)
+    where T: Send, T::Key: Send, S: BuildHasher, F: Fn(&T, &T) -> Ordering + Sync;
+}
+
+/// The operations `Slice` builds on.
+pub trait RawSlice<T: Lookup, B: Storage> {
+    fn empty<'a>() -> &'a Self;
+    fn empty_mut<'a>() -> &'a mut Self;
+
+    fn len(&self) -> usize;
+    fn get(&self, index: usize) -> Option<&T>;
+    fn get_mut(&mut self, index: usize) -> Option<&mut T>;
+
+    /// ***Panics*** if `range` is out of bounds.
+    fn range(&self, range: Range<usize>) -> &Self;
+    /// ***Panics*** if `range` is out of bounds.
+    fn range_mut(&mut self, range: Range<usize>) -> &mut Self;
+    /// ***Panics*** if `index > len`.
+    fn split_at(&self, index: usize) -> (&Self, &Self);
+    /// ***Panics*** if `index > len`.
+    fn split_at_mut(&mut self, index: usize) -> (&mut Self, &mut Self);
+
+    fn iter(&self) -> B::Iter<'_, T>;
+    fn iter_mut(&mut self) -> B::SliceIterMut<'_, T>;
+    fn keys(&self) -> B::Keys<'_, T>;
+    fn into_iter(self: Box<Self>) -> B::IntoIter<T>;
+    fn into_keys(self: Box<Self>) -> B::IntoKeys<T>;
+
+    fn binary_search_by<F>(&self, f: F) -> Result<usize, usize>
+    where F: FnMut(&T) -> Ordering;
+    fn partition_point<P>(&self, pred: P) -> usize
+    where P: FnMut(&T) -> bool;
+}
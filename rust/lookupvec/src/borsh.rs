@@ -0,0 +1,99 @@
+use crate::core::Lookup;
+use crate::core::cautious_capacity;
+use crate::vec::LookupVec;
+
+use borsh::BorshDeserialize;
+use borsh::BorshSerialize;
+use borsh::io::Error;
+use borsh::io::ErrorKind;
+use borsh::io::Read;
+use borsh::io::Result;
+use borsh::io::Write;
+
+use alloc::format;
+use core::hash::BuildHasher;
+
+/// Encoded like a `Vec<T>`: the length as a `u32`, then the items in order.
+impl<T, S> BorshSerialize for LookupVec<T, S>
+where
+    T: Lookup + BorshSerialize,
+{
+    fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let len = u32::try_from(self.len()).map_err(|_| ErrorKind::InvalidData)?;
+        len.serialize(writer)?;
+        for item in self.iter() {
+            item.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// ***Fails*** at the first item with the same key as an earlier one.
+impl<T, S> BorshDeserialize for LookupVec<T, S>
+where
+    T: Lookup + BorshDeserialize,
+    S: BuildHasher + Default,
+{
+    fn deserialize_reader<R: Read>(reader: &mut R) -> Result<Self> {
+        let len = u32::deserialize_reader(reader)? as usize;
+        let mut vec = LookupVec::with_capacity_and_hasher(cautious_capacity::<T>(Some(len)), S::default());
+        for position in 0..len {
+            let item = T::deserialize_reader(reader)?;
+            vec.try_push(item).map_err(|err| Error::new(ErrorKind::InvalidData, format!(
+                "Found duplicate key {:?} at indices {}, {position}",
+                err.keys()[0],
+                err.index(),
+            )))?;
+        }
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use lookupvec_derive::Lookup;
+
+    use alloc::borrow::ToOwned;
+    use alloc::string::String;
+    use alloc::string::ToString;
+
+    #[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Lookup)]
+    struct Record {
+        #[lookup_key]
+        id: String,
+        size: u32,
+    }
+
+    fn record(id: &str, size: u32) -> Record {
+        Record { id: id.to_owned(), size }
+    }
+
+    #[test]
+    fn test_borsh_round_trip() {
+        let vec = LookupVec::<Record>::from([record("b", 2), record("a", 1), record("c", 3)]);
+        let bytes = borsh::to_vec(&vec).unwrap();
+        let records = vec![record("b", 2), record("a", 1), record("c", 3)];
+        assert_eq!(bytes, borsh::to_vec(&records).unwrap());
+
+        let decoded: LookupVec<Record> = borsh::from_slice(&bytes).unwrap();
+        assert_eq!(decoded, vec);
+        assert_eq!(decoded.get_index_of("c"), Some(2));
+    }
+
+    #[test]
+    fn test_borsh_duplicate_keys() {
+        let bytes = borsh::to_vec(&vec![record("a", 1), record("b", 2), record("a", 3)]).unwrap();
+        let err = borsh::from_slice::<LookupVec<Record>>(&bytes).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), r#"Found duplicate key "a" at indices 0, 2"#);
+    }
+
+    #[test]
+    fn test_borsh_truncated() {
+        // Claims four billion items
+        let bytes = [0xff, 0xff, 0xff, 0xf0, 1, 0, 0, 0];
+        assert!(borsh::from_slice::<LookupVec<Record>>(&bytes).is_err());
+    }
+}
@@ -1,19 +1,143 @@
+#[cfg(feature = "alloc")]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "alloc")]
+use alloc::collections::BTreeSet;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use core::borrow::Borrow;
 use core::fmt::Debug;
+#[cfg(feature = "alloc")]
+use core::hash::BuildHasher;
 use core::hash::Hash;
 use core::ops::Deref;
+#[cfg(feature = "alloc")]
+use indexmap::IndexMap;
+#[cfg(feature = "alloc")]
+use indexmap::IndexSet;
 
 pub trait Lookup {
     type Key: Hash + Eq + Clone + Debug;
     fn key(&self) -> Self::Key;
+
+    /// Returns a reference to the key if the item stores it.
+    ///
+    /// `LookupVec` uses this to avoid cloning the key wherever it only needs
+    /// to look at it, such as when checking for an existing item. Items that
+    /// store their key should override it, as `#[derive(Lookup)]` does.
+    fn borrow_key(&self) -> Option<&Self::Key> {
+        None
+    }
+}
+
+/// Items whose key is a single named field.
+///
+/// `#[derive(Lookup)]` implements this for keys that are a plain named field,
+/// following `#[serde(rename = "...")]` on the field. Used by
+/// `lookupvec::serde::as_map::omit_key` to leave out and fill in the key field.
+pub trait KeyField: Lookup {
+    /// The serialized name of the key field.
+    const KEY_FIELD: &'static str;
 }
 
-impl<T, R> Lookup for R
+impl<R> Lookup for R
 where
-    R: Deref<Target = T>,
-    T: Lookup
+    R: Deref,
+    R::Target: Lookup + Sized,
 {
-    type Key = T::Key;
+    type Key = <R::Target as Lookup>::Key;
     fn key(&self) -> Self::Key {
         self.deref().key()
     }
-}
\ No newline at end of file
+
+    fn borrow_key(&self) -> Option<&Self::Key> {
+        self.deref().borrow_key()
+    }
+}
+
+/// Calls `f` with a reference to the item's key, borrowing it if possible.
+#[cfg(any(feature = "alloc", feature = "array"))]
+pub(crate) fn with_key<T: Lookup, R>(item: &T, f: impl FnOnce(&T::Key) -> R) -> R {
+    match item.borrow_key() {
+        Some(key) => f(key),
+        None => f(&item.key()),
+    }
+}
+
+/// Caps the capacity preallocated from a size hint, which may come from
+/// untrusted input, at about 1 MiB.
+#[cfg(any(feature = "borsh", feature = "serde"))]
+pub(crate) fn cautious_capacity<T: Lookup>(hint: Option<usize>) -> usize {
+    const MAX_PREALLOC_BYTES: usize = 1024 * 1024;
+    // Each entry of the map stores the item's key and hash alongside it
+    let entry_size = size_of::<(usize, T::Key, T)>().max(1);
+    hint.unwrap_or(0).min(MAX_PREALLOC_BYTES / entry_size)
+}
+
+/// Collections that can report whether they contain a key, for
+/// `LookupVec::retain_keys()`.
+pub trait Contains<K: ?Sized> {
+    fn contains(&self, key: &K) -> bool;
+}
+
+impl<K: ?Sized, C: Contains<K> + ?Sized> Contains<K> for &C {
+    fn contains(&self, key: &K) -> bool {
+        (**self).contains(key)
+    }
+}
+
+impl<K: PartialEq> Contains<K> for [K] {
+    fn contains(&self, key: &K) -> bool {
+        <[K]>::contains(self, key)
+    }
+}
+
+impl<K: PartialEq, const N: usize> Contains<K> for [K; N] {
+    fn contains(&self, key: &K) -> bool {
+        <[K]>::contains(self, key)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K: PartialEq> Contains<K> for Vec<K> {
+    fn contains(&self, key: &K) -> bool {
+        <[K]>::contains(self, key)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K: Ord, Q: Ord + ?Sized> Contains<Q> for BTreeSet<K>
+where K: Borrow<Q> {
+    fn contains(&self, key: &Q) -> bool {
+        BTreeSet::contains(self, key)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K: Ord, V, Q: Ord + ?Sized> Contains<Q> for BTreeMap<K, V>
+where K: Borrow<Q> {
+    fn contains(&self, key: &Q) -> bool {
+        self.contains_key(key)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K: Hash + Eq, S: BuildHasher, Q: Hash + indexmap::Equivalent<K> + ?Sized> Contains<Q> for IndexSet<K, S> {
+    fn contains(&self, key: &Q) -> bool {
+        IndexSet::contains(self, key)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K: Hash + Eq, V, S: BuildHasher, Q: Hash + indexmap::Equivalent<K> + ?Sized> Contains<Q> for IndexMap<K, V, S> {
+    fn contains(&self, key: &Q) -> bool {
+        self.contains_key(key)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K: Hash + Eq, S: BuildHasher, Q: Hash + hashbrown::Equivalent<K> + ?Sized> Contains<Q> for hashbrown::HashSet<K, S> {
+    fn contains(&self, key: &Q) -> bool {
+        hashbrown::HashSet::contains(self, key)
+    }
+}
@@ -0,0 +1,352 @@
+//! Differences between two `LookupVec`s, for reconciling one against the
+//! other.
+//!
+//! ```
+//! # #[cfg(feature = "derive")] {
+//! # use lookupvec::Lookup;
+//! # use lookupvec::LookupVec;
+//! #[derive(Debug, Clone, PartialEq, Lookup)]
+//! struct Replica {
+//!     #[lookup_key]
+//!     name: &'static str,
+//!     version: u32,
+//! }
+//!
+//! let mut observed = LookupVec::from([
+//!     Replica { name: "a", version: 1 },
+//!     Replica { name: "b", version: 1 },
+//! ]);
+//! let desired = LookupVec::from([
+//!     Replica { name: "b", version: 2 },
+//!     Replica { name: "c", version: 2 },
+//! ]);
+//!
+//! let plan = observed.diff(&desired);
+//! plan.apply(&mut observed)?;
+//! assert_eq!(observed.iter().collect::<Vec<_>>(), desired.iter().collect::<Vec<_>>());
+//! # }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::backend::Backend;
+use crate::core::Lookup;
+use crate::core::with_key;
+use crate::vec::LookupVec;
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::BuildHasher;
+
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// One difference between two `LookupVec`s, as seen from the first.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T: Serialize, T::Key: Serialize",
+    deserialize = "T: Deserialize<'de>, T::Key: Deserialize<'de>",
+)))]
+pub enum Change<T: Lookup> {
+    /// The item is only in the second vec, at `index`.
+    Added { index: usize, item: T },
+    /// The item is only in the first vec, at `index`.
+    Removed { index: usize, item: T },
+    /// The item is in both vecs, but differs.
+    Modified { old: T, new: T },
+    /// The item is in both vecs, at different indices.
+    Moved { key: T::Key, from: usize, to: usize },
+}
+
+impl<T: Lookup> Change<T> {
+    /// The key of the changed item.
+    pub fn key(&self) -> T::Key {
+        match self {
+            Change::Added { item, .. } | Change::Removed { item, .. } => item.key(),
+            Change::Modified { new, .. } => new.key(),
+            Change::Moved { key, .. } => key.clone(),
+        }
+    }
+
+    /// Returns the change that undoes this one.
+    pub fn invert(self) -> Self {
+        match self {
+            Change::Added { index, item } => Change::Removed { index, item },
+            Change::Removed { index, item } => Change::Added { index, item },
+            Change::Modified { old, new } => Change::Modified { old: new, new: old },
+            Change::Moved { key, from, to } => Change::Moved { key, from: to, to: from },
+        }
+    }
+}
+
+/// The changes that turn one `LookupVec` into another, from
+/// `LookupVec::diff`.
+///
+/// Removals come first, in the order of the first vec, followed by the other
+/// changes in the order of the second vec.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T: Serialize, T::Key: Serialize",
+    deserialize = "T: Deserialize<'de>, T::Key: Deserialize<'de>",
+)))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Diff<T: Lookup> {
+    changes: Vec<Change<T>>,
+}
+
+impl<T: Lookup> Diff<T> {
+    pub(crate) fn between<S1, S2, B, F>(
+        old: &LookupVec<T, S1, B>,
+        new: &LookupVec<T, S2, B>,
+        mut eq: F,
+    ) -> Self
+    where
+        T: Clone,
+        S1: BuildHasher,
+        S2: BuildHasher,
+        B: Backend,
+        F: FnMut(&T, &T) -> bool,
+    {
+        let mut changes = Vec::new();
+        for (index, item) in old.iter().enumerate() {
+            if !new.contains(item) {
+                changes.push(Change::Removed { index, item: item.clone() });
+            }
+        }
+        for (to, item) in new.iter().enumerate() {
+            let Some(from) = with_key(item, |key| old.get_index_of(key)) else {
+                changes.push(Change::Added { index: to, item: item.clone() });
+                continue;
+            };
+            if !eq(&old[from], item) {
+                changes.push(Change::Modified { old: old[from].clone(), new: item.clone() });
+            }
+            if from != to {
+                changes.push(Change::Moved { key: item.key(), from, to });
+            }
+        }
+        Diff { changes }
+    }
+
+    pub fn changes(&self) -> &[Change<T>] {
+        &self.changes
+    }
+
+    pub fn into_changes(self) -> Vec<Change<T>> {
+        self.changes
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Returns true if the two vecs were the same.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Returns the diff that turns the second vec back into the first.
+    pub fn invert(self) -> Self {
+        Diff { changes: self.changes.into_iter().map(Change::invert).collect() }
+    }
+
+    /// Turns the first vec into the second. The vec is left unchanged if it
+    /// does not match the first vec closely enough for the diff to apply.
+    ///
+    /// Only the keys and indices of `vec` are checked. Modified items are
+    /// replaced whatever their current value.
+    pub fn apply<S, B>(&self, vec: &mut LookupVec<T, S, B>) -> Result<(), ApplyError<T::Key>>
+    where T: Clone, S: BuildHasher, B: Backend {
+        // Work out where each item ends up before changing anything
+        let mut targets: Vec<Option<usize>> = (0..vec.len()).map(Some).collect();
+        let mut len = vec.len();
+        for change in &self.changes {
+            let key = change.key();
+            let index = vec.get_index_of(&key);
+            match (change, index) {
+                (Change::Added { .. }, Some(_)) => return Err(ApplyError::Exists(key)),
+                (Change::Added { .. }, None) => len += 1,
+                (_, None) => return Err(ApplyError::Missing(key)),
+                (Change::Removed { .. }, Some(index)) => {
+                    targets[index] = None;
+                    len -= 1;
+                },
+                (Change::Moved { to, .. }, Some(index)) => targets[index] = Some(*to),
+                (Change::Modified { .. }, Some(_)) => {},
+            }
+        }
+
+        let mut slots: Vec<Option<usize>> = (0..len).map(|_| None).collect();
+        let mut place = |slot: usize, source: usize| match slots.get_mut(slot) {
+            Some(slot @ None) => {
+                *slot = Some(source);
+                Ok(())
+            },
+            _ => Err(ApplyError::Index(slot)),
+        };
+        for (index, target) in targets.iter().enumerate() {
+            if let Some(target) = *target {
+                place(target, index)?;
+            }
+        }
+        // Added items are numbered after the existing ones
+        let mut added = Vec::new();
+        for change in &self.changes {
+            if let Change::Added { index, item } = change {
+                place(*index, vec.len() + added.len())?;
+                added.push(item);
+            }
+        }
+        let slots: Vec<usize> = slots.into_iter().collect::<Option<_>>()
+            .ok_or(ApplyError::Incomplete)?;
+
+        for change in &self.changes {
+            if let Change::Modified { new, .. } = change {
+                let index = with_key(new, |key| vec.get_index_of(key)).unwrap();
+                *vec.index_mut_unchecked(index) = new.clone();
+            }
+        }
+        let mut items: Vec<Option<T>> = vec.drain(..).map(Some).collect();
+        items.extend(added.into_iter().map(|item| Some(item.clone())));
+        vec.extend(slots.into_iter().map(|source| items[source].take().unwrap()));
+        Ok(())
+    }
+}
+
+impl<T: Lookup> IntoIterator for Diff<T> {
+    type Item = Change<T>;
+    type IntoIter = alloc::vec::IntoIter<Change<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.changes.into_iter()
+    }
+}
+
+impl<'a, T: Lookup> IntoIterator for &'a Diff<T> {
+    type Item = &'a Change<T>;
+    type IntoIter = core::slice::Iter<'a, Change<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.changes.iter()
+    }
+}
+
+/// A `Diff` did not match the `LookupVec` it was applied to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApplyError<K> {
+    /// A removed, modified or moved item is not in the vec.
+    Missing(K),
+    /// An added item is already in the vec.
+    Exists(K),
+    /// Two items would end up at this index, or it is out of bounds.
+    Index(usize),
+    /// Some indices would be left without an item.
+    Incomplete,
+}
+
+impl<K: fmt::Debug> fmt::Display for ApplyError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApplyError::Missing(key) => write!(f, "no item with key {key:?}"),
+            ApplyError::Exists(key) => write!(f, "an item with key {key:?} already exists"),
+            ApplyError::Index(index) => write!(f, "index {index} is out of bounds or used twice"),
+            ApplyError::Incomplete => f.write_str("diff leaves gaps in the vec"),
+        }
+    }
+}
+
+impl<K: fmt::Debug> core::error::Error for ApplyError<K> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use lookupvec_derive::Lookup;
+
+    #[derive(Debug, Clone, PartialEq, Lookup)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    struct Resource {
+        #[lookup_key]
+        id: u32,
+        size: u32,
+    }
+
+    fn resource(id: u32, size: u32) -> Resource {
+        Resource { id, size }
+    }
+
+    fn observed() -> LookupVec<Resource> {
+        LookupVec::from([resource(1, 10), resource(2, 20), resource(3, 30), resource(4, 40)])
+    }
+
+    fn desired() -> LookupVec<Resource> {
+        LookupVec::from([resource(3, 30), resource(1, 15), resource(5, 50), resource(4, 40)])
+    }
+
+    fn items(vec: &LookupVec<Resource>) -> Vec<Resource> {
+        vec.iter().cloned().collect()
+    }
+
+    #[test]
+    fn test_diff() {
+        let diff = observed().diff(&desired());
+        assert_eq!(diff.into_changes(), vec![
+            Change::Removed { index: 1, item: resource(2, 20) },
+            Change::Moved { key: 3, from: 2, to: 0 },
+            Change::Modified { old: resource(1, 10), new: resource(1, 15) },
+            Change::Moved { key: 1, from: 0, to: 1 },
+            Change::Added { index: 2, item: resource(5, 50) },
+        ]);
+        assert!(observed().diff(&observed()).is_empty());
+    }
+
+    #[test]
+    fn test_diff_by() {
+        let diff = observed().diff_by(&desired(), |a, b| a.id == b.id);
+        assert!(diff.changes().iter().all(|change| !matches!(change, Change::Modified { .. })));
+    }
+
+    #[test]
+    fn test_apply_and_invert() {
+        let mut vec = observed();
+        let diff = vec.diff(&desired());
+        diff.apply(&mut vec).unwrap();
+        assert_eq!(items(&vec), items(&desired()));
+        assert_eq!(vec.get_index_of(&5), Some(2));
+
+        diff.invert().apply(&mut vec).unwrap();
+        assert_eq!(items(&vec), items(&observed()));
+    }
+
+    #[test]
+    fn test_apply_mismatch() {
+        let diff = observed().diff(&desired());
+        let mut vec = LookupVec::from([resource(1, 10), resource(5, 50)]);
+        assert_eq!(diff.apply(&mut vec), Err(ApplyError::Missing(2)));
+        assert_eq!(items(&vec), vec![resource(1, 10), resource(5, 50)]);
+
+        let mut vec = desired();
+        assert_eq!(diff.apply(&mut vec), Err(ApplyError::Missing(2)));
+
+        let mut vec = LookupVec::from([resource(6, 60)]);
+        vec.extend(observed());
+        assert_eq!(diff.apply(&mut vec), Err(ApplyError::Index(0)));
+        assert_eq!(vec.len(), 5);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let diff = observed().diff(&desired());
+        let json = serde_json::to_string(&diff).unwrap();
+        assert!(json.starts_with(r#"[{"Removed":{"index":1,"item":{"id":2,"size":20}}}"#));
+
+        let plan: Diff<Resource> = serde_json::from_str(&json).unwrap();
+        let mut vec = observed();
+        plan.apply(&mut vec).unwrap();
+        assert_eq!(items(&vec), items(&desired()));
+    }
+}
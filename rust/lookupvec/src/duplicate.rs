@@ -0,0 +1,78 @@
+use crate::core::Lookup;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// What a `LookupVec` does when an item is pushed with the same key as an
+/// item it already contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    /// Replace the existing item, keeping its position.
+    #[default]
+    Replace,
+    /// Keep the existing item and discard the new one.
+    KeepFirst,
+    /// Replace the existing item and move it to the end.
+    MoveToEnd,
+    /// Reject the new item with a `DuplicateKeyError`.
+    Error,
+}
+
+/// An item was rejected because its key is already in use.
+pub struct DuplicateKeyError<T: Lookup> {
+    item: T,
+    index: usize,
+    keys: Vec<T::Key>,
+}
+
+impl<T: Lookup> DuplicateKeyError<T> {
+    pub(crate) fn new(item: T, index: usize) -> Self {
+        let keys = vec![item.key()];
+        DuplicateKeyError { item, index, keys }
+    }
+
+    pub(crate) fn with_keys(item: T, index: usize, keys: Vec<T::Key>) -> Self {
+        DuplicateKeyError { item, index, keys }
+    }
+
+    /// The first rejected item.
+    pub fn item(&self) -> &T {
+        &self.item
+    }
+
+    pub fn into_item(self) -> T {
+        self.item
+    }
+
+    /// Index of the item whose key the rejected item duplicates.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Every duplicated key, in the order they were found.
+    pub fn keys(&self) -> &[T::Key] {
+        &self.keys
+    }
+}
+
+impl<T: Lookup> fmt::Debug for DuplicateKeyError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DuplicateKeyError")
+            .field("index", &self.index)
+            .field("keys", &self.keys)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Lookup> fmt::Display for DuplicateKeyError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "duplicate key {:?} of item at index {}", self.keys[0], self.index)?;
+        if self.keys.len() > 1 {
+            write!(f, " (and {} more duplicate keys)", self.keys.len() - 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Lookup> core::error::Error for DuplicateKeyError<T> {}
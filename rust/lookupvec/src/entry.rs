@@ -0,0 +1,462 @@
+use crate::backend::Backend;
+use crate::backend::Indexed;
+use crate::backend::store::RawSlice;
+use crate::backend::store::Store;
+use crate::core::Lookup;
+use crate::core::with_key;
+use crate::duplicate::DuplicateKeyError;
+use crate::duplicate::DuplicatePolicy;
+use crate::vec::LookupVec;
+
+use ahash::random_state::RandomState;
+
+use core::fmt;
+use core::hash::BuildHasher;
+
+/// Entry for an existing item in a `LookupVec`, or a vacant location to
+/// insert one. Returned by `LookupVec::entry`.
+pub enum Entry<'a, T: Lookup, S: BuildHasher = RandomState, B: Backend = Indexed> {
+    Occupied(OccupiedEntry<'a, T, S, B>),
+    Vacant(VacantEntry<'a, T, S, B>),
+}
+
+impl<'a, T: Lookup, S: BuildHasher, B: Backend> Entry<'a, T, S, B> {
+    pub(crate) fn new(vec: &'a mut LookupVec<T, S, B>, key: T::Key) -> Self {
+        let hash = vec.store.hasher().hash_one(&key);
+        match vec.store.find_hashed(hash, &key) {
+            Some(index) => {
+                // Keep the key only if the store doesn't have a copy to lend
+                let key = vec.store.stored_key(index).is_none().then_some(key);
+                Entry::Occupied(OccupiedEntry { vec, index, key })
+            },
+            None => Entry::Vacant(VacantEntry { vec, key, hash }),
+        }
+    }
+
+    /// Returns the index where the item exists or will be inserted.
+    pub fn index(&self) -> usize {
+        match self {
+            Entry::Occupied(entry) => entry.index(),
+            Entry::Vacant(entry) => entry.index(),
+        }
+    }
+
+    pub fn key(&self) -> &T::Key {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Inserts the item if the entry is vacant, or handles the existing item
+    /// according to the vec's `DuplicatePolicy`, and returns an
+    /// `OccupiedEntry` for the item with this key.
+    ///
+    /// ***Panics*** if the entry is occupied and the policy is `Error`.
+    pub fn insert_entry(self, value: T) -> OccupiedEntry<'a, T, S, B> {
+        match self {
+            Entry::Occupied(mut entry) => {
+                match entry.vec.duplicate_policy() {
+                    DuplicatePolicy::Replace => {
+                        entry.insert(value);
+                    },
+                    DuplicatePolicy::KeepFirst => {},
+                    DuplicatePolicy::MoveToEnd => {
+                        entry.insert(value);
+                        let last = entry.vec.len() - 1;
+                        entry.vec.store.move_index(entry.index, last);
+                        entry.index = last;
+                    },
+                    DuplicatePolicy::Error => panic!("{}", DuplicateKeyError::new(value, entry.index())),
+                }
+                entry
+            }
+            Entry::Vacant(entry) => entry.insert_entry(value),
+        }
+    }
+
+    pub fn or_insert(self, default: T) -> &'a mut T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    pub fn or_insert_with<F>(self, call: F) -> &'a mut T
+    where F: FnOnce() -> T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(call()),
+        }
+    }
+
+    pub fn or_insert_with_key<F>(self, call: F) -> &'a mut T
+    where F: FnOnce(&T::Key) -> T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = call(entry.key());
+                entry.insert(value)
+            }
+        }
+    }
+
+    /// Modifies the item if the entry is occupied.
+    pub fn and_modify<F>(mut self, f: F) -> Self
+    where F: FnOnce(&mut T) {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<T: Lookup + fmt::Debug, S: BuildHasher, B: Backend> fmt::Debug for Entry<'_, T, S, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entry::Occupied(entry) => f.debug_tuple("Entry").field(entry).finish(),
+            Entry::Vacant(entry) => f.debug_tuple("Entry").field(entry).finish(),
+        }
+    }
+}
+
+/// A view into an occupied entry in a `LookupVec`.
+///
+/// If the item's key is changed through `get_mut` or `into_mut`, it is handled
+/// according to the vec's `KeyPolicy` on the next call that needs the hash
+/// table.
+pub struct OccupiedEntry<'a, T: Lookup, S: BuildHasher = RandomState, B: Backend = Indexed> {
+    vec: &'a mut LookupVec<T, S, B>,
+    index: usize,
+    /// The entry's key, if the store keeps no copy of it.
+    key: Option<T::Key>,
+}
+
+impl<'a, T: Lookup, S: BuildHasher, B: Backend> OccupiedEntry<'a, T, S, B> {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn key(&self) -> &T::Key {
+        match &self.key {
+            Some(key) => key,
+            None => self.vec.store.stored_key(self.index).unwrap(),
+        }
+    }
+
+    pub fn get(&self) -> &T {
+        &self.vec[self.index]
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.vec.check.mark(self.index..=self.index);
+        self.vec.index_mut_unchecked(self.index)
+    }
+
+    pub fn into_mut(self) -> &'a mut T {
+        self.vec.check.mark(self.index..=self.index);
+        self.vec.index_mut_unchecked(self.index)
+    }
+
+    pub fn swap_remove(self) -> T {
+        self.vec.check.removed();
+        self.vec.store.swap_remove_index(self.index).unwrap()
+    }
+
+    pub fn shift_remove(self) -> T {
+        self.vec.check.removed();
+        self.vec.store.shift_remove_index(self.index).unwrap()
+    }
+
+    pub fn move_index(self, to: usize) {
+        self.vec.check.moved_to(to);
+        self.vec.store.move_index(self.index, to)
+    }
+
+    pub fn swap_indices(self, other: usize) {
+        self.vec.check.moved_to(other);
+        self.vec.store.swap_indices(self.index, other)
+    }
+
+    /// Replaces the item in the entry, returning the old one.
+    ///
+    /// The new item must have the same key as the entry.
+    pub fn insert(&mut self, value: T) -> T {
+        debug_assert_key_matches(self.key(), &value);
+        core::mem::replace(self.vec.index_mut_unchecked(self.index), value)
+    }
+}
+
+impl<T: Lookup + fmt::Debug, S: BuildHasher, B: Backend> fmt::Debug for OccupiedEntry<'_, T, S, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OccupiedEntry")
+            .field("key", self.key())
+            .field("value", self.get())
+            .finish()
+    }
+}
+
+/// A view into a vacant entry in a `LookupVec`.
+pub struct VacantEntry<'a, T: Lookup, S: BuildHasher = RandomState, B: Backend = Indexed> {
+    vec: &'a mut LookupVec<T, S, B>,
+    key: T::Key,
+    hash: u64,
+}
+
+impl<'a, T: Lookup, S: BuildHasher, B: Backend> VacantEntry<'a, T, S, B> {
+    /// Returns the index where the item will be inserted.
+    pub fn index(&self) -> usize {
+        self.vec.len()
+    }
+
+    pub fn key(&self) -> &T::Key {
+        &self.key
+    }
+
+    pub fn into_key(self) -> T::Key {
+        self.key
+    }
+
+    /// Appends the item and returns a reference to it.
+    ///
+    /// The new item must have the same key as the entry.
+    pub fn insert(self, value: T) -> &'a mut T {
+        self.insert_entry(value).into_mut()
+    }
+
+    /// Appends the item and returns an `OccupiedEntry` for it.
+    ///
+    /// The new item must have the same key as the entry.
+    pub fn insert_entry(self, value: T) -> OccupiedEntry<'a, T, S, B> {
+        debug_assert_key_matches(&self.key, &value);
+        let VacantEntry { vec, key, hash } = self;
+        let index = vec.len();
+        let key = vec.store.push_unique(hash, Some(key), value);
+        OccupiedEntry { vec, index, key }
+    }
+
+    /// Inserts the item at the given index, shifting later items over, and
+    /// returns a reference to it.
+    ///
+    /// The new item must have the same key as the entry.
+    ///
+    /// ***Panics*** if `index > len`.
+    pub fn shift_insert(self, index: usize, value: T) -> &'a mut T {
+        debug_assert_key_matches(&self.key, &value);
+        let VacantEntry { vec, key, hash } = self;
+        let len = vec.len();
+        assert!(index <= len, "index out of bounds: the len is {len} but the index is {index}");
+        vec.store.push_unique(hash, Some(key), value);
+        vec.store.move_index(len, index);
+        vec.check.mark(index..=index);
+        vec.index_mut_unchecked(index)
+    }
+}
+
+impl<T: Lookup, S: BuildHasher, B: Backend> fmt::Debug for VacantEntry<'_, T, S, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("VacantEntry").field(self.key()).finish()
+    }
+}
+
+/// A view into an occupied entry in a `LookupVec`, obtained by index.
+///
+/// If the item's key is changed through `get_mut` or `into_mut`, it is handled
+/// according to the vec's `KeyPolicy` on the next call that needs the hash
+/// table.
+pub struct IndexedEntry<'a, T: Lookup, S: BuildHasher = RandomState, B: Backend = Indexed> {
+    vec: &'a mut LookupVec<T, S, B>,
+    index: usize,
+}
+
+impl<'a, T: Lookup, S: BuildHasher, B: Backend> IndexedEntry<'a, T, S, B> {
+    pub(crate) fn new(vec: &'a mut LookupVec<T, S, B>, index: usize) -> Self {
+        IndexedEntry { vec, index }
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn key(&self) -> B::Key<'_, T> {
+        self.vec.store.as_slice().keys().nth(self.index).unwrap()
+    }
+
+    pub fn get(&self) -> &T {
+        &self.vec[self.index]
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.vec.check.mark(self.index..=self.index);
+        self.vec.index_mut_unchecked(self.index)
+    }
+
+    pub fn into_mut(self) -> &'a mut T {
+        self.vec.check.mark(self.index..=self.index);
+        self.vec.index_mut_unchecked(self.index)
+    }
+
+    pub fn swap_remove(self) -> T {
+        self.vec.check.removed();
+        self.vec.store.swap_remove_index(self.index).unwrap()
+    }
+
+    pub fn shift_remove(self) -> T {
+        self.vec.check.removed();
+        self.vec.store.shift_remove_index(self.index).unwrap()
+    }
+
+    pub fn move_index(self, to: usize) {
+        self.vec.check.moved_to(to);
+        self.vec.store.move_index(self.index, to)
+    }
+
+    pub fn swap_indices(self, other: usize) {
+        self.vec.check.moved_to(other);
+        self.vec.store.swap_indices(self.index, other)
+    }
+
+    /// Replaces the item in the entry, returning the old one.
+    ///
+    /// The new item must have the same key as the entry.
+    pub fn insert(&mut self, value: T) -> T {
+        debug_assert_key_matches(&*self.key(), &value);
+        core::mem::replace(self.vec.index_mut_unchecked(self.index), value)
+    }
+}
+
+impl<T: Lookup + fmt::Debug, S: BuildHasher, B: Backend> fmt::Debug for IndexedEntry<'_, T, S, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IndexedEntry")
+            .field("index", &self.index())
+            .field("key", &*self.key())
+            .field("value", self.get())
+            .finish()
+    }
+}
+
+fn debug_assert_key_matches<T: Lookup>(key: &T::Key, value: &T) {
+    debug_assert!(
+        with_key(value, |k| k == key),
+        "item key {:?} does not match entry key {:?}",
+        value.key(),
+        key,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::LookupVec;
+    use crate::entry::Entry;
+    use crate::test_util::*;
+    use pretty_assertions::assert_eq;
+
+    // As of 7/2025 alloc::prelude is nightly-only
+    use alloc::borrow::ToOwned;
+    use alloc::vec::Vec;
+
+    fn values(vec: &LookupVec<TestItem>) -> Vec<(&str, i32)> {
+        vec.iter().map(|item| (item.id.as_str(), item.value)).collect()
+    }
+
+    #[test]
+    fn test_or_insert_with() {
+        let mut vec: LookupVec<TestItem> = lookupvec![item("a", 1)];
+
+        vec.entry("a".to_owned()).or_insert_with(|| item("a", 10)).value += 1;
+        vec.entry("b".to_owned()).or_insert_with(|| item("b", 10)).value += 1;
+        assert_eq!(values(&vec), vec![("a", 2), ("b", 11)]);
+    }
+
+    #[test]
+    fn test_or_insert_with_key() {
+        let mut vec: LookupVec<TestItem> = LookupVec::new();
+        let inserted = vec.entry("a".to_owned()).or_insert_with_key(|key| item(key, 5));
+        assert_eq!(inserted.value, 5);
+        assert_eq!(values(&vec), vec![("a", 5)]);
+    }
+
+    #[test]
+    fn test_and_modify() {
+        let mut vec: LookupVec<TestItem> = lookupvec![item("a", 1)];
+
+        for id in ["a", "b", "a"] {
+            vec.entry(id.to_owned())
+                .and_modify(|item| item.value += 1)
+                .or_insert_with_key(|key| item(key, 1));
+        }
+        assert_eq!(values(&vec), vec![("a", 3), ("b", 1)]);
+    }
+
+    #[test]
+    fn test_entry_index() {
+        let mut vec: LookupVec<TestItem> = lookupvec![item("a", 1), item("b", 2)];
+        assert_eq!(vec.entry("b".to_owned()).index(), 1);
+        assert_eq!(vec.entry("c".to_owned()).index(), 2);
+        assert_eq!(vec.entry("c".to_owned()).key(), "c");
+    }
+
+    #[test]
+    fn test_insert_entry() {
+        let mut vec: LookupVec<TestItem> = lookupvec![item("a", 1)];
+
+        let entry = vec.entry("a".to_owned()).insert_entry(item("a", 10));
+        assert_eq!(entry.index(), 0);
+        let entry = vec.entry("b".to_owned()).insert_entry(item("b", 20));
+        assert_eq!(entry.get().value, 20);
+        assert_eq!(values(&vec), vec![("a", 10), ("b", 20)]);
+    }
+
+    #[test]
+    fn test_occupied_and_vacant() {
+        let mut vec: LookupVec<TestItem> = lookupvec![item("a", 1), item("b", 2), item("c", 3)];
+
+        match vec.entry("b".to_owned()) {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.insert(item("b", 20)), item("b", 2));
+                entry.move_index(0);
+            },
+            Entry::Vacant(_) => panic!("expected occupied entry"),
+        }
+        assert_eq!(values(&vec), vec![("b", 20), ("a", 1), ("c", 3)]);
+
+        match vec.entry("d".to_owned()) {
+            Entry::Occupied(_) => panic!("expected vacant entry"),
+            Entry::Vacant(entry) => {
+                assert_eq!(entry.index(), 3);
+                entry.shift_insert(1, item("d", 4));
+            },
+        }
+        assert_eq!(values(&vec), vec![("b", 20), ("d", 4), ("a", 1), ("c", 3)]);
+
+        if let Entry::Occupied(entry) = vec.entry("a".to_owned()) {
+            assert_eq!(entry.shift_remove(), item("a", 1));
+        }
+        assert_eq!(values(&vec), vec![("b", 20), ("d", 4), ("c", 3)]);
+    }
+
+    #[test]
+    fn test_indexed_entries() {
+        let mut vec: LookupVec<TestItem> = lookupvec![item("a", 1), item("b", 2), item("c", 3)];
+
+        vec.first_entry().unwrap().get_mut().value = 10;
+        vec.last_entry().unwrap().get_mut().value = 30;
+        let entry = vec.get_index_entry(1).unwrap();
+        assert_eq!(entry.key(), "b");
+        entry.swap_remove();
+        assert!(vec.get_index_entry(2).is_none());
+        assert_eq!(values(&vec), vec![("a", 10), ("c", 30)]);
+
+        let mut empty: LookupVec<TestItem> = LookupVec::new();
+        assert!(empty.first_entry().is_none());
+        assert!(empty.last_entry().is_none());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "does not match entry key")]
+    fn test_vacant_insert_key_mismatch() {
+        let mut vec: LookupVec<TestItem> = LookupVec::new();
+        vec.entry("a".to_owned()).or_insert_with(|| item("b", 1));
+    }
+}
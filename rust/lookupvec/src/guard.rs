@@ -0,0 +1,508 @@
+use crate::backend::Backend;
+use crate::backend::Indexed;
+use crate::backend::store::Store;
+use crate::core::Lookup;
+use crate::slice::Slice;
+use crate::vec::LookupVec;
+
+use ahash::random_state::RandomState;
+
+use core::fmt;
+use core::hash::BuildHasher;
+use core::ops::Bound;
+use core::ops::Deref;
+use core::ops::DerefMut;
+use core::ops::Range;
+use core::ops::RangeBounds;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering;
+
+/// What a `LookupVec` does when a mutable borrow changes an item's key.
+///
+/// Items handed out through `RefMut`, `ItemMut` or `SliceMut` are checked
+/// when the guard is dropped. Items handed out as a plain `&mut T`
+/// (`IndexMut` and entries) are checked on the next call that takes
+/// `&mut self`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyPolicy {
+    /// Re-key the item under its new key, keeping its position.
+    ///
+    /// ***Panics*** if the new key belongs to another item.
+    #[default]
+    Rekey,
+    /// Panic if an item's key has changed.
+    Panic,
+    /// Like `Panic`, but only checked when debug assertions are enabled.
+    DebugAssert,
+}
+
+impl KeyPolicy {
+    pub(crate) fn is_checked(self) -> bool {
+        self != KeyPolicy::DebugAssert || cfg!(debug_assertions)
+    }
+}
+
+/// Returns true if the thread is unwinding, in which case guards record a
+/// changed key for the next repair rather than panicking again.
+#[cfg(feature = "std")]
+pub(crate) fn panicking() -> bool {
+    std::thread::panicking()
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn panicking() -> bool {
+    false
+}
+
+/// Tracks which items may have had their key changed without being
+/// re-keyed yet.
+#[derive(Default)]
+pub(crate) struct KeyCheck {
+    pub(crate) policy: KeyPolicy,
+    /// Items handed out as a plain `&mut T` since the last repair.
+    stale: Option<Range<usize>>,
+    /// Set when an `ItemMut` drops an item whose key changed. The item can't
+    /// be re-keyed while the vec is being iterated, so every item is treated
+    /// as stale until the next repair.
+    changed: AtomicBool,
+}
+
+impl KeyCheck {
+    pub(crate) const fn new(policy: KeyPolicy) -> Self {
+        KeyCheck {
+            policy,
+            stale: None,
+            changed: AtomicBool::new(false),
+        }
+    }
+
+    /// Records that items in `range` were handed out mutably.
+    pub(crate) fn mark<R: RangeBounds<usize>>(&mut self, range: R) {
+        if !self.policy.is_checked() {
+            return;
+        }
+        let start = match range.start_bound() {
+            Bound::Included(&i) => i,
+            Bound::Excluded(&i) => i + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&i) => i + 1,
+            Bound::Excluded(&i) => i,
+            Bound::Unbounded => usize::MAX,
+        };
+        self.stale = match self.stale.take() {
+            Some(stale) => Some(stale.start.min(start)..stale.end.max(end)),
+            None => Some(start..end),
+        };
+    }
+
+    /// Records that the only stale item, if any, moved to `to`.
+    pub(crate) fn moved_to(&mut self, to: usize) {
+        if self.stale.is_some() {
+            self.stale = Some(to..to + 1);
+        }
+    }
+
+    /// Records that the only stale item, if any, was removed.
+    pub(crate) fn removed(&mut self) {
+        self.stale = None;
+    }
+
+    /// Applies the policy to an item handed out by an iterator, which was
+    /// filed under `slot`.
+    pub(crate) fn check_slot<B: Backend, T: Lookup, S: BuildHasher>(&self, index: usize, slot: B::Slot<'_, T, S>, item: &T) {
+        if self.policy.is_checked() && !B::slot_holds(slot, index, item) {
+            self.item_changed(index, B::slot_key(slot), item);
+        }
+    }
+
+    /// Applies the policy to an item known to have a new key. `old_key` is
+    /// the key it was stored under, if the backend kept it.
+    pub(crate) fn item_changed<T: Lookup>(&self, index: usize, old_key: Option<&T::Key>, item: &T) {
+        match (self.policy, old_key) {
+            (KeyPolicy::Panic | KeyPolicy::DebugAssert, Some(key)) if !panicking() => {
+                panic!("key of item at index {index} changed from {key:?} to {:?}", item.key());
+            },
+            (KeyPolicy::Panic | KeyPolicy::DebugAssert, None) if !panicking() => {
+                panic!("key of item at index {index} changed to {:?}", item.key());
+            },
+            _ => self.changed.store(true, Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn stale_range(&self, len: usize) -> Option<Range<usize>> {
+        if self.changed.load(Ordering::Relaxed) {
+            return Some(0..len);
+        }
+        self.stale.as_ref().map(|stale| stale.start.min(len)..stale.end.min(len))
+    }
+
+    pub(crate) fn take_stale(&mut self, len: usize) -> Option<Range<usize>> {
+        let range = self.stale_range(len);
+        self.stale = None;
+        *self.changed.get_mut() = false;
+        range
+    }
+}
+
+impl Clone for KeyCheck {
+    fn clone(&self) -> Self {
+        KeyCheck {
+            policy: self.policy,
+            stale: self.stale.clone(),
+            changed: AtomicBool::new(self.changed.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Converts `range` to a `Range`, or returns `None` if it is out of bounds
+/// for a sequence of length `len`.
+pub(crate) fn simplify_range<R: RangeBounds<usize>>(range: R, len: usize) -> Option<Range<usize>> {
+    let start = match range.start_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&i) => i.checked_add(1)?,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&i) => i.checked_add(1)?,
+        Bound::Excluded(&i) => i,
+        Bound::Unbounded => len,
+    };
+    (start <= end && end <= len).then_some(start..end)
+}
+
+/// Like `simplify_range`, for indexing.
+///
+/// ***Panics*** if `range` is out of bounds.
+pub(crate) fn index_range<R: RangeBounds<usize>>(range: R, len: usize) -> Range<usize> {
+    simplify_range(range, len).unwrap_or_else(|| panic!("range out of bounds: the len is {len}"))
+}
+
+/// Mutable reference to an item in a `LookupVec` that re-checks the item's
+/// key when dropped, according to the vec's `KeyPolicy`.
+pub struct RefMut<'a, T: Lookup, S: BuildHasher = RandomState, B: Backend = Indexed> {
+    vec: &'a mut LookupVec<T, S, B>,
+    index: usize,
+    /// The hash the item was filed under, for backends that need it to find
+    /// the item's slot again.
+    hash: Option<u64>,
+}
+
+impl<'a, T: Lookup, S: BuildHasher, B: Backend> RefMut<'a, T, S, B> {
+    pub(crate) fn new(vec: &'a mut LookupVec<T, S, B>, index: usize) -> Self {
+        let hash = vec.store.slot_hash(index);
+        RefMut { vec, index, hash }
+    }
+
+    /// Returns the index of the item.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<T: Lookup, S: BuildHasher, B: Backend> Deref for RefMut<'_, T, S, B> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.vec[self.index]
+    }
+}
+
+impl<T: Lookup, S: BuildHasher, B: Backend> DerefMut for RefMut<'_, T, S, B> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.vec.index_mut_unchecked(self.index)
+    }
+}
+
+impl<T: Lookup, S: BuildHasher, B: Backend> Drop for RefMut<'_, T, S, B> {
+    fn drop(&mut self) {
+        self.vec.check_index(self.index, self.hash);
+    }
+}
+
+impl<T: Lookup + fmt::Debug, S: BuildHasher, B: Backend> fmt::Debug for RefMut<'_, T, S, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// Mutable reference to an item from `LookupVec::iter_mut`, which checks the
+/// item's key when dropped.
+///
+/// Under `KeyPolicy::Rekey`, an item whose key changed can't be re-keyed
+/// while the vec is being iterated. It is re-keyed by the next call that
+/// takes `&mut self`, and until then lookups check every item.
+pub struct ItemMut<'a, T: Lookup + 'a, S: BuildHasher + 'a = RandomState, B: Backend = Indexed> {
+    slot: B::Slot<'a, T, S>,
+    item: &'a mut T,
+    index: usize,
+    check: &'a KeyCheck,
+}
+
+impl<'a, T: Lookup, S: BuildHasher, B: Backend> ItemMut<'a, T, S, B> {
+    pub(crate) fn new(index: usize, slot: B::Slot<'a, T, S>, item: &'a mut T, check: &'a KeyCheck) -> Self {
+        ItemMut { slot, item, index, check }
+    }
+
+    /// Returns the index of the item.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<T: Lookup, S: BuildHasher, B: Backend> Deref for ItemMut<'_, T, S, B> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.item
+    }
+}
+
+impl<T: Lookup, S: BuildHasher, B: Backend> DerefMut for ItemMut<'_, T, S, B> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.item
+    }
+}
+
+impl<T: Lookup, S: BuildHasher, B: Backend> Drop for ItemMut<'_, T, S, B> {
+    fn drop(&mut self) {
+        self.check.check_slot::<B, T, S>(self.index, self.slot, self.item);
+    }
+}
+
+impl<T: Lookup + fmt::Debug, S: BuildHasher, B: Backend> fmt::Debug for ItemMut<'_, T, S, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// Mutable slice of a `LookupVec` that re-checks the keys of its items when
+/// dropped, according to the vec's `KeyPolicy`.
+pub struct SliceMut<'a, T: Lookup, S: BuildHasher = RandomState, B: Backend = Indexed> {
+    vec: &'a mut LookupVec<T, S, B>,
+    range: Range<usize>,
+}
+
+impl<'a, T: Lookup, S: BuildHasher, B: Backend> SliceMut<'a, T, S, B> {
+    pub(crate) fn new(vec: &'a mut LookupVec<T, S, B>, range: Range<usize>) -> Self {
+        SliceMut { vec, range }
+    }
+}
+
+impl<T: Lookup, S: BuildHasher, B: Backend> Deref for SliceMut<'_, T, S, B> {
+    type Target = Slice<T, B>;
+
+    fn deref(&self) -> &Slice<T, B> {
+        &self.vec[self.range.clone()]
+    }
+}
+
+impl<T: Lookup, S: BuildHasher, B: Backend> DerefMut for SliceMut<'_, T, S, B> {
+    fn deref_mut(&mut self) -> &mut Slice<T, B> {
+        self.vec.range_mut_unchecked(self.range.clone())
+    }
+}
+
+impl<T: Lookup, S: BuildHasher, B: Backend> Drop for SliceMut<'_, T, S, B> {
+    fn drop(&mut self) {
+        self.vec.check_range(self.range.clone());
+    }
+}
+
+impl<T: Lookup + fmt::Debug, S: BuildHasher, B: Backend> fmt::Debug for SliceMut<'_, T, S, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::guard::KeyPolicy;
+    use crate::LookupVec;
+    use crate::test_util::*;
+    use pretty_assertions::assert_eq;
+
+    // As of 7/2025 alloc::prelude is nightly-only
+    use alloc::borrow::ToOwned;
+
+    fn policy_vec(policy: KeyPolicy) -> LookupVec<TestItem> {
+        LookupVec::from([item("a", 1), item("b", 2), item("c", 3)]).with_key_policy(policy)
+    }
+
+    #[test]
+    fn test_get_mut_rekeys() {
+        let mut vec = policy_vec(KeyPolicy::Rekey);
+        vec.get_mut("b").unwrap().id = "x".to_owned();
+
+        assert_keys_eq!(vec, "a", "x", "c");
+        assert!(vec.get("b").is_none());
+        assert_eq!(vec.get("x").unwrap().value, 2);
+        assert_eq!(vec.get_index_of("x"), Some(1));
+    }
+
+    #[test]
+    fn test_get_index_mut_rekeys() {
+        let mut vec = policy_vec(KeyPolicy::Rekey);
+        vec.get_index_mut(0).unwrap().id = "x".to_owned();
+        vec.last_mut().unwrap().id = "y".to_owned();
+        vec.first_mut().unwrap().value = 10;
+
+        assert_keys_eq!(vec, "x", "b", "y");
+        assert_eq!(vec.get("x").unwrap().value, 10);
+        assert!(vec.validate().is_empty());
+    }
+
+    #[test]
+    fn test_ref_mut_unchanged_key() {
+        let mut vec = policy_vec(KeyPolicy::Panic);
+        let mut item = vec.get_mut("b").unwrap();
+        assert_eq!(item.index(), 1);
+        item.value = 20;
+        drop(item);
+        assert_eq!(vec.get("b").unwrap().value, 20);
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate key")]
+    fn test_rekey_duplicate_panics() {
+        let mut vec = policy_vec(KeyPolicy::Rekey);
+        vec.get_mut("b").unwrap().id = "a".to_owned();
+    }
+
+    #[test]
+    #[should_panic(expected = "changed from")]
+    fn test_get_mut_panic_policy() {
+        let mut vec = policy_vec(KeyPolicy::Panic);
+        vec.get_mut("b").unwrap().id = "x".to_owned();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "changed from")]
+    fn test_get_mut_debug_assert_policy() {
+        let mut vec = policy_vec(KeyPolicy::DebugAssert);
+        vec.get_mut("b").unwrap().id = "x".to_owned();
+    }
+
+    #[test]
+    fn test_iter_mut_rekeys() {
+        let mut vec = policy_vec(KeyPolicy::Rekey);
+        for mut item in vec.iter_mut() {
+            item.id = item.id.to_uppercase();
+        }
+
+        // Lookups are correct before the vec is repaired...
+        assert_eq!(vec.get("B").unwrap().value, 2);
+        assert!(!vec.contains_key("b"));
+
+        // ...and the next mutating call repairs it
+        vec.push(item("d", 4));
+        assert_keys_eq!(vec, "A", "B", "C", "d");
+        assert!(vec.validate().is_empty());
+    }
+
+    #[test]
+    fn test_index_mut_rekeys() {
+        let mut vec = policy_vec(KeyPolicy::Rekey);
+        vec[1].id = "x".to_owned();
+        assert_eq!(vec.validate(), vec![1]);
+        assert_eq!(vec.get("x").unwrap().value, 2);
+        assert!(vec.get("b").is_none());
+
+        vec.swap_indices(0, 1);
+        vec.shift_remove("c");
+        assert_keys_eq!(vec, "x", "a");
+    }
+
+    #[test]
+    fn test_entry_rekeys() {
+        let mut vec = policy_vec(KeyPolicy::Rekey);
+        vec.entry("a".to_owned()).and_modify(|item| item.id = "x".to_owned());
+        vec.push(item("a", 10));
+        assert_keys_eq!(vec, "x", "b", "c", "a");
+    }
+
+    #[test]
+    fn test_slice_mut_rekeys() {
+        let mut vec = policy_vec(KeyPolicy::Rekey);
+        vec[1..][1].id = "x".to_owned();
+        vec.rekey_all();
+        assert_keys_eq!(vec, "a", "b", "x");
+    }
+
+    #[test]
+    fn test_get_range_mut_rekeys_on_drop() {
+        let mut vec = policy_vec(KeyPolicy::Rekey);
+        vec.get_range_mut(1..).unwrap()[1].id = "x".to_owned();
+        assert!(vec.validate().is_empty());
+        assert_keys_eq!(vec, "a", "b", "x");
+
+        vec.as_mut_slice().iter_mut().for_each(|item| item.value += 1);
+        assert!(vec.validate().is_empty());
+        assert_eq!(vec.get("x").unwrap().value, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "changed from")]
+    fn test_get_range_mut_panic_policy() {
+        let mut vec = policy_vec(KeyPolicy::Panic);
+        vec.get_range_mut(..2).unwrap()[0].id = "x".to_owned();
+    }
+
+    #[test]
+    fn test_keys_report_changed_key() {
+        let mut vec = policy_vec(KeyPolicy::Rekey);
+        vec[1].id = "x".to_owned();
+        assert_keys_eq!(vec, "a", "x", "c");
+    }
+
+    #[test]
+    fn test_debug_shows_items() {
+        let vec = policy_vec(KeyPolicy::Rekey);
+        assert_eq!(format!("{vec:?}"), format!("{:?}", [item("a", 1), item("b", 2), item("c", 3)]));
+    }
+
+    #[test]
+    #[should_panic(expected = "changed from")]
+    fn test_iter_mut_panic_policy() {
+        let mut vec = policy_vec(KeyPolicy::Panic);
+        for mut item in vec.iter_mut() {
+            item.id = item.id.to_uppercase();
+        }
+        vec.push(item("d", 4));
+    }
+
+    #[test]
+    fn test_rekey_all_swapped_keys() {
+        let mut vec = policy_vec(KeyPolicy::Rekey);
+        let slice = &mut vec[0..2];
+        slice[0].id = "b".to_owned();
+        slice[1].id = "a".to_owned();
+        assert_eq!(vec.validate(), vec![0, 1]);
+        assert_eq!(vec.get("a").unwrap().value, 2);
+
+        assert_eq!(vec.rekey_all(), 2);
+        assert_keys_eq!(vec, "b", "a", "c");
+        assert_eq!(vec.get("a").unwrap().value, 2);
+        assert_eq!(vec.rekey_all(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_rekey_all_duplicate_keeps_items() {
+        use std::panic::AssertUnwindSafe;
+        use std::panic::catch_unwind;
+        use std::vec::Vec;
+
+        let mut vec = policy_vec(KeyPolicy::Rekey);
+        vec[0..2][0].id = "b".to_owned();
+        let result = catch_unwind(AssertUnwindSafe(|| vec.rekey_all()));
+        assert!(result.is_err());
+        assert_eq!(vec.len(), 3);
+        assert_eq!(vec.validate(), vec![0]);
+        assert_eq!(vec.get_index_of("c"), Some(2));
+        let values: Vec<i32> = vec.iter().map(|item| item.value).collect();
+        assert_eq!(values, vec![1, 2, 3]);
+    }
+}
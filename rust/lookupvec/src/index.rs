@@ -0,0 +1,774 @@
+//! Secondary indexes, for looking items up by fields other than their key.
+//!
+//! Mark fields with `#[lookup_index]` (or `#[lookup_index(unique)]`) when
+//! deriving `Lookup`, and store the items in a `MultiIndexLookupVec`:
+//!
+//! ```
+//! # #[cfg(feature = "derive")] {
+//! # use lookupvec::Lookup;
+//! # use lookupvec::MultiIndexLookupVec;
+//! #[derive(Lookup)]
+//! struct User {
+//!     #[lookup_key]
+//!     id: u64,
+//!     #[lookup_index(unique)]
+//!     email: String,
+//!     #[lookup_index]
+//!     team: String,
+//! }
+//!
+//! let users = MultiIndexLookupVec::from([
+//!     User { id: 1, email: "a@example.com".to_owned(), team: "core".to_owned() },
+//!     User { id: 2, email: "b@example.com".to_owned(), team: "core".to_owned() },
+//! ]);
+//! let user = users.get_by(User::EMAIL_INDEX, "a@example.com");
+//! let team = users.get_all_by(User::TEAM_INDEX, "core").count();
+//! assert_eq!((user.unwrap().id, team), (1, 2));
+//! # }
+//! ```
+//!
+//! The derive adds a `<FIELD>_INDEX` associated const for each indexed field.
+
+use crate::core::Lookup;
+use crate::core::with_key;
+use crate::guard::KeyPolicy;
+use crate::guard::panicking;
+use crate::iter::Iter;
+use crate::iter::IntoIter;
+use crate::iter::Keys;
+use crate::slice::Slice;
+use crate::vec::LookupVec;
+
+use ahash::random_state::RandomState;
+use delegate::delegate;
+use hashbrown::HashTable;
+use indexmap::Equivalent;
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::BuildHasher;
+use core::hash::Hash;
+use core::hash::Hasher;
+use core::ops::Bound;
+use core::ops::Deref;
+use core::ops::DerefMut;
+use core::ops::Index;
+use core::ops::RangeBounds;
+
+/// Items with secondary indexes, usually implemented with
+/// `#[derive(Lookup)]` and `#[lookup_index]`.
+pub trait LookupIndexes: Lookup + Sized {
+    const INDEX_COUNT: usize;
+
+    /// Describes the secondary index at position `id`, counting in
+    /// declaration order.
+    fn index_info(id: usize) -> IndexInfo<Self>;
+}
+
+/// Describes one secondary index of `T`, without naming the indexed type.
+pub struct IndexInfo<T> {
+    pub name: &'static str,
+    pub unique: bool,
+    /// Feeds the item's indexed value to the hasher.
+    pub hash: fn(&T, &mut dyn Hasher),
+    /// Returns whether two items have the same indexed value.
+    pub eq: fn(&T, &T) -> bool,
+}
+
+impl<T> Clone for IndexInfo<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for IndexInfo<T> {}
+
+/// Handle to a secondary index of `T` on a value of type `K`, used to query a
+/// `MultiIndexLookupVec`.
+pub struct IndexField<T, K> {
+    id: usize,
+    get: fn(&T) -> &K,
+}
+
+impl<T, K> IndexField<T, K> {
+    /// `id` is the position of the index, as passed to
+    /// `LookupIndexes::index_info()`.
+    pub const fn new(id: usize, get: fn(&T) -> &K) -> Self {
+        IndexField { id, get }
+    }
+}
+
+impl<T, K> Clone for IndexField<T, K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, K> Copy for IndexField<T, K> {}
+
+impl<T, K> fmt::Debug for IndexField<T, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IndexField").field("id", &self.id).finish()
+    }
+}
+
+fn hash_with<S: BuildHasher>(hasher: &S, f: impl FnOnce(&mut dyn Hasher)) -> u64 {
+    let mut state = hasher.build_hasher();
+    f(&mut state);
+    state.finish()
+}
+
+fn hash_index<T: LookupIndexes, S: BuildHasher>(hasher: &S, id: usize, item: &T) -> u64 {
+    hash_with(hasher, |state| (T::index_info(id).hash)(item, state))
+}
+
+/// A `LookupVec` that also keeps secondary indexes on its items up to date.
+///
+/// Each index is a hash table of item positions. Unique indexes reject two
+/// items with the same value; non-unique indexes allow any number.
+#[derive(Debug, Clone)]
+pub struct MultiIndexLookupVec<T: LookupIndexes, S = RandomState> {
+    vec: LookupVec<T, S>,
+    indexes: Vec<HashTable<usize>>,
+}
+
+impl<T: LookupIndexes, S: Default> MultiIndexLookupVec<T, S> {
+    pub fn new() -> Self {
+        Self::with_hasher(S::default())
+    }
+
+    pub fn with_capacity(n: usize) -> Self {
+        Self::with_capacity_and_hasher(n, S::default())
+    }
+}
+
+impl<T: LookupIndexes, S> MultiIndexLookupVec<T, S> {
+    pub fn with_hasher(hasher: S) -> Self {
+        MultiIndexLookupVec {
+            vec: LookupVec::with_hasher(hasher),
+            indexes: (0..T::INDEX_COUNT).map(|_| HashTable::new()).collect(),
+        }
+    }
+
+    pub fn with_capacity_and_hasher(n: usize, hasher: S) -> Self {
+        MultiIndexLookupVec {
+            vec: LookupVec::with_capacity_and_hasher(n, hasher),
+            indexes: (0..T::INDEX_COUNT).map(|_| HashTable::with_capacity(n)).collect(),
+        }
+    }
+
+    /// Sets what happens when a mutable borrow changes an item's key.
+    pub fn with_key_policy(mut self, policy: KeyPolicy) -> Self {
+        self.vec = self.vec.with_key_policy(policy);
+        self
+    }
+
+    pub fn as_lookup_vec(&self) -> &LookupVec<T, S> {
+        &self.vec
+    }
+
+    pub fn into_lookup_vec(self) -> LookupVec<T, S> {
+        self.vec
+    }
+
+    delegate![
+        to self.vec {
+            pub fn key_policy(&self) -> KeyPolicy;
+            pub fn len(&self) -> usize;
+            pub fn is_empty(&self) -> bool;
+            pub fn hasher(&self) -> &S;
+            pub fn capacity(&self) -> usize;
+            pub fn get_index(&self, index: usize) -> Option<&T>;
+            pub fn first(&self) -> Option<&T>;
+            pub fn last(&self) -> Option<&T>;
+            pub fn as_slice(&self) -> &Slice<T>;
+            pub fn iter(&self) -> Iter<'_, T>;
+            pub fn keys(&self) -> Keys<'_, T>;
+        }
+    ];
+
+    /// Applies `f` to the index of every entry of every secondary index,
+    /// dropping the entries for which it returns `None`.
+    fn remap_indexes(&mut self, mut f: impl FnMut(usize) -> Option<usize>) {
+        for table in &mut self.indexes {
+            table.retain(|index| match f(*index) {
+                Some(new_index) => {
+                    *index = new_index;
+                    true
+                },
+                None => false,
+            });
+        }
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        self.remap_indexes(|index| (index < len).then_some(index));
+        self.vec.truncate(len);
+    }
+
+    pub fn clear(&mut self) {
+        self.indexes.iter_mut().for_each(HashTable::clear);
+        self.vec.clear();
+    }
+}
+
+impl<T: LookupIndexes, S: BuildHasher> MultiIndexLookupVec<T, S> {
+    delegate![
+        to self.vec {
+            pub fn get<Q>(&self, key: &Q) -> Option<&T>
+            where Q: ?Sized + Hash + Equivalent<T::Key>;
+            pub fn get_index_of<Q>(&self, key: &Q) -> Option<usize>
+            where Q: ?Sized + Hash + Equivalent<T::Key>;
+            pub fn contains_key<Q>(&self, key: &Q) -> bool
+            where Q: ?Sized + Hash + Equivalent<T::Key>;
+            pub fn contains(&self, value: &T) -> bool;
+        }
+    ];
+
+    fn hash_query<K, Q>(&self, key: &Q) -> u64
+    where Q: ?Sized + Hash + Equivalent<K> {
+        hash_with(self.vec.hasher(), |mut state| key.hash(&mut state))
+    }
+
+    /// Returns the item whose indexed value is `key`. For a non-unique index,
+    /// returns any one of the matching items.
+    pub fn get_by<K, Q>(&self, index: IndexField<T, K>, key: &Q) -> Option<&T>
+    where Q: ?Sized + Hash + Equivalent<K> {
+        self.get_index_of_by(index, key).map(|i| &self.vec[i])
+    }
+
+    /// Returns the position of the item whose indexed value is `key`. For a
+    /// non-unique index, returns any one of the matching positions.
+    pub fn get_index_of_by<K, Q>(&self, index: IndexField<T, K>, key: &Q) -> Option<usize>
+    where Q: ?Sized + Hash + Equivalent<K> {
+        let hash = self.hash_query(key);
+        self.indexes[index.id]
+            .find(hash, |&i| key.equivalent((index.get)(&self.vec[i])))
+            .copied()
+    }
+
+    /// Returns every item whose indexed value is `key`, in order.
+    pub fn get_all_by<K, Q>(&self, index: IndexField<T, K>, key: &Q) -> impl Iterator<Item = &T>
+    where Q: ?Sized + Hash + Equivalent<K> {
+        let hash = self.hash_query(key);
+        let mut indices: Vec<usize> = self.indexes[index.id]
+            .iter_hash(hash)
+            .copied()
+            .filter(|&i| key.equivalent((index.get)(&self.vec[i])))
+            .collect();
+        indices.sort_unstable();
+        indices.into_iter().map(|i| &self.vec[i])
+    }
+
+    pub fn contains_by<K, Q>(&self, index: IndexField<T, K>, key: &Q) -> bool
+    where Q: ?Sized + Hash + Equivalent<K> {
+        self.get_index_of_by(index, key).is_some()
+    }
+
+    /// Returns the index of an item other than the one at `except` that has
+    /// the same value as `item` for a unique index, with the index's name.
+    fn find_unique_conflict(&self, item: &T, except: Option<usize>) -> Option<(usize, &'static str)> {
+        (0..T::INDEX_COUNT).find_map(|id| {
+            let info = T::index_info(id);
+            if !info.unique {
+                return None;
+            }
+            let hash = hash_index(self.vec.hasher(), id, item);
+            self.indexes[id]
+                .find(hash, |&i| Some(i) != except && (info.eq)(item, &self.vec[i]))
+                .map(|&i| (i, info.name))
+        })
+    }
+
+    /// Panics if `item` has the same value for a unique index as an item other
+    /// than the one at `except`.
+    fn check_unique(&self, item: &T, except: Option<usize>) {
+        if let Some((i, name)) = self.find_unique_conflict(item, except) {
+            panic!("item at index {i} already has this value for unique index `{name}`");
+        }
+    }
+
+    /// Removes the item at `index` from every secondary index.
+    fn unindex(&mut self, index: usize) {
+        let MultiIndexLookupVec { vec, indexes } = self;
+        for (id, table) in indexes.iter_mut().enumerate() {
+            let hash = hash_index(vec.hasher(), id, &vec[index]);
+            if let Ok(entry) = table.find_entry(hash, |&i| i == index) {
+                entry.remove();
+            }
+        }
+    }
+
+    /// Adds the item at `index` to every secondary index.
+    fn reindex(&mut self, index: usize) {
+        let MultiIndexLookupVec { vec, indexes } = self;
+        for (id, table) in indexes.iter_mut().enumerate() {
+            let hash = hash_index(vec.hasher(), id, &vec[index]);
+            table.insert_unique(hash, index, |&i| hash_index(vec.hasher(), id, &vec[i]));
+        }
+    }
+
+    /// Changes the position stored for the item at `from` to `to`.
+    fn move_indexed(&mut self, from: usize, to: usize) {
+        let MultiIndexLookupVec { vec, indexes } = self;
+        for (id, table) in indexes.iter_mut().enumerate() {
+            let hash = hash_index(vec.hasher(), id, &vec[from]);
+            if let Some(i) = table.find_mut(hash, |&i| i == from) {
+                *i = to;
+            }
+        }
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        let MultiIndexLookupVec { vec, indexes } = self;
+        vec.reserve(additional);
+        for (id, table) in indexes.iter_mut().enumerate() {
+            table.reserve(additional, |&i| hash_index(vec.hasher(), id, &vec[i]));
+        }
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        let MultiIndexLookupVec { vec, indexes } = self;
+        vec.shrink_to_fit();
+        for (id, table) in indexes.iter_mut().enumerate() {
+            table.shrink_to_fit(|&i| hash_index(vec.hasher(), id, &vec[i]));
+        }
+    }
+
+    /// Returns a mutable reference to the item with key `key`. Secondary
+    /// indexes are updated when the reference is dropped.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<RefMut<'_, T, S>>
+    where Q: ?Sized + Hash + Equivalent<T::Key> {
+        let index = self.get_index_of(key)?;
+        Some(RefMut::new(self, index))
+    }
+
+    pub fn get_index_mut(&mut self, index: usize) -> Option<RefMut<'_, T, S>> {
+        if index < self.len() {
+            Some(RefMut::new(self, index))
+        } else {
+            None
+        }
+    }
+
+    pub fn push(&mut self, value: T) -> Option<T> {
+        self.push_full(value).1
+    }
+
+    /// Pushes the item, or replaces the item with the same key in place.
+    ///
+    /// ***Panics*** if another item has the same value for a unique index.
+    pub fn push_full(&mut self, value: T) -> (usize, Option<T>) {
+        let existing = with_key(&value, |key| self.vec.get_index_of(key));
+        self.check_unique(&value, existing);
+        if let Some(index) = existing {
+            self.unindex(index);
+        }
+        let (index, replaced) = self.vec.push_full(value);
+        self.reindex(index);
+        (index, replaced)
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.swap_remove_index(self.len().checked_sub(1)?)
+    }
+
+    pub fn shift_remove<Q>(&mut self, key: &Q) -> Option<T>
+    where Q: ?Sized + Hash + Equivalent<T::Key> {
+        let index = self.get_index_of(key)?;
+        self.shift_remove_index(index)
+    }
+
+    pub fn swap_remove<Q>(&mut self, key: &Q) -> Option<T>
+    where Q: ?Sized + Hash + Equivalent<T::Key> {
+        let index = self.get_index_of(key)?;
+        self.swap_remove_index(index)
+    }
+
+    pub fn shift_remove_index(&mut self, index: usize) -> Option<T> {
+        if index >= self.len() {
+            return None;
+        }
+        self.unindex(index);
+        self.remap_indexes(|i| Some(if i > index { i - 1 } else { i }));
+        self.vec.shift_remove_index(index)
+    }
+
+    pub fn swap_remove_index(&mut self, index: usize) -> Option<T> {
+        let last = self.len().checked_sub(1).filter(|&last| index <= last)?;
+        self.unindex(index);
+        if index != last {
+            self.move_indexed(last, index);
+        }
+        self.vec.swap_remove_index(index)
+    }
+
+    pub fn drain<R>(&mut self, range: R) -> crate::iter::Drain<'_, T>
+    where R: RangeBounds<usize> {
+        let start = match range.start_bound() {
+            Bound::Included(&i) => i,
+            Bound::Excluded(&i) => i + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&i) => i + 1,
+            Bound::Excluded(&i) => i,
+            Bound::Unbounded => self.len(),
+        };
+        if start < end && end <= self.len() {
+            self.remap_indexes(|index| {
+                if index < start {
+                    Some(index)
+                } else if index >= end {
+                    Some(index - (end - start))
+                } else {
+                    None
+                }
+            });
+        }
+        self.vec.drain(range)
+    }
+
+    /// Keeps only the items for which `keep` returns true, in order.
+    pub fn retain<F>(&mut self, mut keep: F)
+    where F: FnMut(&T) -> bool {
+        // Record which items are kept, then map each kept item's old index to
+        // the number of kept items before it
+        let mut kept = Vec::with_capacity(self.len());
+        self.vec.retain(|item| {
+            let keep = keep(item);
+            kept.push(keep);
+            keep
+        });
+        let mut new_indices = Vec::with_capacity(kept.len());
+        let mut next = 0;
+        for keep in kept {
+            new_indices.push(keep.then_some(next));
+            next += usize::from(keep);
+        }
+        self.remap_indexes(|index| new_indices[index]);
+    }
+}
+
+impl<'a, T: LookupIndexes, S> IntoIterator for &'a MultiIndexLookupVec<T, S> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: LookupIndexes, S> IntoIterator for MultiIndexLookupVec<T, S> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.vec.into_iter()
+    }
+}
+
+impl<T: LookupIndexes, S: BuildHasher + Default> FromIterator<T> for MultiIndexLookupVec<T, S> {
+    fn from_iter<I: IntoIterator<Item = T>>(iterable: I) -> Self {
+        let iter = iterable.into_iter();
+        let (low, _) = iter.size_hint();
+        let mut vec = Self::with_capacity_and_hasher(low, <_>::default());
+        vec.extend(iter);
+        vec
+    }
+}
+
+impl<T: LookupIndexes, const N: usize> From<[T; N]> for MultiIndexLookupVec<T> {
+    fn from(arr: [T; N]) -> Self {
+        Self::from_iter(arr)
+    }
+}
+
+impl<T: LookupIndexes, S: BuildHasher> From<LookupVec<T, S>> for MultiIndexLookupVec<T, S> {
+    /// ***Panics*** if two items have the same value for a unique index.
+    fn from(vec: LookupVec<T, S>) -> Self {
+        let mut indexes = MultiIndexLookupVec {
+            indexes: (0..T::INDEX_COUNT).map(|_| HashTable::with_capacity(vec.len())).collect(),
+            vec,
+        };
+        for index in 0..indexes.len() {
+            indexes.check_unique(&indexes.vec[index], None);
+            indexes.reindex(index);
+        }
+        indexes
+    }
+}
+
+impl<T: LookupIndexes, S: Default> Default for MultiIndexLookupVec<T, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: LookupIndexes, S: BuildHasher> Extend<T> for MultiIndexLookupVec<T, S> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iterable: I) {
+        // See `LookupVec`'s impl for the reservation logic
+        let iter = iterable.into_iter();
+        let reserve = if self.is_empty() {
+            iter.size_hint().0
+        } else {
+            iter.size_hint().0.div_ceil(2)
+        };
+        self.reserve(reserve);
+        iter.for_each(move |t| {
+            self.push(t);
+        });
+    }
+}
+
+impl<T: LookupIndexes, S> Index<usize> for MultiIndexLookupVec<T, S> {
+    type Output = T;
+
+    /// ***Panics*** if `index` is out of bounds.
+    fn index(&self, index: usize) -> &T {
+        &self.vec[index]
+    }
+}
+
+/// Mutable reference to an item in a `MultiIndexLookupVec`. When dropped, the
+/// item's key is re-checked according to the vec's `KeyPolicy` and its
+/// secondary indexes are updated.
+///
+/// ***Panics*** on drop if the item now has the same value for a unique index
+/// as another item. The item is indexed again before that, so the vec stays
+/// usable after the panic is caught, with both items found by that value.
+pub struct RefMut<'a, T: LookupIndexes, S: BuildHasher = RandomState> {
+    vec: &'a mut MultiIndexLookupVec<T, S>,
+    index: usize,
+}
+
+impl<'a, T: LookupIndexes, S: BuildHasher> RefMut<'a, T, S> {
+    fn new(vec: &'a mut MultiIndexLookupVec<T, S>, index: usize) -> Self {
+        vec.unindex(index);
+        RefMut { vec, index }
+    }
+
+    /// Returns the index of the item.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<T: LookupIndexes, S: BuildHasher> Deref for RefMut<'_, T, S> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.vec.vec[self.index]
+    }
+}
+
+impl<T: LookupIndexes, S: BuildHasher> DerefMut for RefMut<'_, T, S> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.vec.vec.index_mut_unchecked(self.index)
+    }
+}
+
+impl<T: LookupIndexes, S: BuildHasher> Drop for RefMut<'_, T, S> {
+    fn drop(&mut self) {
+        // Look for a conflict before re-indexing, and re-index before
+        // anything can panic, so that no index is left missing the item
+        let conflict = self.vec.find_unique_conflict(&self.vec.vec[self.index], Some(self.index));
+        self.vec.reindex(self.index);
+        // The `Indexed` backend finds the item by its stored key, so needs no
+        // hash
+        self.vec.vec.check_index(self.index, None);
+        if let Some((i, name)) = conflict.filter(|_| !panicking()) {
+            panic!("item at index {i} already has this value for unique index `{name}`");
+        }
+    }
+}
+
+impl<T: LookupIndexes + fmt::Debug, S: BuildHasher> fmt::Debug for RefMut<'_, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use lookupvec_derive::Lookup;
+
+    // As of 7/2025 alloc::prelude is nightly-only
+    use alloc::borrow::ToOwned;
+    use alloc::string::String;
+
+    #[derive(Debug, Clone, PartialEq, Lookup)]
+    struct User {
+        #[lookup_key]
+        id: u32,
+        #[lookup_index(unique)]
+        email: String,
+        #[lookup_index]
+        team: String,
+    }
+
+    fn user(id: u32, email: &str, team: &str) -> User {
+        User { id, email: email.to_owned(), team: team.to_owned() }
+    }
+
+    fn test_vec() -> MultiIndexLookupVec<User> {
+        MultiIndexLookupVec::from([
+            user(1, "a@x", "core"),
+            user(2, "b@x", "web"),
+            user(3, "c@x", "core"),
+            user(4, "d@x", "infra"),
+        ])
+    }
+
+    fn ids<'a>(users: impl Iterator<Item = &'a User>) -> Vec<u32> {
+        users.map(|user| user.id).collect()
+    }
+
+    /// Checks every item can be found through every index.
+    fn assert_indexed(vec: &MultiIndexLookupVec<User>) {
+        for (i, user) in vec.iter().enumerate() {
+            assert_eq!(vec.get_index_of_by(User::EMAIL_INDEX, &user.email), Some(i));
+            assert!(ids(vec.get_all_by(User::TEAM_INDEX, &user.team)).contains(&user.id));
+        }
+        for table in &vec.indexes {
+            assert_eq!(table.len(), vec.len());
+        }
+    }
+
+    #[test]
+    fn test_get_by() {
+        let vec = test_vec();
+        assert_eq!(vec.get_by(User::EMAIL_INDEX, "c@x").unwrap().id, 3);
+        assert_eq!(vec.get_by(User::EMAIL_INDEX, "x@x"), None);
+        assert_eq!(ids(vec.get_all_by(User::TEAM_INDEX, "core")), vec![1, 3]);
+        assert_eq!(ids(vec.get_all_by(User::TEAM_INDEX, "sales")), Vec::<u32>::new());
+        assert!(vec.contains_by(User::TEAM_INDEX, "web"));
+        assert_eq!(vec.get(&2).unwrap().email, "b@x");
+        assert_indexed(&vec);
+    }
+
+    #[test]
+    fn test_push_replaces() {
+        let mut vec = test_vec();
+        assert_eq!(vec.push(user(2, "b2@x", "core")), Some(user(2, "b@x", "web")));
+        assert_eq!(vec.get_by(User::EMAIL_INDEX, "b@x"), None);
+        assert_eq!(vec.get_by(User::EMAIL_INDEX, "b2@x").unwrap().id, 2);
+        assert_eq!(ids(vec.get_all_by(User::TEAM_INDEX, "core")), vec![1, 2, 3]);
+        assert_eq!(ids(vec.get_all_by(User::TEAM_INDEX, "web")), Vec::<u32>::new());
+
+        // Keeping the same unique value is not a conflict
+        vec.push(user(2, "b2@x", "web"));
+        assert_indexed(&vec);
+    }
+
+    #[test]
+    #[should_panic(expected = "unique index `email`")]
+    fn test_push_unique_conflict() {
+        let mut vec = test_vec();
+        vec.push(user(5, "a@x", "core"));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut vec = test_vec();
+        assert_eq!(vec.shift_remove(&1).unwrap().email, "a@x");
+        assert_indexed(&vec);
+        assert_eq!(vec.get_by(User::EMAIL_INDEX, "a@x"), None);
+
+        assert_eq!(vec.swap_remove(&2).unwrap().email, "b@x");
+        assert_indexed(&vec);
+        assert_eq!(ids(vec.iter()), vec![4, 3]);
+
+        assert_eq!(vec.pop().unwrap().id, 3);
+        assert_eq!(ids(vec.get_all_by(User::TEAM_INDEX, "core")), Vec::<u32>::new());
+        assert_indexed(&vec);
+    }
+
+    #[test]
+    fn test_drain_truncate_retain() {
+        let mut vec = test_vec();
+        assert_eq!(ids(vec.drain(1..3).collect::<Vec<User>>().iter()), vec![2, 3]);
+        assert_eq!(ids(vec.iter()), vec![1, 4]);
+        assert_indexed(&vec);
+
+        let mut vec = test_vec();
+        vec.truncate(2);
+        assert_eq!(vec.get_by(User::EMAIL_INDEX, "c@x"), None);
+        assert_indexed(&vec);
+
+        let mut vec = test_vec();
+        vec.retain(|user| user.id % 2 == 0);
+        assert_eq!(ids(vec.iter()), vec![2, 4]);
+        assert_eq!(vec.get_by(User::EMAIL_INDEX, "d@x").unwrap().id, 4);
+        assert_indexed(&vec);
+    }
+
+    #[test]
+    fn test_get_mut_reindexes() {
+        let mut vec = test_vec();
+        vec.get_mut(&1).unwrap().team = "web".to_owned();
+        assert_eq!(ids(vec.get_all_by(User::TEAM_INDEX, "web")), vec![1, 2]);
+
+        let mut user = vec.get_index_mut(3).unwrap();
+        user.email = "d2@x".to_owned();
+        user.id = 40;
+        drop(user);
+        assert_eq!(vec.get_by(User::EMAIL_INDEX, "d2@x").unwrap().id, 40);
+        assert_eq!(vec.get_index_of(&40), Some(3));
+        assert_indexed(&vec);
+    }
+
+    #[test]
+    #[should_panic(expected = "unique index `email`")]
+    fn test_get_mut_unique_conflict() {
+        let mut vec = test_vec();
+        vec.get_mut(&1).unwrap().email = "b@x".to_owned();
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_get_mut_panics_keep_indexes() {
+        use std::panic::AssertUnwindSafe;
+        use std::panic::catch_unwind;
+
+        // A unique conflict panics after the item is indexed again
+        let mut vec = test_vec();
+        let result = catch_unwind(AssertUnwindSafe(|| vec.get_mut(&1).unwrap().email = "b@x".to_owned()));
+        assert!(result.is_err());
+        assert_eq!(ids(vec.get_all_by(User::TEAM_INDEX, "core")), vec![1, 3]);
+        for table in &vec.indexes {
+            assert_eq!(table.len(), vec.len());
+        }
+
+        // So does a changed key under `KeyPolicy::Panic`
+        let mut vec = test_vec().with_key_policy(KeyPolicy::Panic);
+        let result = catch_unwind(AssertUnwindSafe(|| vec.get_mut(&2).unwrap().id = 20));
+        assert!(result.is_err());
+        assert_eq!(vec.get_by(User::EMAIL_INDEX, "b@x").unwrap().id, 20);
+        assert_eq!(ids(vec.get_all_by(User::TEAM_INDEX, "web")), vec![20]);
+
+        // Dropping a conflicting item while already unwinding doesn't panic
+        // again, which would abort
+        let mut vec = test_vec();
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let mut user = vec.get_mut(&1).unwrap();
+            user.email = "b@x".to_owned();
+            panic!("unrelated");
+        }));
+        assert_eq!(*result.unwrap_err().downcast::<&str>().unwrap(), "unrelated");
+        assert_eq!(vec.get(&1).unwrap().email, "b@x");
+        for table in &vec.indexes {
+            assert_eq!(table.len(), vec.len());
+        }
+    }
+
+    #[test]
+    fn test_from_lookup_vec() {
+        let lookup_vec = LookupVec::from([user(1, "a@x", "core"), user(2, "b@x", "core")]);
+        let vec = MultiIndexLookupVec::from(lookup_vec.clone());
+        assert_eq!(ids(vec.get_all_by(User::TEAM_INDEX, "core")), vec![1, 2]);
+        assert_eq!(ids(vec.into_lookup_vec().iter()), ids(lookup_vec.iter()));
+    }
+}
@@ -1,11 +1,21 @@
+use crate::backend::Backend;
+use crate::backend::Indexed;
 use crate::core::Lookup;
+use crate::guard::ItemMut;
+use crate::guard::KeyCheck;
+use crate::vec::LookupVec;
 
+use ahash::random_state::RandomState;
+
+use core::hash::BuildHasher;
+use core::iter::Chain;
+use core::iter::Enumerate;
 use core::iter::FusedIterator;
 
 use delegate::delegate;
 
-pub struct Iter<'a, T: Lookup> (pub(crate) indexmap::map::Values<'a, T::Key, T>);
-impl<'a, T: Lookup> Iterator for Iter<'a, T> {
+pub struct Iter<'a, T: Lookup + 'a, B: Backend = Indexed> (pub(crate) B::Iter<'a, T>);
+impl<'a, T: Lookup, B: Backend> Iterator for Iter<'a, T, B> {
     type Item = &'a T;
 
     delegate![
@@ -18,7 +28,7 @@ impl<'a, T: Lookup> Iterator for Iter<'a, T> {
         }
     ];
 }
-impl<'a, T: Lookup> DoubleEndedIterator for Iter<'a, T> {
+impl<'a, T: Lookup, B: Backend> DoubleEndedIterator for Iter<'a, T, B> {
     delegate![
         to self.0 {
             fn next_back(&mut self) -> Option<&'a T>;
@@ -26,41 +36,61 @@ impl<'a, T: Lookup> DoubleEndedIterator for Iter<'a, T> {
         }
     ];
 }
-impl<T: Lookup> ExactSizeIterator for Iter<'_, T> {
+impl<T: Lookup, B: Backend> ExactSizeIterator for Iter<'_, T, B> {
     fn len(&self) -> usize { self.0.len() }
 }
-impl<T: Lookup> FusedIterator for Iter<'_, T> {}
+impl<T: Lookup, B: Backend> FusedIterator for Iter<'_, T, B> {}
 
 
-pub struct IterMut<'a, T: Lookup> (pub(crate) indexmap::map::ValuesMut<'a, T::Key, T>);
-impl<'a, T: Lookup> Iterator for IterMut<'a, T> {
-    type Item = &'a mut T;
+/// Mutable iterator over a `LookupVec`. Each item's key is checked when its
+/// `ItemMut` is dropped.
+pub struct IterMut<'a, T: Lookup + 'a, S: BuildHasher + 'a = RandomState, B: Backend = Indexed> {
+    iter: Enumerate<B::IterMut<'a, T, S>>,
+    check: &'a KeyCheck,
+}
 
-    delegate![
-        to self.0 {
-            fn next(&mut self) -> Option<&'a mut T>;
-            fn last(self) -> Option<&'a mut T>;
-            fn nth(&mut self, n: usize) -> Option<&'a mut T>;
-            fn size_hint(&self) -> (usize, Option<usize>);
-            fn count(self) -> usize;
-        }
-    ];
+impl<'a, T: Lookup, S: BuildHasher, B: Backend> IterMut<'a, T, S, B> {
+    pub(crate) fn new(iter: B::IterMut<'a, T, S>, check: &'a KeyCheck) -> Self {
+        IterMut { iter: iter.enumerate(), check }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn wrap(&self, (index, (slot, item)): (usize, (B::Slot<'a, T, S>, &'a mut T))) -> ItemMut<'a, T, S, B> {
+        ItemMut::new(index, slot, item, self.check)
+    }
 }
-impl<'a, T: Lookup> DoubleEndedIterator for IterMut<'a, T> {
-    delegate![
-        to self.0 {
-            fn next_back(&mut self) -> Option<&'a mut T>;
-            fn nth_back(&mut self, n: usize) -> Option<&'a mut T>;
-        }
-    ];
+
+impl<'a, T: Lookup, S: BuildHasher, B: Backend> Iterator for IterMut<'a, T, S, B> {
+    type Item = ItemMut<'a, T, S, B>;
+
+    fn next(&mut self) -> Option<ItemMut<'a, T, S, B>> {
+        self.iter.next().map(|entry| self.wrap(entry))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<ItemMut<'a, T, S, B>> {
+        self.iter.nth(n).map(|entry| self.wrap(entry))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
 }
-impl<T: Lookup> ExactSizeIterator for IterMut<'_, T> {
-    fn len(&self) -> usize { self.0.len() }
+impl<'a, T: Lookup, S: BuildHasher, B: Backend> DoubleEndedIterator for IterMut<'a, T, S, B> {
+    fn next_back(&mut self) -> Option<ItemMut<'a, T, S, B>> {
+        self.iter.next_back().map(|entry| self.wrap(entry))
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<ItemMut<'a, T, S, B>> {
+        self.iter.nth_back(n).map(|entry| self.wrap(entry))
+    }
+}
+impl<T: Lookup, S: BuildHasher, B: Backend> ExactSizeIterator for IterMut<'_, T, S, B> {
+    fn len(&self) -> usize { self.iter.len() }
 }
-impl<T: Lookup> FusedIterator for IterMut<'_, T> {}
+impl<T: Lookup, S: BuildHasher, B: Backend> FusedIterator for IterMut<'_, T, S, B> {}
 
-pub struct IntoIter<T: Lookup> (pub(crate) indexmap::map::IntoValues<T::Key, T>);
-impl<T: Lookup> Iterator for IntoIter<T> {
+pub struct IntoIter<T: Lookup, B: Backend = Indexed> (pub(crate) B::IntoIter<T>);
+impl<T: Lookup, B: Backend> Iterator for IntoIter<T, B> {
     type Item = T;
 
     delegate![
@@ -73,7 +103,7 @@ impl<T: Lookup> Iterator for IntoIter<T> {
         }
     ];
 }
-impl<T: Lookup> DoubleEndedIterator for IntoIter<T> {
+impl<T: Lookup, B: Backend> DoubleEndedIterator for IntoIter<T, B> {
     delegate![
         to self.0 {
             fn next_back(&mut self) -> Option<T>;
@@ -81,41 +111,44 @@ impl<T: Lookup> DoubleEndedIterator for IntoIter<T> {
         }
     ];
 }
-impl<T: Lookup> ExactSizeIterator for IntoIter<T> {
+impl<T: Lookup, B: Backend> ExactSizeIterator for IntoIter<T, B> {
     fn len(&self) -> usize { self.0.len() }
 }
-impl<T: Lookup> FusedIterator for IntoIter<T> {}
+impl<T: Lookup, B: Backend> FusedIterator for IntoIter<T, B> {}
 
 
-pub struct Keys<'a, T: Lookup> (pub(crate) indexmap::map::Keys<'a, T::Key, T>);
-impl<'a, T: Lookup> Iterator for Keys<'a, T> {
-    type Item = &'a T::Key;
+/// Iterator over the keys of a `LookupVec`. Items that can borrow their key
+/// report it from the item itself, so a key changed through `IndexMut` or an
+/// entry shows up here before the vec is re-keyed.
+pub struct Keys<'a, T: Lookup + 'a, B: Backend = Indexed> (pub(crate) B::Keys<'a, T>);
+impl<'a, T: Lookup, B: Backend> Iterator for Keys<'a, T, B> {
+    type Item = B::Key<'a, T>;
 
     delegate![
         to self.0 {
-            fn next(&mut self) -> Option<&'a T::Key>;
-            fn last(self) -> Option<&'a T::Key>;
-            fn nth(&mut self, n: usize) -> Option<&'a T::Key>;
+            fn next(&mut self) -> Option<B::Key<'a, T>>;
+            fn last(self) -> Option<B::Key<'a, T>>;
+            fn nth(&mut self, n: usize) -> Option<B::Key<'a, T>>;
             fn size_hint(&self) -> (usize, Option<usize>);
             fn count(self) -> usize;
         }
     ];
 }
-impl<'a, T: Lookup> DoubleEndedIterator for Keys<'a, T> {
+impl<'a, T: Lookup, B: Backend> DoubleEndedIterator for Keys<'a, T, B> {
     delegate![
         to self.0 {
-            fn next_back(&mut self) -> Option<&'a T::Key>;
-            fn nth_back(&mut self, n: usize) -> Option<&'a T::Key>;
+            fn next_back(&mut self) -> Option<B::Key<'a, T>>;
+            fn nth_back(&mut self, n: usize) -> Option<B::Key<'a, T>>;
         }
     ];
 }
-impl<T: Lookup> ExactSizeIterator for Keys<'_, T> {
+impl<T: Lookup, B: Backend> ExactSizeIterator for Keys<'_, T, B> {
     fn len(&self) -> usize { self.0.len() }
 }
-impl<T: Lookup> FusedIterator for Keys<'_, T> {}
+impl<T: Lookup, B: Backend> FusedIterator for Keys<'_, T, B> {}
 
-pub struct IntoKeys<T: Lookup> (pub(crate) indexmap::map::IntoKeys<T::Key, T>);
-impl<T: Lookup> Iterator for IntoKeys<T> {
+pub struct IntoKeys<T: Lookup, B: Backend = Indexed> (pub(crate) B::IntoKeys<T>);
+impl<T: Lookup, B: Backend> Iterator for IntoKeys<T, B> {
     type Item = T::Key;
 
     delegate![
@@ -128,7 +161,7 @@ impl<T: Lookup> Iterator for IntoKeys<T> {
         }
     ];
 }
-impl<T: Lookup> DoubleEndedIterator for IntoKeys<T> {
+impl<T: Lookup, B: Backend> DoubleEndedIterator for IntoKeys<T, B> {
     delegate![
         to self.0 {
             fn next_back(&mut self) -> Option<T::Key>;
@@ -136,41 +169,125 @@ impl<T: Lookup> DoubleEndedIterator for IntoKeys<T> {
         }
     ];
 }
-impl<T: Lookup> ExactSizeIterator for IntoKeys<T> {
+impl<T: Lookup, B: Backend> ExactSizeIterator for IntoKeys<T, B> {
     fn len(&self) -> usize { self.0.len() }
 }
-impl<T: Lookup> FusedIterator for IntoKeys<T> {}
+impl<T: Lookup, B: Backend> FusedIterator for IntoKeys<T, B> {}
 
-pub struct Drain<'a, T: Lookup> (pub(crate) indexmap::map::Drain<'a, T::Key, T>);
-impl<T: Lookup> Iterator for Drain<'_, T> {
+pub struct Drain<'a, T: Lookup + 'a, B: Backend = Indexed> (pub(crate) B::Drain<'a, T>);
+impl<T: Lookup, B: Backend> Iterator for Drain<'_, T, B> {
     type Item = T;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.0.next().map(|item| item.1)
+    delegate![
+        to self.0 {
+            fn next(&mut self) -> Option<T>;
+            fn last(self) -> Option<T>;
+            fn nth(&mut self, n: usize) -> Option<T>;
+            fn size_hint(&self) -> (usize, Option<usize>);
+            fn count(self) -> usize;
+        }
+    ];
+}
+impl<T: Lookup, B: Backend> DoubleEndedIterator for Drain<'_, T, B> {
+    delegate![
+        to self.0 {
+            fn next_back(&mut self) -> Option<T>;
+            fn nth_back(&mut self, n: usize) -> Option<T>;
+        }
+    ];
+}
+impl<T: Lookup, B: Backend> ExactSizeIterator for Drain<'_, T, B> {
+    fn len(&self) -> usize { self.0.len() }
+}
+impl<T: Lookup, B: Backend> FusedIterator for Drain<'_, T, B> {}
+
+/// Items of a `LookupVec` whose key is not in another, from
+/// `LookupVec::difference`.
+pub struct Difference<'a, T: Lookup, S, B: Backend = Indexed> {
+    pub(crate) iter: Iter<'a, T, B>,
+    pub(crate) other: &'a LookupVec<T, S, B>,
+}
+impl<'a, T: Lookup, S: BuildHasher, B: Backend> Iterator for Difference<'a, T, S, B> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.find(|item| !self.other.contains(item))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+impl<'a, T: Lookup, S: BuildHasher, B: Backend> DoubleEndedIterator for Difference<'a, T, S, B> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        self.iter.rfind(|item| !self.other.contains(item))
+    }
+}
+impl<T: Lookup, S: BuildHasher, B: Backend> FusedIterator for Difference<'_, T, S, B> {}
+
+/// Items of a `LookupVec` whose key is also in another, from
+/// `LookupVec::intersection`.
+pub struct Intersection<'a, T: Lookup, S, B: Backend = Indexed> {
+    pub(crate) iter: Iter<'a, T, B>,
+    pub(crate) other: &'a LookupVec<T, S, B>,
+}
+impl<'a, T: Lookup, S: BuildHasher, B: Backend> Iterator for Intersection<'a, T, S, B> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.find(|item| self.other.contains(item))
     }
-    fn last(self) -> Option<Self::Item> {
-        self.0.last().map(|item| item.1)
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
     }
-    fn nth(&mut self, n: usize) -> Option<Self::Item> {
-        self.0.nth(n).map(|item| item.1)
+}
+impl<'a, T: Lookup, S: BuildHasher, B: Backend> DoubleEndedIterator for Intersection<'a, T, S, B> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        self.iter.rfind(|item| self.other.contains(item))
     }
+}
+impl<T: Lookup, S: BuildHasher, B: Backend> FusedIterator for Intersection<'_, T, S, B> {}
+
+/// Items whose key is in exactly one of two `LookupVec`s, from
+/// `LookupVec::symmetric_difference`.
+pub struct SymmetricDifference<'a, T: Lookup, S1, S2, B: Backend = Indexed> (
+    pub(crate) Chain<Difference<'a, T, S2, B>, Difference<'a, T, S1, B>>,
+);
+impl<'a, T: Lookup, S1: BuildHasher, S2: BuildHasher, B: Backend> Iterator for SymmetricDifference<'a, T, S1, S2, B> {
+    type Item = &'a T;
 
     delegate![
         to self.0 {
+            fn next(&mut self) -> Option<&'a T>;
             fn size_hint(&self) -> (usize, Option<usize>);
-            fn count(self) -> usize;
         }
     ];
 }
-impl<T: Lookup> DoubleEndedIterator for Drain<'_, T> {
-    fn next_back(&mut self) -> Option<Self::Item> {
-        self.0.next_back().map(|item| item.1)
-    }
-    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
-        self.0.nth_back(n).map(|item| item.1)
-    }
+impl<'a, T: Lookup, S1: BuildHasher, S2: BuildHasher, B: Backend> DoubleEndedIterator for SymmetricDifference<'a, T, S1, S2, B> {
+    delegate![
+        to self.0 {
+            fn next_back(&mut self) -> Option<&'a T>;
+        }
+    ];
 }
-impl<T: Lookup> ExactSizeIterator for Drain<'_, T> {
-    fn len(&self) -> usize { self.0.len() }
+impl<T: Lookup, S1: BuildHasher, S2: BuildHasher, B: Backend> FusedIterator for SymmetricDifference<'_, T, S1, S2, B> {}
+
+/// Items whose key is in either of two `LookupVec`s, from `LookupVec::union`.
+pub struct Union<'a, T: Lookup, S, B: Backend = Indexed> (pub(crate) Chain<Iter<'a, T, B>, Difference<'a, T, S, B>>);
+impl<'a, T: Lookup, S: BuildHasher, B: Backend> Iterator for Union<'a, T, S, B> {
+    type Item = &'a T;
+
+    delegate![
+        to self.0 {
+            fn next(&mut self) -> Option<&'a T>;
+            fn size_hint(&self) -> (usize, Option<usize>);
+        }
+    ];
+}
+impl<'a, T: Lookup, S: BuildHasher, B: Backend> DoubleEndedIterator for Union<'a, T, S, B> {
+    delegate![
+        to self.0 {
+            fn next_back(&mut self) -> Option<&'a T>;
+        }
+    ];
 }
-impl<T: Lookup> FusedIterator for Drain<'_, T> {}
+impl<T: Lookup, S: BuildHasher, B: Backend> FusedIterator for Union<'_, T, S, B> {}
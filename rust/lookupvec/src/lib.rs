@@ -3,55 +3,17 @@
 // Trait impls:
 //  Maybe:
 //      Index<&Q>
-//      Index<(Bound<usize>, Bound<usize>)>
-//      Index<Range<usize>>
-//      Index<RangeFrom<usize>>
-//      Index<RangeFull>
-//      Index<RangeInclusive<usize>>
-//      Index<RangeTo<usize>>
-//      Index<RangeToInclusive<usize>>
 //      IndexMut<&Q>
-//      IndexMut<(Bound<usize>, Bound<usize>)>
-//      IndexMut<Range<usize>>
-//      IndexMut<RangeFrom<usize>>
-//      IndexMut<RangeFull>
-//      IndexMut<RangeInclusive<usize>>
-//      IndexMut<RangeTo<usize>>
-//      IndexMut<RangeToInclusive<usize>>
 //  Nah:
-//      Arbitrary
-//      Arbitrary<'a>
-//      BorshDeserialize
-//      BorshSerialize
-//      FromParallelIterator<(K, V)>
-//      IntoParallelIterator
 //      MutableKeys
 //      ParallelDrainRange
 //      ParallelExtend<(&'a K, &'a V)>
-//      ParallelExtend<(K, V)>
 //      RawEntryApiV1<K, V, S>
 //
 // Methods:
-//  Maybe:
-//      as_slice()
-//      as_mut_slice()
-//      into_boxed_slice()
-//      binary_search_by()
-//      binary_search_by_key()
-//      binary_search_keys()
-//      get_range()
-//      get_range_mut()
-//      insert_sorted()
-//      shift_insert()
-//      retain()
 //  Nah:
-//      entry()
-//      first_entry()
-//      last_entry()
-//      get_index_entry()
 //      get_full(&self, key: &str) -> Option<(usize, &str, &T)>;
 //      get_full_mut(&mut self, key: &str) -> Option<(usize, &str, &mut T)>;
-//      partition_point()
 //      sort_unstable_by()
 //      sort_unstable_keys()
 //      sorted_unstable_by()
@@ -63,28 +25,111 @@
 //      try_reserve()
 //      try_reserve_exact()
 //      rayon:
-//          par_keys()
 //          par_values()
 //          par_eq()
 //          par_values_mut()
 //          par_*
 //
 
-#[cfg(test)]
-#[macro_use]
+#[cfg(feature = "alloc")]
+#[cfg_attr(test, macro_use)]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
-pub mod core;
-pub mod iter;
+// Lets `#[derive(Lookup)]` refer to `::lookupvec` from within this crate
+extern crate self as lookupvec;
+
+#[cfg(feature = "alloc")]
 #[macro_use]
 mod macros;
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+#[cfg(feature = "array")]
+pub mod array;
+#[cfg(feature = "alloc")]
+pub mod backend;
+#[cfg(feature = "borsh")]
+mod borsh;
+pub mod core;
+#[cfg(feature = "alloc")]
+pub mod diff;
+#[cfg(feature = "alloc")]
+pub mod duplicate;
+#[cfg(feature = "alloc")]
+pub mod entry;
+#[cfg(feature = "alloc")]
+pub mod guard;
+#[cfg(feature = "alloc")]
+pub mod index;
+#[cfg(feature = "alloc")]
+pub mod iter;
+#[cfg(feature = "alloc")]
+pub mod merge;
+#[cfg(feature = "proptest")]
+pub mod proptest;
+#[cfg(feature = "rayon")]
+pub mod rayon;
+#[cfg(feature = "schemars")]
+pub mod schemars;
 #[cfg(feature = "serde")]
 pub mod serde;
-//pub mod slice;
+#[cfg(feature = "alloc")]
+pub mod slice;
+#[cfg(feature = "alloc")]
+pub mod sorted;
+#[cfg(all(test, feature = "alloc"))]
+mod test_util;
+#[cfg(feature = "alloc")]
 pub mod vec;
 
+#[cfg(feature = "array")]
+pub use array::ArrayLookupVec;
+#[cfg(feature = "compact")]
+pub use backend::Compact;
+#[cfg(feature = "compact")]
+pub use backend::CompactLookupVec;
+#[cfg(feature = "alloc")]
+pub use backend::Backend;
+#[cfg(feature = "alloc")]
+pub use backend::Indexed;
+pub use core::Contains;
+pub use core::KeyField;
 pub use core::Lookup;
+#[cfg(feature = "alloc")]
+pub use diff::Diff;
+#[cfg(feature = "alloc")]
+pub use duplicate::DuplicateKeyError;
+#[cfg(feature = "alloc")]
+pub use duplicate::DuplicatePolicy;
+#[cfg(feature = "alloc")]
+pub use guard::ItemMut;
+#[cfg(feature = "alloc")]
+pub use guard::KeyPolicy;
+#[cfg(feature = "alloc")]
+pub use guard::RefMut;
+#[cfg(feature = "alloc")]
+pub use guard::SliceMut;
+#[cfg(feature = "alloc")]
+pub use index::LookupIndexes;
+#[cfg(feature = "alloc")]
+pub use index::MultiIndexLookupVec;
+#[cfg(feature = "alloc")]
+pub use merge::Merge;
+#[cfg(feature = "alloc")]
+pub use merge::MergePosition;
+#[cfg(feature = "alloc")]
+pub use merge::MergeStrategy;
+#[cfg(feature = "alloc")]
+pub use sorted::Comparator;
+#[cfg(feature = "alloc")]
+pub use sorted::SortedLookupVec;
+#[cfg(feature = "alloc")]
 pub use vec::LookupVec;
 
+// Lets composite keys generated by `#[derive(Lookup)]` accept borrowed queries
+pub use equivalent::Equivalent;
+
 #[cfg(feature = "derive")]
 pub use lookupvec_derive::Lookup;
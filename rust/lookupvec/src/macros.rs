@@ -1,5 +1,16 @@
 #[macro_export]
 macro_rules! lookupvec {
+    (duplicates = $policy:expr; $($item:expr),* $(,)?) => {
+        {
+            const CAP: usize = <[()]>::len(&[$({ stringify!($item); }),*]);
+            let mut vec = $crate::LookupVec::<_>::with_capacity(CAP)
+                .with_duplicate_policy($policy);
+            $(
+                vec.push($item);
+            )*
+            vec
+        }
+    };
     ($($item:expr,)+) => { $crate::lookupvec!($($item),+) };
     ($($item:expr),*) => {
         {
@@ -0,0 +1,107 @@
+use crate::core::Lookup;
+use crate::core::with_key;
+
+use hashbrown::HashTable;
+
+use core::hash::BuildHasher;
+
+/// How `LookupVec::merge_with` combines an incoming item with the existing
+/// item that has the same key.
+///
+/// Implemented by `Merge` for the common cases, and by closures
+/// `FnMut(&mut T, T)` that combine the incoming item into the existing one.
+pub trait MergeStrategy<T: Lookup> {
+    /// Returns false to reject merging `incoming` into `existing`.
+    ///
+    /// `merge_with` asks this for every incoming item before merging any of
+    /// them, so `existing` is the item as it was before the merge.
+    fn accepts(&mut self, existing: &T, incoming: &T) -> bool {
+        let _ = (existing, incoming);
+        true
+    }
+
+    /// Merges `incoming` into `existing`.
+    fn merge(&mut self, existing: &mut T, incoming: T);
+}
+
+/// Ready-made merge strategies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Merge {
+    /// Keep the existing item and discard the incoming one.
+    KeepLeft,
+    /// Replace the existing item with the incoming one.
+    #[default]
+    KeepRight,
+    /// Reject the incoming item with a `DuplicateKeyError`.
+    Error,
+}
+
+impl<T: Lookup> MergeStrategy<T> for Merge {
+    fn accepts(&mut self, _existing: &T, _incoming: &T) -> bool {
+        *self != Merge::Error
+    }
+
+    fn merge(&mut self, existing: &mut T, incoming: T) {
+        match self {
+            Merge::KeepLeft => {},
+            Merge::KeepRight => *existing = incoming,
+            Merge::Error => unreachable!("`Merge::Error` accepts no items"),
+        }
+    }
+}
+
+impl<T: Lookup, F: FnMut(&mut T, T)> MergeStrategy<T> for F {
+    fn merge(&mut self, existing: &mut T, incoming: T) {
+        self(existing, incoming)
+    }
+}
+
+/// Asks `strategy` whether it accepts each of `items` that has the key of an
+/// existing item, as found by `find`, or of an earlier item in `items`.
+///
+/// Returns the position in `items` of the first rejected item, and the index
+/// of the item it would have been merged into.
+pub(crate) fn check_merge<'v, T, S, M>(
+    items: &[T],
+    len: usize,
+    hasher: &S,
+    strategy: &mut M,
+    find: impl Fn(&T) -> Option<(usize, &'v T)>,
+) -> Result<(), (usize, usize)>
+where T: Lookup + 'v, S: BuildHasher, M: MergeStrategy<T> {
+    // Items that will be appended, as their position in `items` and their
+    // index once appended
+    let mut appended = HashTable::<(usize, usize)>::new();
+    let hash = |item: &T| with_key(item, |key| hasher.hash_one(key));
+    for (position, item) in items.iter().enumerate() {
+        let existing = find(item).or_else(|| {
+            let same_key = |&(p, _): &(usize, usize)| with_key(item, |key| with_key(&items[p], |k| key == k));
+            let &(earlier, index) = appended.find(hash(item), same_key)?;
+            Some((index, &items[earlier]))
+        });
+        match existing {
+            Some((index, existing)) => {
+                if !strategy.accepts(existing, item) {
+                    return Err((position, index));
+                }
+            },
+            None => {
+                let index = len + appended.len();
+                appended.insert_unique(hash(item), (position, index), |&(p, _)| hash(&items[p]));
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Where `LookupVec::merge_with` leaves an item that was merged with an
+/// incoming one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergePosition {
+    /// Keep the existing item's position.
+    #[default]
+    Existing,
+    /// Move the item to the end, where the incoming item would have been
+    /// appended.
+    Incoming,
+}
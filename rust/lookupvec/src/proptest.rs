@@ -0,0 +1,393 @@
+//! Property testing support, from the `proptest` feature.
+//!
+//! `lookupvec()` generates vecs whose items all have distinct keys, and
+//! `check_ops()` replays a sequence of `Op`s against both a `LookupVec` and a
+//! `Model`, failing at the first difference:
+//!
+//! ```
+//! # #[cfg(feature = "derive")] {
+//! # use lookupvec::Lookup;
+//! use proptest::prelude::*;
+//!
+//! #[derive(Debug, Clone, PartialEq, Lookup)]
+//! struct Item {
+//!     #[lookup_key]
+//!     id: u8,
+//!     value: u8,
+//! }
+//!
+//! fn item() -> impl Strategy<Value = Item> + Clone {
+//!     (0..8u8, any::<u8>()).prop_map(|(id, value)| Item { id, value })
+//! }
+//!
+//! proptest! {
+//!     fn matches_model(ops in lookupvec::proptest::ops(item(), 0..64)) {
+//!         lookupvec::proptest::check_ops(&ops)?;
+//!     }
+//! }
+//!
+//! matches_model();
+//! # }
+//! ```
+//!
+//! In a test suite, mark `matches_model` with `#[test]` instead of calling it.
+
+use crate::core::Lookup;
+use crate::core::with_key;
+use crate::vec::LookupVec;
+
+use ahash::random_state::RandomState;
+use hashbrown::HashMap;
+use proptest::collection::SizeRange;
+use proptest::collection::VecStrategy;
+use proptest::collection::VecValueTree;
+use proptest::prelude::*;
+use proptest::sample::Index;
+use proptest::strategy::NewTree;
+use proptest::strategy::ValueTree;
+use proptest::strategy::statics::Filter;
+use proptest::strategy::statics::FilterFn;
+use proptest::strategy::statics::Map;
+use proptest::strategy::statics::MapFn;
+use proptest::test_runner::TestCaseError;
+use proptest::test_runner::TestRunner;
+
+use alloc::format;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::marker::PhantomData;
+
+/// Creates a strategy for `LookupVec`s with items drawn from `element` and a
+/// length in `size`.
+///
+/// Items with the same key as an earlier item are dropped, and vecs left
+/// shorter than `size` allows are rejected locally, like
+/// `proptest::collection::hash_set`.
+pub fn lookupvec<E>(element: E, size: impl Into<SizeRange>) -> LookupVecStrategy<E>
+where
+    E: Strategy,
+    E::Value: Lookup,
+{
+    let size = size.into();
+    let min_size = MinSize(size.start());
+    LookupVecStrategy(Filter::new(
+        Map::new(proptest::collection::vec(element, size), VecToLookupVec(PhantomData)),
+        "LookupVec minimum size".into(),
+        min_size,
+    ))
+}
+
+type Inner<S, T> = Filter<Map<S, VecToLookupVec<T>>, MinSize>;
+
+/// Strategy from `lookupvec()`.
+#[must_use = "strategies do nothing unless used"]
+#[derive(Debug)]
+pub struct LookupVecStrategy<E: Strategy>(Inner<VecStrategy<E>, E::Value>)
+where E::Value: Lookup;
+
+impl<E: Strategy> Strategy for LookupVecStrategy<E>
+where E::Value: Lookup {
+    type Tree = LookupVecValueTree<E::Tree>;
+    type Value = LookupVec<E::Value>;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        self.0.new_tree(runner).map(LookupVecValueTree)
+    }
+}
+
+/// Value tree from `LookupVecStrategy`. Shrinks like the underlying `Vec`.
+#[derive(Debug)]
+pub struct LookupVecValueTree<E: ValueTree>(Inner<VecValueTree<E>, E::Value>)
+where E::Value: Lookup;
+
+impl<E: ValueTree> ValueTree for LookupVecValueTree<E>
+where E::Value: Lookup {
+    type Value = LookupVec<E::Value>;
+
+    fn current(&self) -> Self::Value {
+        self.0.current()
+    }
+
+    fn simplify(&mut self) -> bool {
+        self.0.simplify()
+    }
+
+    fn complicate(&mut self) -> bool {
+        self.0.complicate()
+    }
+}
+
+struct VecToLookupVec<T>(PhantomData<fn() -> T>);
+
+impl<T> Clone for VecToLookupVec<T> {
+    fn clone(&self) -> Self {
+        VecToLookupVec(PhantomData)
+    }
+}
+
+impl<T> Debug for VecToLookupVec<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("VecToLookupVec")
+    }
+}
+
+impl<T: Lookup + Debug> MapFn<Vec<T>> for VecToLookupVec<T> {
+    type Output = LookupVec<T>;
+
+    fn apply(&self, items: Vec<T>) -> LookupVec<T> {
+        let mut vec = LookupVec::with_capacity(items.len());
+        for item in items {
+            if !vec.contains(&item) {
+                vec.push(item);
+            }
+        }
+        vec
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MinSize(usize);
+
+impl<T: Lookup> FilterFn<LookupVec<T>> for MinSize {
+    fn apply(&self, vec: &LookupVec<T>) -> bool {
+        vec.len() >= self.0
+    }
+}
+
+/// An operation replayed by `check_ops()`. Indices are scaled to the length
+/// of the vec when the operation is applied, and operations that need an item
+/// are skipped while the vec is empty.
+#[derive(Debug, Clone)]
+pub enum Op<T> {
+    Push(T),
+    Insert(Index, T),
+    /// Removes the key of the item at the index.
+    ShiftRemove(Index),
+    /// Removes the key of the item at the index.
+    SwapRemove(Index),
+    MoveIndex(Index, Index),
+    /// Drains the range between the two indices.
+    Drain(Index, Index),
+    /// Splits off and discards the items from the index on.
+    SplitOff(Index),
+}
+
+/// Creates a strategy for up to `len` operations, with items drawn from
+/// `element`. Use an `element` with few distinct keys to exercise duplicates.
+pub fn ops<E>(element: E, len: impl Into<SizeRange>) -> impl Strategy<Value = Vec<Op<E::Value>>>
+where
+    E: Strategy + Clone,
+    E::Value: Clone,
+{
+    let op = prop_oneof![
+        3 => element.clone().prop_map(Op::Push),
+        2 => (any::<Index>(), element).prop_map(|(index, item)| Op::Insert(index, item)),
+        1 => any::<Index>().prop_map(Op::ShiftRemove),
+        1 => any::<Index>().prop_map(Op::SwapRemove),
+        1 => (any::<Index>(), any::<Index>()).prop_map(|(from, to)| Op::MoveIndex(from, to)),
+        1 => (any::<Index>(), any::<Index>()).prop_map(|(start, end)| Op::Drain(start, end)),
+        1 => any::<Index>().prop_map(Op::SplitOff),
+    ];
+    proptest::collection::vec(op, len)
+}
+
+/// Reference implementation of a `LookupVec`: the items in order, and the
+/// index of each key.
+#[derive(Debug, Clone)]
+pub struct Model<T: Lookup> {
+    items: Vec<T>,
+    indices: HashMap<T::Key, usize, RandomState>,
+}
+
+impl<T: Lookup> Default for Model<T> {
+    fn default() -> Self {
+        Model { items: Vec::new(), indices: HashMap::default() }
+    }
+}
+
+impl<T: Lookup> Model<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    pub fn get_index_of(&self, key: &T::Key) -> Option<usize> {
+        self.indices.get(key).copied()
+    }
+
+    pub fn push(&mut self, item: T) -> Option<T> {
+        match with_key(&item, |key| self.get_index_of(key)) {
+            Some(index) => Some(core::mem::replace(&mut self.items[index], item)),
+            None => {
+                self.indices.insert(item.key(), self.items.len());
+                self.items.push(item);
+                None
+            },
+        }
+    }
+
+    /// Follows `IndexMap::insert_before`: an existing item with the key is
+    /// moved to just before the item that was at `index`.
+    pub fn insert(&mut self, index: usize, item: T) -> (usize, Option<T>) {
+        let (index, replaced) = match with_key(&item, |key| self.get_index_of(key)) {
+            Some(existing) => {
+                let replaced = self.items.remove(existing);
+                (if existing < index { index - 1 } else { index }, Some(replaced))
+            },
+            None => (index, None),
+        };
+        self.items.insert(index, item);
+        self.reindex();
+        (index, replaced)
+    }
+
+    pub fn shift_remove(&mut self, key: &T::Key) -> Option<T> {
+        let index = self.get_index_of(key)?;
+        let item = self.items.remove(index);
+        self.reindex();
+        Some(item)
+    }
+
+    pub fn swap_remove(&mut self, key: &T::Key) -> Option<T> {
+        let index = self.get_index_of(key)?;
+        let item = self.items.swap_remove(index);
+        self.reindex();
+        Some(item)
+    }
+
+    pub fn move_index(&mut self, from: usize, to: usize) {
+        let item = self.items.remove(from);
+        self.items.insert(to, item);
+        self.reindex();
+    }
+
+    pub fn drain(&mut self, start: usize, end: usize) -> Vec<T> {
+        let drained = self.items.drain(start..end).collect();
+        self.reindex();
+        drained
+    }
+
+    pub fn split_off(&mut self, at: usize) -> Vec<T> {
+        let tail = self.items.split_off(at);
+        self.reindex();
+        tail
+    }
+
+    fn reindex(&mut self) {
+        self.indices.clear();
+        for (index, item) in self.items.iter().enumerate() {
+            self.indices.insert(item.key(), index);
+        }
+    }
+}
+
+/// Applies `ops` to an empty `LookupVec` and an empty `Model`, and fails if
+/// their results or contents ever differ.
+pub fn check_ops<T>(ops: &[Op<T>]) -> Result<(), TestCaseError>
+where T: Lookup + Clone + PartialEq + Debug {
+    let mut vec = LookupVec::<T>::new();
+    let mut model = Model::new();
+    for (step, op) in ops.iter().enumerate() {
+        let len = vec.len();
+        match op.clone() {
+            Op::Push(item) => {
+                prop_assert_eq!(vec.push(item.clone()), model.push(item), "step {}: {:?}", step, op);
+            },
+            Op::Insert(index, item) => {
+                let index = index.index(len + 1);
+                prop_assert_eq!(vec.insert(index, item.clone()), model.insert(index, item), "step {}: {:?}", step, op);
+            },
+            _ if len == 0 => continue,
+            Op::ShiftRemove(index) => {
+                let key = model.items()[index.index(len)].key();
+                prop_assert_eq!(vec.shift_remove(&key), model.shift_remove(&key), "step {}: {:?}", step, op);
+            },
+            Op::SwapRemove(index) => {
+                let key = model.items()[index.index(len)].key();
+                prop_assert_eq!(vec.swap_remove(&key), model.swap_remove(&key), "step {}: {:?}", step, op);
+            },
+            Op::MoveIndex(from, to) => {
+                let (from, to) = (from.index(len), to.index(len));
+                vec.move_index(from, to);
+                model.move_index(from, to);
+            },
+            Op::Drain(start, end) => {
+                let (start, end) = (start.index(len + 1), end.index(len + 1));
+                let (start, end) = (start.min(end), start.max(end));
+                let drained: Vec<T> = vec.drain(start..end).collect();
+                prop_assert_eq!(drained, model.drain(start, end), "step {}: {:?}", step, op);
+            },
+            Op::SplitOff(at) => {
+                let at = at.index(len + 1);
+                let tail: Vec<T> = vec.split_off(at).into_iter().collect();
+                prop_assert_eq!(tail, model.split_off(at), "step {}: {:?}", step, op);
+            },
+        }
+        check_model(&vec, &model).map_err(|err| TestCaseError::fail(format!("step {step}: {op:?}: {err}")))?;
+    }
+    Ok(())
+}
+
+/// Fails if `vec` and `model` hold different items, in order, or disagree on
+/// where any key is.
+pub fn check_model<T, S>(vec: &LookupVec<T, S>, model: &Model<T>) -> Result<(), TestCaseError>
+where
+    T: Lookup + PartialEq + Debug,
+    S: core::hash::BuildHasher,
+{
+    prop_assert!(vec.iter().eq(model.items()), "items differ: {:?} != {:?}", vec.iter().collect::<Vec<_>>(), model.items());
+    for (index, item) in model.items().iter().enumerate() {
+        let key = item.key();
+        prop_assert_eq!(vec.get_index_of(&key), Some(index), "index of {:?}", key);
+    }
+    prop_assert!(vec.validate().is_empty(), "stale keys at {:?}", vec.validate());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lookupvec_derive::Lookup;
+
+    #[derive(Debug, Clone, PartialEq, Lookup)]
+    struct Item {
+        #[lookup_key]
+        id: u8,
+        value: u8,
+    }
+
+    // Few keys, so that most sequences hit duplicates
+    fn item() -> impl Strategy<Value = Item> + Clone {
+        (0..8u8, any::<u8>()).prop_map(|(id, value)| Item { id, value })
+    }
+
+    proptest! {
+        #[test]
+        fn test_lookupvec_strategy(vec in lookupvec(item(), 3..=8)) {
+            prop_assert!((3..=8).contains(&vec.len()));
+            let mut ids: Vec<u8> = vec.iter().map(|item| item.id).collect();
+            ids.sort_unstable();
+            ids.dedup();
+            prop_assert_eq!(ids.len(), vec.len());
+        }
+
+        #[test]
+        fn test_matches_model(ops in ops(item(), 0..64)) {
+            check_ops(&ops)?;
+        }
+    }
+
+    #[test]
+    fn test_model_insert() {
+        let mut model = Model::new();
+        for id in 0..4 {
+            model.push(Item { id, value: 0 });
+        }
+        assert_eq!(model.insert(3, Item { id: 0, value: 1 }), (2, Some(Item { id: 0, value: 0 })));
+        assert_eq!(model.items().iter().map(|item| item.id).collect::<Vec<_>>(), vec![1, 2, 0, 3]);
+        assert_eq!(model.get_index_of(&3), Some(3));
+    }
+}
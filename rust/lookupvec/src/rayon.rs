@@ -0,0 +1,127 @@
+//! Parallel iterators over a `LookupVec`, from the `rayon` feature.
+
+use crate::backend::Backend;
+use crate::backend::Indexed;
+use crate::core::Lookup;
+use crate::guard::ItemMut;
+use crate::guard::KeyCheck;
+
+use ahash::random_state::RandomState;
+use rayon::iter::IndexedParallelIterator;
+use rayon::iter::ParallelIterator;
+use rayon::iter::plumbing::Consumer;
+use rayon::iter::plumbing::ProducerCallback;
+use rayon::iter::plumbing::UnindexedConsumer;
+
+use core::hash::BuildHasher;
+
+macro_rules! delegate_parallel_iterator {
+    ($item:ty) => {
+        type Item = $item;
+
+        fn drive_unindexed<C>(self, consumer: C) -> C::Result
+        where C: UnindexedConsumer<Self::Item> {
+            self.0.drive_unindexed(consumer)
+        }
+
+        fn opt_len(&self) -> Option<usize> {
+            Some(self.0.len())
+        }
+    };
+}
+
+macro_rules! delegate_indexed_parallel_iterator {
+    () => {
+        fn drive<C>(self, consumer: C) -> C::Result
+        where C: Consumer<Self::Item> {
+            self.0.drive(consumer)
+        }
+
+        fn len(&self) -> usize {
+            self.0.len()
+        }
+
+        fn with_producer<CB>(self, callback: CB) -> CB::Output
+        where CB: ProducerCallback<Self::Item> {
+            self.0.with_producer(callback)
+        }
+    };
+}
+
+pub struct ParIter<'a, T: Lookup + Sync + 'a, B: Backend = Indexed> (pub(crate) B::ParIter<'a, T>)
+where T::Key: Sync;
+impl<'a, T: Lookup + Sync, B: Backend> ParallelIterator for ParIter<'a, T, B>
+where T::Key: Sync {
+    delegate_parallel_iterator!(&'a T);
+}
+impl<T: Lookup + Sync, B: Backend> IndexedParallelIterator for ParIter<'_, T, B>
+where T::Key: Sync {
+    delegate_indexed_parallel_iterator!();
+}
+
+/// Parallel mutable iterator over a `LookupVec`. Each item's key is checked
+/// when its `ItemMut` is dropped.
+pub struct ParIterMut<'a, T, S = RandomState, B = Indexed>
+where T: Lookup + Send + 'a, T::Key: Send + Sync, S: BuildHasher + Sync + 'a, B: Backend {
+    pub(crate) iter: B::ParIterMut<'a, T, S>,
+    pub(crate) check: &'a KeyCheck,
+}
+impl<'a, T, S, B> ParIterMut<'a, T, S, B>
+where T: Lookup + Send, T::Key: Send + Sync, S: BuildHasher + Sync, B: Backend, B::Slot<'a, T, S>: Send {
+    fn items(self) -> impl IndexedParallelIterator<Item = ItemMut<'a, T, S, B>> {
+        let check = self.check;
+        self.iter.enumerate().map(move |(index, (slot, item))| ItemMut::new(index, slot, item, check))
+    }
+}
+impl<'a, T, S, B> ParallelIterator for ParIterMut<'a, T, S, B>
+where T: Lookup + Send, T::Key: Send + Sync, S: BuildHasher + Sync, B: Backend, B::Slot<'a, T, S>: Send {
+    type Item = ItemMut<'a, T, S, B>;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where C: UnindexedConsumer<Self::Item> {
+        self.items().drive_unindexed(consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+impl<'a, T, S, B> IndexedParallelIterator for ParIterMut<'a, T, S, B>
+where T: Lookup + Send, T::Key: Send + Sync, S: BuildHasher + Sync, B: Backend, B::Slot<'a, T, S>: Send {
+    fn drive<C>(self, consumer: C) -> C::Result
+    where C: Consumer<Self::Item> {
+        self.items().drive(consumer)
+    }
+
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+
+    fn with_producer<CB>(self, callback: CB) -> CB::Output
+    where CB: ProducerCallback<Self::Item> {
+        self.items().with_producer(callback)
+    }
+}
+
+pub struct IntoParIter<T: Lookup + Send, B: Backend = Indexed> (pub(crate) B::IntoParIter<T>)
+where T::Key: Send;
+impl<T: Lookup + Send, B: Backend> ParallelIterator for IntoParIter<T, B>
+where T::Key: Send {
+    delegate_parallel_iterator!(T);
+}
+impl<T: Lookup + Send, B: Backend> IndexedParallelIterator for IntoParIter<T, B>
+where T::Key: Send {
+    delegate_indexed_parallel_iterator!();
+}
+
+/// Parallel iterator over the keys of a `LookupVec`, like `Keys`.
+pub struct ParKeys<'a, T: Lookup + Sync + 'a, B: Backend = Indexed> (pub(crate) B::ParKeys<'a, T>)
+where T::Key: Send + Sync;
+impl<'a, T: Lookup + Sync, B: Backend> ParallelIterator for ParKeys<'a, T, B>
+where T::Key: Send + Sync, B::Key<'a, T>: Send {
+    delegate_parallel_iterator!(B::Key<'a, T>);
+}
+impl<'a, T: Lookup + Sync, B: Backend> IndexedParallelIterator for ParKeys<'a, T, B>
+where T::Key: Send + Sync, B::Key<'a, T>: Send {
+    delegate_indexed_parallel_iterator!();
+}
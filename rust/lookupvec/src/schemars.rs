@@ -0,0 +1,204 @@
+//! JSON Schemas for `LookupVec`, from the `schemars` feature.
+//!
+//! The `JsonSchema` impl describes the default representation, an array of
+//! items. Fields serialized with `lookupvec::serde::as_map` describe
+//! themselves with `AsMap`:
+//!
+//! ```
+//! # #[cfg(all(feature = "derive", feature = "serde"))] {
+//! # use lookupvec::Lookup;
+//! # use lookupvec::LookupVec;
+//! # use schemars::JsonSchema;
+//! # use serde::Deserialize;
+//! # use serde::Serialize;
+//! #[derive(Serialize, Deserialize, JsonSchema, Lookup)]
+//! struct Service {
+//!     #[lookup_key]
+//!     name: String,
+//!     port: u16,
+//! }
+//!
+//! #[derive(Serialize, Deserialize, JsonSchema)]
+//! struct Config {
+//!     #[serde(with = "lookupvec::serde::as_map")]
+//!     #[schemars(with = "lookupvec::schemars::AsMap<Service>")]
+//!     services: LookupVec<Service>,
+//! }
+//!
+//! let schema = schemars::schema_for!(Config);
+//! let services = &schema.as_value()["properties"]["services"];
+//! assert_eq!(services["type"], "object");
+//! assert_eq!(services["x-lookupvec-key"], "name");
+//! # }
+//! ```
+//!
+//! The `AsMap` schemas record the name of the key field under
+//! `x-lookupvec-key`, for items with a `KeyField`.
+
+use crate::core::KeyField;
+use crate::core::Lookup;
+use crate::vec::LookupVec;
+
+use schemars::JsonSchema;
+use schemars::Schema;
+use schemars::SchemaGenerator;
+use schemars::json_schema;
+
+use alloc::borrow::Cow;
+use alloc::format;
+use core::marker::PhantomData;
+
+/// Extension keyword holding the name of the key field.
+pub const KEY_EXTENSION: &str = "x-lookupvec-key";
+
+/// Items have distinct keys, so they are also `uniqueItems`.
+impl<T: Lookup + JsonSchema, S> JsonSchema for LookupVec<T, S> {
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn schema_name() -> Cow<'static, str> {
+        format!("LookupVec_of_{}", T::schema_name()).into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        format!("lookupvec::LookupVec<{}>", T::schema_id()).into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "array",
+            "items": generator.subschema_for::<T>(),
+            "uniqueItems": true,
+        })
+    }
+}
+
+/// Schema for a `LookupVec` serialized with `lookupvec::serde::as_map`: an
+/// object from each key to its item.
+pub struct AsMap<T>(PhantomData<T>);
+
+impl<T: KeyField + JsonSchema> JsonSchema for AsMap<T> {
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn schema_name() -> Cow<'static, str> {
+        format!("LookupMap_of_{}", T::schema_name()).into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        format!("lookupvec::schemars::AsMap<{}>", T::schema_id()).into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "object",
+            "additionalProperties": generator.subschema_for::<T>(),
+            KEY_EXTENSION: T::KEY_FIELD,
+        })
+    }
+}
+
+/// Schema for a `LookupVec` serialized with
+/// `lookupvec::serde::as_map::omit_key`. Items are described inline, without
+/// their key field.
+pub struct AsMapOmitKey<T>(PhantomData<T>);
+
+impl<T: KeyField + JsonSchema> JsonSchema for AsMapOmitKey<T> {
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn schema_name() -> Cow<'static, str> {
+        format!("LookupMap_of_{}_without_key", T::schema_name()).into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        format!("lookupvec::schemars::AsMapOmitKey<{}>", T::schema_id()).into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let mut item = T::json_schema(generator);
+        if let Some(properties) = item.get_mut("properties").and_then(|p| p.as_object_mut()) {
+            properties.remove(T::KEY_FIELD);
+        }
+        if let Some(required) = item.get_mut("required").and_then(|r| r.as_array_mut()) {
+            required.retain(|field| field != T::KEY_FIELD);
+        }
+        json_schema!({
+            "type": "object",
+            "additionalProperties": item,
+            KEY_EXTENSION: T::KEY_FIELD,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use lookupvec_derive::Lookup;
+
+    use alloc::string::String;
+    use schemars::schema_for;
+    use serde_json::json;
+
+    #[derive(JsonSchema, Lookup)]
+    #[allow(dead_code)]
+    struct Service {
+        #[lookup_key]
+        #[serde(rename = "name")]
+        id: String,
+        port: u16,
+    }
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    struct Config {
+        list: LookupVec<Service>,
+        #[schemars(with = "AsMap<Service>")]
+        map: LookupVec<Service>,
+        #[schemars(with = "AsMapOmitKey<Service>")]
+        short: LookupVec<Service>,
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let schema = schema_for!(Config);
+        let properties = &schema.as_value()["properties"];
+        assert_eq!(properties["list"], json!({
+            "type": "array",
+            "items": { "$ref": "#/$defs/Service" },
+            "uniqueItems": true,
+        }));
+        assert_eq!(properties["map"], json!({
+            "type": "object",
+            "additionalProperties": { "$ref": "#/$defs/Service" },
+            "x-lookupvec-key": "name",
+        }));
+
+        let short = &properties["short"]["additionalProperties"];
+        assert_eq!(short["properties"], json!({
+            "port": { "type": "integer", "format": "uint16", "minimum": 0, "maximum": 65535 },
+        }));
+        assert_eq!(short["required"], json!(["port"]));
+        assert_eq!(properties["short"]["x-lookupvec-key"], "name");
+    }
+
+    #[test]
+    fn test_json_schema_computed_key() {
+        #[derive(JsonSchema, Lookup)]
+        #[lookup(key = format!("{}:{}", self.host, self.port), key_type = String)]
+        #[allow(dead_code)]
+        struct Endpoint {
+            host: String,
+            port: u16,
+        }
+
+        let schema = schema_for!(LookupVec<Endpoint>);
+        assert_eq!(schema.as_value()["type"], "array");
+        assert_eq!(schema.as_value()["items"]["$ref"], "#/$defs/Endpoint");
+        assert!(schema.as_value().get("x-lookupvec-key").is_none());
+    }
+}
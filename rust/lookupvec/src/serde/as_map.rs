@@ -0,0 +1,666 @@
+//! Serializes a `LookupVec` as a map from each item's key to the item, for
+//! use with `#[serde(with = "lookupvec::serde::as_map")]`.
+//!
+//! ```
+//! # #[cfg(feature = "derive")] {
+//! # use lookupvec::Lookup;
+//! # use lookupvec::LookupVec;
+//! # use serde::Deserialize;
+//! # use serde::Serialize;
+//! #[derive(Serialize, Deserialize, Lookup)]
+//! struct Service {
+//!     #[lookup_key]
+//!     name: String,
+//!     port: u16,
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(with = "lookupvec::serde::as_map::omit_key")]
+//!     services: LookupVec<Service>,
+//! }
+//!
+//! let json = r#"{"services":{"web":{"port":80}}}"#;
+//! let config: Config = serde_json::from_str(json)?;
+//! assert_eq!(config.services.get("web").unwrap().port, 80);
+//! assert_eq!(serde_json::to_string(&config)?, json);
+//! # }
+//! # Ok::<(), serde_json::Error>(())
+//! ```
+//!
+//! `as_map` writes and reads each item in full, and each item's key must match
+//! its map key.
+//!
+//! `as_map::omit_key` leaves out the key field, since the map key already
+//! holds it. When deserializing, an item that leaves out its key field gets
+//! the map key filled in. This needs a self-describing format, and items that
+//! are structs with a `KeyField`.
+
+use crate::core::KeyField;
+use crate::core::Lookup;
+use crate::core::with_key;
+use crate::core::cautious_capacity;
+use crate::LookupVec;
+
+use delegate::delegate;
+use serde::de;
+use serde::de::DeserializeSeed;
+use serde::de::Error as _;
+use serde::de::IntoDeserializer;
+use serde::de::MapAccess;
+use serde::de::Visitor;
+use serde::de::value::StrDeserializer;
+use serde::de::value::StringDeserializer;
+use serde::ser;
+use serde::ser::SerializeMap;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+use alloc::string::String;
+use core::fmt;
+use core::hash::BuildHasher;
+use core::marker::PhantomData;
+
+pub fn serialize<T, S, Ser>(vec: &LookupVec<T, S>, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+where
+    T: Lookup + Serialize,
+    T::Key: Serialize,
+    Ser: Serializer,
+{
+    let mut map = serializer.serialize_map(Some(vec.len()))?;
+    for item in vec {
+        with_key(item, |key| map.serialize_entry(key, item))?;
+    }
+    map.end()
+}
+
+pub fn deserialize<'de, T, S, D>(deserializer: D) -> Result<LookupVec<T, S>, D::Error>
+where
+    T: Lookup + Deserialize<'de>,
+    T::Key: Deserialize<'de>,
+    S: BuildHasher + Default,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_map(MapVisitor::<T, S, PhantomData<T>> { marker: PhantomData })
+}
+
+/// Like `as_map`, but leaves the key field out of each serialized item.
+pub mod omit_key {
+    use super::*;
+
+    pub fn serialize<T, S, Ser>(vec: &LookupVec<T, S>, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        T: KeyField + Serialize,
+        T::Key: Serialize,
+        Ser: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(vec.len()))?;
+        for item in vec {
+            with_key(item, |key| map.serialize_entry(key, &OmitKey(item)))?;
+        }
+        map.end()
+    }
+
+    /// Like `as_map::deserialize`, but fills in the key field of items that
+    /// leave it out.
+    pub fn deserialize<'de, T, S, D>(deserializer: D) -> Result<LookupVec<T, S>, D::Error>
+    where
+        T: KeyField + Deserialize<'de>,
+        T::Key: Deserialize<'de> + IntoDeserializer<'de, de::value::Error>,
+        S: BuildHasher + Default,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(MapVisitor::<T, S, InjectKey<T>> { marker: PhantomData })
+    }
+}
+
+/// Deserializes the item under a map key.
+trait ItemSeed<'de, T: Lookup>: DeserializeSeed<'de, Value = T> {
+    fn for_key(key: &T::Key) -> Self;
+}
+
+impl<'de, T: Lookup + Deserialize<'de>> ItemSeed<'de, T> for PhantomData<T> {
+    fn for_key(_key: &T::Key) -> Self {
+        PhantomData
+    }
+}
+
+impl<'de, T> ItemSeed<'de, T> for InjectKey<T>
+where
+    T: KeyField + Deserialize<'de>,
+    T::Key: IntoDeserializer<'de, de::value::Error>,
+{
+    fn for_key(key: &T::Key) -> Self {
+        InjectKey::new(key.clone())
+    }
+}
+
+struct MapVisitor<T, S, Seed> {
+    marker: PhantomData<(T, S, Seed)>,
+}
+
+impl<'de, T, S, Seed> Visitor<'de> for MapVisitor<T, S, Seed>
+where
+    T: Lookup,
+    T::Key: Deserialize<'de>,
+    S: BuildHasher + Default,
+    Seed: ItemSeed<'de, T>,
+{
+    type Value = LookupVec<T, S>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map of items by key")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut vec: LookupVec<T, S> = LookupVec::with_capacity_and_hasher(
+            cautious_capacity::<T>(map.size_hint()),
+            S::default(),
+        );
+        while let Some(key) = map.next_key::<T::Key>()? {
+            let item = map.next_value_seed(Seed::for_key(&key))?;
+            if !with_key(&item, |item_key| *item_key == key) {
+                return Err(A::Error::custom(format_args!(
+                    "entry {key:?} has a different key {:?} in its value",
+                    item.key(),
+                )));
+            }
+            if vec.try_push(item).is_err() {
+                return Err(A::Error::custom(format_args!("duplicate map key {key:?}")));
+            }
+        }
+        Ok(vec)
+    }
+}
+
+/// Deserializes an item, filling in its key field with `key` if the item
+/// leaves it out.
+pub(crate) struct InjectKey<T: Lookup> {
+    key: T::Key,
+    marker: PhantomData<T>,
+}
+
+impl<T: Lookup> InjectKey<T> {
+    pub(crate) fn new(key: T::Key) -> Self {
+        InjectKey { key, marker: PhantomData }
+    }
+}
+
+impl<'de, T> DeserializeSeed<'de> for InjectKey<T>
+where
+    T: KeyField + Deserialize<'de>,
+    T::Key: IntoDeserializer<'de, de::value::Error>,
+{
+    type Value = T;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(InjectKeyDeserializer {
+            inner: deserializer,
+            field: T::KEY_FIELD,
+            key: self.key,
+        })
+    }
+}
+
+struct InjectKeyDeserializer<D, K> {
+    inner: D,
+    field: &'static str,
+    key: K,
+}
+
+impl<'de, D, K> Deserializer<'de> for InjectKeyDeserializer<D, K>
+where
+    D: Deserializer<'de>,
+    K: IntoDeserializer<'de, de::value::Error>,
+{
+    type Error = D::Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, D::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.inner.deserialize_any(InjectKeyVisitor {
+            inner: visitor,
+            field: self.field,
+            key: self.key,
+        })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct InjectKeyVisitor<V, K> {
+    inner: V,
+    field: &'static str,
+    key: K,
+}
+
+impl<'de, V, K> Visitor<'de> for InjectKeyVisitor<V, K>
+where
+    V: Visitor<'de>,
+    K: IntoDeserializer<'de, de::value::Error>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.expecting(formatter)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<V::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        self.inner.visit_map(InjectKeyMap {
+            inner: map,
+            field: self.field,
+            key: Some(self.key),
+            seen: false,
+            injecting: false,
+            ended: false,
+        })
+    }
+}
+
+/// Passes through the fields of an item, then adds the key field if it was
+/// not among them.
+struct InjectKeyMap<A, K> {
+    inner: A,
+    field: &'static str,
+    key: Option<K>,
+    seen: bool,
+    injecting: bool,
+    // `inner` must not be asked for more keys once it has run out
+    ended: bool,
+}
+
+impl<'de, A, K> MapAccess<'de> for InjectKeyMap<A, K>
+where
+    A: MapAccess<'de>,
+    K: IntoDeserializer<'de, de::value::Error>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<Seed>(&mut self, seed: Seed) -> Result<Option<Seed::Value>, A::Error>
+    where
+        Seed: DeserializeSeed<'de>,
+    {
+        if !self.ended {
+            if let Some(name) = self.inner.next_key::<String>()? {
+                self.seen |= name == self.field;
+                return seed.deserialize(StringDeserializer::new(name)).map(Some);
+            }
+            self.ended = true;
+        }
+        if !self.seen && self.key.is_some() {
+            self.injecting = true;
+            return seed.deserialize(StrDeserializer::new(self.field)).map(Some);
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<Seed>(&mut self, seed: Seed) -> Result<Seed::Value, A::Error>
+    where
+        Seed: DeserializeSeed<'de>,
+    {
+        if self.injecting {
+            self.injecting = false;
+            let key = self.key.take().unwrap();
+            seed.deserialize(key.into_deserializer()).map_err(A::Error::custom)
+        } else {
+            self.inner.next_value_seed(seed)
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint().map(|n| n + 1)
+    }
+}
+
+/// Serializes an item without its key field.
+struct OmitKey<'a, T>(&'a T);
+
+impl<T: KeyField + Serialize> Serialize for OmitKey<'_, T> {
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        self.0.serialize(OmitFieldSerializer { inner: serializer, field: T::KEY_FIELD })
+    }
+}
+
+/// Passes everything through to `inner`, except that structs skip `field`.
+struct OmitFieldSerializer<Ser> {
+    inner: Ser,
+    field: &'static str,
+}
+
+impl<Ser: Serializer> Serializer for OmitFieldSerializer<Ser> {
+    type Ok = Ser::Ok;
+    type Error = Ser::Error;
+    type SerializeSeq = Ser::SerializeSeq;
+    type SerializeTuple = Ser::SerializeTuple;
+    type SerializeTupleStruct = Ser::SerializeTupleStruct;
+    type SerializeTupleVariant = Ser::SerializeTupleVariant;
+    type SerializeMap = Ser::SerializeMap;
+    type SerializeStruct = OmitFieldStruct<Ser::SerializeStruct>;
+    type SerializeStructVariant = Ser::SerializeStructVariant;
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct, Ser::Error> {
+        let inner = self.inner.serialize_struct(name, len.saturating_sub(1))?;
+        Ok(OmitFieldStruct { inner, field: self.field })
+    }
+
+    delegate![
+        to self.inner {
+            fn serialize_bool(self, v: bool) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_i8(self, v: i8) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_i16(self, v: i16) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_i32(self, v: i32) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_i64(self, v: i64) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_i128(self, v: i128) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_u8(self, v: u8) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_u16(self, v: u16) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_u32(self, v: u32) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_u64(self, v: u64) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_u128(self, v: u128) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_f32(self, v: f32) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_f64(self, v: f64) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_char(self, v: char) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_str(self, v: &str) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_bytes(self, v: &[u8]) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_none(self) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_unit(self) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_unit_struct(self, name: &'static str) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_unit_variant(
+                self,
+                name: &'static str,
+                variant_index: u32,
+                variant: &'static str,
+            ) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_newtype_struct<T: ?Sized + Serialize>(
+                self,
+                name: &'static str,
+                value: &T,
+            ) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_newtype_variant<T: ?Sized + Serialize>(
+                self,
+                name: &'static str,
+                variant_index: u32,
+                variant: &'static str,
+                value: &T,
+            ) -> Result<Ser::Ok, Ser::Error>;
+            fn serialize_seq(self, len: Option<usize>) -> Result<Ser::SerializeSeq, Ser::Error>;
+            fn serialize_tuple(self, len: usize) -> Result<Ser::SerializeTuple, Ser::Error>;
+            fn serialize_tuple_struct(
+                self,
+                name: &'static str,
+                len: usize,
+            ) -> Result<Ser::SerializeTupleStruct, Ser::Error>;
+            fn serialize_tuple_variant(
+                self,
+                name: &'static str,
+                variant_index: u32,
+                variant: &'static str,
+                len: usize,
+            ) -> Result<Ser::SerializeTupleVariant, Ser::Error>;
+            fn serialize_map(self, len: Option<usize>) -> Result<Ser::SerializeMap, Ser::Error>;
+            fn serialize_struct_variant(
+                self,
+                name: &'static str,
+                variant_index: u32,
+                variant: &'static str,
+                len: usize,
+            ) -> Result<Ser::SerializeStructVariant, Ser::Error>;
+        }
+    ];
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+struct OmitFieldStruct<St> {
+    inner: St,
+    field: &'static str,
+}
+
+impl<St: ser::SerializeStruct> ser::SerializeStruct for OmitFieldStruct<St> {
+    type Ok = St::Ok;
+    type Error = St::Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), St::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        if key == self.field {
+            return Ok(());
+        }
+        self.inner.serialize_field(key, value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), St::Error> {
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<St::Ok, St::Error> {
+        self.inner.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use lookupvec_derive::Lookup;
+
+    // As of 7/2025 alloc::prelude is nightly-only
+    use alloc::borrow::ToOwned;
+    use alloc::string::ToString;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Lookup)]
+    struct Service {
+        #[lookup_key]
+        name: String,
+        port: u16,
+    }
+
+    fn service(name: &str, port: u16) -> Service {
+        Service { name: name.to_owned(), port }
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Config {
+        #[serde(with = "crate::serde::as_map")]
+        services: LookupVec<Service>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct CompactConfig {
+        #[serde(with = "crate::serde::as_map::omit_key")]
+        services: LookupVec<Service>,
+    }
+
+    fn services() -> LookupVec<Service> {
+        LookupVec::from([service("web", 80), service("db", 5432)])
+    }
+
+    #[test]
+    fn test_serialize() {
+        let json = serde_json::to_string(&Config { services: services() }).unwrap();
+        assert_eq!(json, r#"{"services":{"web":{"name":"web","port":80},"db":{"name":"db","port":5432}}}"#);
+
+        let json = serde_json::to_string(&CompactConfig { services: services() }).unwrap();
+        assert_eq!(json, r#"{"services":{"web":{"port":80},"db":{"port":5432}}}"#);
+    }
+
+    #[test]
+    fn test_deserialize_injects_key() {
+        let json = r#"{"services":{"web":{"port":80},"db":{"port":5432,"name":"db"}}}"#;
+        let config: CompactConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.services, services());
+
+        let json = serde_json::to_string(&CompactConfig { services: services() }).unwrap();
+        let config: CompactConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(config.services, services());
+
+        let err = serde_json::from_str::<Config>(json.as_str()).unwrap_err().to_string();
+        assert!(err.starts_with("missing field `name`"), "{err}");
+    }
+
+    #[test]
+    fn test_deserialize_full_items() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Pair(String, u32);
+
+        impl Lookup for Pair {
+            type Key = String;
+
+            fn key(&self) -> String {
+                self.0.clone()
+            }
+        }
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Pairs(#[serde(with = "crate::serde::as_map")] LookupVec<Pair>);
+
+        let pairs = Pairs(LookupVec::from([Pair("a".to_owned(), 1), Pair("b".to_owned(), 2)]));
+        let bytes = postcard::to_allocvec(&pairs).unwrap();
+        assert_eq!(postcard::from_bytes::<Pairs>(&bytes).unwrap(), pairs);
+    }
+
+    #[test]
+    fn test_injected_map_ends() {
+        // Asks for another key after the map has ended
+        #[derive(Debug, PartialEq)]
+        struct Probe {
+            name: String,
+        }
+
+        impl Lookup for Probe {
+            type Key = String;
+
+            fn key(&self) -> String {
+                self.name.clone()
+            }
+        }
+
+        impl KeyField for Probe {
+            const KEY_FIELD: &'static str = "name";
+        }
+
+        impl<'de> Deserialize<'de> for Probe {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct ProbeVisitor;
+
+                impl<'de> Visitor<'de> for ProbeVisitor {
+                    type Value = Probe;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("a probe")
+                    }
+
+                    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Probe, A::Error> {
+                        let mut name = None;
+                        while let Some(key) = map.next_key::<String>()? {
+                            let value: String = map.next_value()?;
+                            if key == "name" {
+                                name = Some(value);
+                            }
+                        }
+                        assert_eq!(map.next_key::<String>()?, None);
+                        Ok(Probe { name: name.ok_or_else(|| A::Error::missing_field("name"))? })
+                    }
+                }
+
+                deserializer.deserialize_map(ProbeVisitor)
+            }
+        }
+
+        /// Panics if asked for a key after it has run out.
+        struct StrictMap {
+            entries: alloc::vec::IntoIter<(&'static str, &'static str)>,
+            value: Option<&'static str>,
+            ended: bool,
+        }
+
+        impl<'de> MapAccess<'de> for StrictMap {
+            type Error = de::value::Error;
+
+            fn next_key_seed<Seed>(&mut self, seed: Seed) -> Result<Option<Seed::Value>, Self::Error>
+            where
+                Seed: DeserializeSeed<'de>,
+            {
+                assert!(!self.ended, "asked for a key after the end of the map");
+                let Some((key, value)) = self.entries.next() else {
+                    self.ended = true;
+                    return Ok(None);
+                };
+                self.value = Some(value);
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+
+            fn next_value_seed<Seed>(&mut self, seed: Seed) -> Result<Seed::Value, Self::Error>
+            where
+                Seed: DeserializeSeed<'de>,
+            {
+                seed.deserialize(self.value.take().unwrap().into_deserializer())
+            }
+        }
+
+        let map = StrictMap { entries: vec![("x", "1")].into_iter(), value: None, ended: false };
+        let seed = InjectKey::<Probe>::new("a".to_owned());
+        let probe = seed.deserialize(de::value::MapAccessDeserializer::new(map)).unwrap();
+        assert_eq!(probe, Probe { name: "a".to_owned() });
+    }
+
+    #[test]
+    fn test_deserialize_key_mismatch() {
+        let json = r#"{"services":{"web":{"port":80},"db":{"name":"database","port":5432}}}"#;
+        let err = serde_json::from_str::<CompactConfig>(json).unwrap_err().to_string();
+        assert!(err.starts_with(r#"entry "db" has a different key "database" in its value"#), "{err}");
+
+        let json = r#"{"services":{"db":{"name":"database","port":5432}}}"#;
+        let err = serde_json::from_str::<Config>(json).unwrap_err().to_string();
+        assert!(err.starts_with(r#"entry "db" has a different key "database" in its value"#), "{err}");
+    }
+
+    #[test]
+    fn test_deserialize_duplicate_key() {
+        let json = r#"{"services":{"web":{"port":80},"web":{"port":8080}}}"#;
+        let err = serde_json::from_str::<CompactConfig>(json).unwrap_err().to_string();
+        assert!(err.starts_with(r#"duplicate map key "web""#), "{err}");
+    }
+
+    #[test]
+    fn test_renamed_key_field() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize, Lookup)]
+        struct Host {
+            #[lookup_key]
+            #[serde(rename = "hostname")]
+            name: String,
+            up: bool,
+        }
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Hosts(#[serde(with = "crate::serde::as_map::omit_key")] LookupVec<Host>);
+
+        let hosts = Hosts(LookupVec::from([Host { name: "a".to_string(), up: true }]));
+        let json = serde_json::to_string(&hosts).unwrap();
+        assert_eq!(json, r#"{"a":{"up":true}}"#);
+        assert_eq!(serde_json::from_str::<Hosts>(&json).unwrap(), hosts);
+        assert_eq!(serde_json::from_str::<Hosts>(r#"{"a":{"hostname":"a","up":true}}"#).unwrap(), hosts);
+    }
+}
@@ -0,0 +1,392 @@
+//! A buffered copy of any self-describing value, so that it can be inspected
+//! before deciding what to deserialize it as.
+
+use serde::de;
+use serde::de::DeserializeSeed;
+use serde::de::EnumAccess;
+use serde::de::IntoDeserializer;
+use serde::de::MapAccess;
+use serde::de::SeqAccess;
+use serde::de::VariantAccess;
+use serde::de::Visitor;
+use serde::de::value::MapDeserializer;
+use serde::de::value::SeqDeserializer;
+use serde::Deserialize;
+use serde::Deserializer;
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Content {
+    Bool(bool),
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    Char(char),
+    String(String),
+    Bytes(Vec<u8>),
+    None,
+    Some(Box<Content>),
+    Unit,
+    Newtype(Box<Content>),
+    Seq(Vec<Content>),
+    Map(Vec<(Content, Content)>),
+}
+
+impl Content {
+    /// Removes the map entry with the string key `key` and returns its value.
+    pub(crate) fn remove(&mut self, key: &str) -> Option<Content> {
+        match self {
+            Content::Map(entries) => {
+                let index = entries.iter()
+                    .position(|(k, _)| matches!(k, Content::String(k) if k == key))?;
+                Some(entries.remove(index).1)
+            },
+            _ => None,
+        }
+    }
+
+    pub(crate) fn deserialize_into<'de, T, E>(self) -> Result<T, E>
+    where
+        T: Deserialize<'de>,
+        E: de::Error,
+    {
+        T::deserialize(ContentDeserializer::new(self))
+    }
+}
+
+impl<'de> Deserialize<'de> for Content {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ContentVisitor)
+    }
+}
+
+struct ContentVisitor;
+
+impl<'de> Visitor<'de> for ContentVisitor {
+    type Value = Content;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Content, E> {
+        Ok(Content::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Content, E> {
+        Ok(Content::I64(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Content, E> {
+        Ok(Content::U64(v))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Content, E> {
+        Ok(Content::F64(v))
+    }
+
+    fn visit_char<E: de::Error>(self, v: char) -> Result<Content, E> {
+        Ok(Content::Char(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Content, E> {
+        Ok(Content::String(v.into()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Content, E> {
+        Ok(Content::String(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Content, E> {
+        Ok(Content::Bytes(v.into()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Content, E> {
+        Ok(Content::Bytes(v))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Content, E> {
+        Ok(Content::None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Content, D::Error> {
+        Content::deserialize(deserializer).map(|v| Content::Some(Box::new(v)))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Content, E> {
+        Ok(Content::Unit)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<Content, D::Error> {
+        Content::deserialize(deserializer).map(|v| Content::Newtype(Box::new(v)))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Content, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Content::Seq(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Content, A::Error> {
+        let mut entries = Vec::new();
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(Content::Map(entries))
+    }
+}
+
+pub(crate) struct ContentDeserializer<E> {
+    content: Content,
+    marker: PhantomData<E>,
+}
+
+impl<E> ContentDeserializer<E> {
+    pub(crate) fn new(content: Content) -> Self {
+        ContentDeserializer { content, marker: PhantomData }
+    }
+}
+
+impl<'de, E: de::Error> IntoDeserializer<'de, E> for Content {
+    type Deserializer = ContentDeserializer<E>;
+
+    fn into_deserializer(self) -> ContentDeserializer<E> {
+        ContentDeserializer::new(self)
+    }
+}
+
+impl<'de, E: de::Error> Deserializer<'de> for ContentDeserializer<E> {
+    type Error = E;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        match self.content {
+            Content::Bool(v) => visitor.visit_bool(v),
+            Content::U64(v) => visitor.visit_u64(v),
+            Content::I64(v) => visitor.visit_i64(v),
+            Content::F64(v) => visitor.visit_f64(v),
+            Content::Char(v) => visitor.visit_char(v),
+            Content::String(v) => visitor.visit_string(v),
+            Content::Bytes(v) => visitor.visit_byte_buf(v),
+            Content::None => visitor.visit_none(),
+            Content::Some(v) => visitor.visit_some(ContentDeserializer::new(*v)),
+            Content::Unit => visitor.visit_unit(),
+            Content::Newtype(v) => visitor.visit_newtype_struct(ContentDeserializer::new(*v)),
+            Content::Seq(items) => {
+                let mut seq = SeqDeserializer::new(items.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            },
+            Content::Map(entries) => {
+                let mut map = MapDeserializer::new(entries.into_iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            },
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        match self.content {
+            Content::None | Content::Unit => visitor.visit_none(),
+            Content::Some(v) => visitor.visit_some(ContentDeserializer::new(*v)),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        match self.content {
+            Content::Newtype(v) => visitor.visit_newtype_struct(ContentDeserializer::new(*v)),
+            _ => visitor.visit_newtype_struct(self),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        match self.content {
+            // A unit variant, `"Variant"`
+            Content::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            // Any other variant, `{"Variant": value}`
+            Content::Map(mut entries) if entries.len() == 1 => {
+                let (variant, value) = entries.pop().unwrap();
+                visitor.visit_enum(ContentEnum { variant, value, marker: PhantomData })
+            },
+            other => Err(de::Error::invalid_type(unexpected(&other), &"an enum variant")),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+fn unexpected(content: &Content) -> de::Unexpected<'_> {
+    match content {
+        Content::Bool(v) => de::Unexpected::Bool(*v),
+        Content::U64(v) => de::Unexpected::Unsigned(*v),
+        Content::I64(v) => de::Unexpected::Signed(*v),
+        Content::F64(v) => de::Unexpected::Float(*v),
+        Content::Char(v) => de::Unexpected::Char(*v),
+        Content::String(v) => de::Unexpected::Str(v),
+        Content::Bytes(v) => de::Unexpected::Bytes(v),
+        Content::None | Content::Some(_) => de::Unexpected::Option,
+        Content::Unit => de::Unexpected::Unit,
+        Content::Newtype(_) => de::Unexpected::NewtypeStruct,
+        Content::Seq(_) => de::Unexpected::Seq,
+        Content::Map(_) => de::Unexpected::Map,
+    }
+}
+
+struct ContentEnum<E> {
+    variant: Content,
+    value: Content,
+    marker: PhantomData<E>,
+}
+
+impl<'de, E: de::Error> EnumAccess<'de> for ContentEnum<E> {
+    type Error = E;
+    type Variant = ContentDeserializer<E>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant), E> {
+        let variant = seed.deserialize(ContentDeserializer::<E>::new(self.variant))?;
+        Ok((variant, ContentDeserializer::new(self.value)))
+    }
+}
+
+impl<'de, E: de::Error> VariantAccess<'de> for ContentDeserializer<E> {
+    type Error = E;
+
+    fn unit_variant(self) -> Result<(), E> {
+        match self.content {
+            Content::Unit | Content::None => Ok(()),
+            other => Err(de::Error::invalid_type(unexpected(&other), &"a unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, E> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, E> {
+        self.deserialize_any(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, E> {
+        self.deserialize_any(visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    // As of 7/2025 alloc::prelude is nightly-only
+    use alloc::borrow::ToOwned;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Point {
+        x: i32,
+        y: Option<u8>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Meters(f64);
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Shape {
+        Empty,
+        Circle(Meters),
+        Line(Point, Point),
+        Rect { width: u32, height: u32 },
+    }
+
+    /// Buffers `json` as `Content`, then deserializes it as `T`.
+    fn through_content<T: for<'de> Deserialize<'de>>(json: &str) -> Result<T, serde_json::Error> {
+        let content: Content = serde_json::from_str(json).unwrap();
+        content.deserialize_into()
+    }
+
+    #[test]
+    fn test_buffer() {
+        let content: Content = serde_json::from_str(r#"{"a": [1, -2, 0.5, null, true], "b": "c"}"#).unwrap();
+        assert_eq!(content, Content::Map(vec![
+            (Content::String("a".to_owned()), Content::Seq(vec![
+                Content::U64(1),
+                Content::I64(-2),
+                Content::F64(0.5),
+                Content::Unit,
+                Content::Bool(true),
+            ])),
+            (Content::String("b".to_owned()), Content::String("c".to_owned())),
+        ]));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut content: Content = serde_json::from_str(r#"{"a": 1, "b": 2}"#).unwrap();
+        assert_eq!(content.remove("a"), Some(Content::U64(1)));
+        assert_eq!(content.remove("a"), None);
+        assert_eq!(content, Content::Map(vec![(Content::String("b".to_owned()), Content::U64(2))]));
+        assert_eq!(Content::U64(1).remove("a"), None);
+    }
+
+    #[test]
+    fn test_deserialize_into() {
+        assert_eq!(through_content::<Point>(r#"{"x": -1, "y": 2}"#).unwrap(), Point { x: -1, y: Some(2) });
+        assert_eq!(through_content::<Point>(r#"{"x": 1, "y": null}"#).unwrap(), Point { x: 1, y: None });
+        assert_eq!(through_content::<Meters>("1.5").unwrap(), Meters(1.5));
+        assert_eq!(through_content::<Vec<Option<u8>>>("[1, null]").unwrap(), vec![Some(1), None]);
+        assert!(through_content::<Vec<u8>>("[]").unwrap().is_empty());
+
+        let err = through_content::<Point>(r#"{"x": "one"}"#).unwrap_err().to_string();
+        assert!(err.starts_with("invalid type: string \"one\", expected i32"), "{err}");
+        let err = through_content::<(u8,)>("[1, 2]").unwrap_err().to_string();
+        assert!(err.starts_with("invalid length 2"), "{err}");
+    }
+
+    #[test]
+    fn test_deserialize_enum() {
+        assert_eq!(through_content::<Shape>(r#""Empty""#).unwrap(), Shape::Empty);
+        assert_eq!(through_content::<Shape>(r#"{"Circle": 2.0}"#).unwrap(), Shape::Circle(Meters(2.0)));
+        assert_eq!(
+            through_content::<Shape>(r#"{"Line": [{"x": 0, "y": null}, {"x": 1, "y": 1}]}"#).unwrap(),
+            Shape::Line(Point { x: 0, y: None }, Point { x: 1, y: Some(1) }),
+        );
+        assert_eq!(
+            through_content::<Shape>(r#"{"Rect": {"width": 2, "height": 3}}"#).unwrap(),
+            Shape::Rect { width: 2, height: 3 },
+        );
+
+        let err = through_content::<Shape>(r#"{"Empty": 1}"#).unwrap_err().to_string();
+        assert!(err.starts_with("invalid type: integer `1`, expected a unit variant"), "{err}");
+        let err = through_content::<Shape>("[]").unwrap_err().to_string();
+        assert!(err.starts_with("invalid type: sequence, expected an enum variant"), "{err}");
+    }
+}
@@ -0,0 +1,603 @@
+//! A `LookupVec` is serialized as a sequence of its items, in order, so
+//! non-self-describing binary formats such as postcard and bincode encode it
+//! exactly like a `Vec<T>`. Decoding checks for duplicate keys as with any
+//! other format.
+//!
+//! ```
+//! # #[cfg(feature = "derive")] {
+//! # use lookupvec::Lookup;
+//! # use lookupvec::LookupVec;
+//! # use serde::Deserialize;
+//! # use serde::Serialize;
+//! #[derive(Debug, PartialEq, Serialize, Deserialize, Lookup)]
+//! struct Item {
+//!     #[lookup_key]
+//!     id: u32,
+//!     name: String,
+//! }
+//!
+//! let vec = LookupVec::from([Item { id: 1, name: "a".to_owned() }]);
+//! let bytes = postcard::to_allocvec(&vec)?;
+//! assert_eq!(bytes, postcard::to_allocvec(&vec![Item { id: 1, name: "a".to_owned() }])?);
+//! let decoded: LookupVec<Item> = postcard::from_bytes(&bytes)?;
+//! assert_eq!(decoded, vec);
+//! # }
+//! # Ok::<(), postcard::Error>(())
+//! ```
+
+pub mod as_map;
+mod content;
+pub mod overlay;
+
+pub use overlay::overlay;
+pub use overlay::overlay_with;
+
+use crate::core::cautious_capacity;
+use crate::duplicate::DuplicatePolicy;
+use crate::LookupVec;
+use crate::Lookup;
+
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::de::Error as DeError;
+use serde::de::IntoDeserializer;
+use serde::de::value::SeqDeserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+#[allow(unused_imports)]
+use core::error::Error as _;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::BuildHasher;
+
+impl<T, S> Serialize for LookupVec<T, S> 
+where 
+    T: Lookup + Serialize,
+    //S: BuildHasher,
+{
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        // Serialize as a sequence/array of values
+        use serde::ser::SerializeSeq;
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for value in self.iter() {
+            seq.serialize_element(value)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de, T, S> Deserialize<'de> for LookupVec<T, S>
+where
+    T: Lookup + Deserialize<'de>,
+    S: BuildHasher + Default,
+{
+    /// ***Fails*** at the first item with the same key as an earlier one. See
+    /// the `last_wins`, `first_wins` and `all_duplicates` modules for other
+    /// ways of handling duplicates.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_seq(deserializer, Duplicates::FailFast)
+    }
+
+    /// Reuses the allocation of `place`. Its contents are unspecified if this
+    /// fails.
+    fn deserialize_in_place<D>(deserializer: D, place: &mut Self) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_seq_into(deserializer, place, Duplicates::FailFast)
+    }
+}
+
+/// How a sequence with duplicate keys is deserialized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Duplicates {
+    /// Fail at the first duplicate.
+    FailFast,
+    /// Fail after reading the whole sequence, reporting every duplicate.
+    ReportAll,
+    /// Keep the last item with each key, at the position of the first.
+    LastWins,
+    /// Keep the first item with each key.
+    FirstWins,
+}
+
+fn deserialize_seq<'de, T, S, D>(deserializer: D, duplicates: Duplicates) -> Result<LookupVec<T, S>, D::Error>
+where
+    T: Lookup + Deserialize<'de>,
+    S: BuildHasher + Default,
+    D: Deserializer<'de>,
+{
+    let mut vec = LookupVec::with_hasher(S::default());
+    deserialize_seq_into(deserializer, &mut vec, duplicates)?;
+    Ok(vec)
+}
+
+/// Replaces the items of `vec` with the deserialized sequence, keeping its
+/// allocation and hasher.
+fn deserialize_seq_into<'de, T, S, D>(
+    deserializer: D,
+    vec: &mut LookupVec<T, S>,
+    duplicates: Duplicates,
+) -> Result<(), D::Error>
+where
+    T: Lookup + Deserialize<'de>,
+    S: BuildHasher,
+    D: Deserializer<'de>,
+{
+    struct LookupVecVisitor<'a, T: Lookup, S> {
+        vec: &'a mut LookupVec<T, S>,
+        duplicates: Duplicates,
+    }
+
+    impl<'de, T, S> serde::de::Visitor<'de> for LookupVecVisitor<'_, T, S>
+    where
+        T: Lookup + Deserialize<'de>,
+        S: BuildHasher,
+    {
+        type Value = ();
+
+        fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+            formatter.write_str("a sequence of values")
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+        where
+            A: serde::de::SeqAccess<'de>,
+        {
+            let vec = self.vec;
+            vec.clear();
+            vec.reserve(cautious_capacity::<T>(seq.size_hint()));
+            // Position in the sequence of each item in `vec`, which differ once
+            // duplicates have been skipped, and its entry in `found` once it
+            // has duplicates
+            let mut positions: Vec<(usize, Option<usize>)> = Vec::new();
+            let mut found: Vec<(T::Key, Vec<usize>)> = Vec::new();
+
+            let mut position = 0;
+            while let Some(value) = seq.next_element()? {
+                let policy = match self.duplicates {
+                    Duplicates::LastWins => DuplicatePolicy::Replace,
+                    Duplicates::FirstWins => DuplicatePolicy::KeepFirst,
+                    Duplicates::FailFast | Duplicates::ReportAll => DuplicatePolicy::Error,
+                };
+                match vec.push_with(value, policy) {
+                    Ok((_, None)) => positions.push((position, None)),
+                    Ok(_) => {},
+                    Err(err) => {
+                        let (first, entry) = &mut positions[err.index()];
+                        let entry = *entry.get_or_insert_with(|| {
+                            found.push((err.keys()[0].clone(), vec![*first]));
+                            found.len() - 1
+                        });
+                        found[entry].1.push(position);
+                        if self.duplicates == Duplicates::FailFast {
+                            break;
+                        }
+                    },
+                }
+                position += 1;
+            }
+
+            if !found.is_empty() {
+                return Err(A::Error::custom(DuplicateKeys(&found)));
+            }
+            Ok(())
+        }
+    }
+
+    deserializer.deserialize_seq(LookupVecVisitor { vec, duplicates })
+}
+
+/// Error message for items with the same key as an earlier item, given as
+/// each duplicated key and the positions of all items with it.
+struct DuplicateKeys<'a, K>(&'a [(K, Vec<usize>)]);
+
+impl<K: fmt::Debug> fmt::Display for DuplicateKeys<'_, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.len() > 1 {
+            write!(f, "Found {} duplicate keys: ", self.0.len())?;
+        } else {
+            f.write_str("Found duplicate key ")?;
+        }
+        for (i, (key, positions)) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{key:?} at indices ")?;
+            for (j, position) in positions.iter().enumerate() {
+                if j > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{position}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+macro_rules! duplicates_module {
+    ($(#[$attr:meta])* $name:ident, $duplicates:expr) => {
+        $(#[$attr])*
+        pub mod $name {
+            use super::*;
+
+            pub fn serialize<T, S, Ser>(vec: &LookupVec<T, S>, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+            where
+                T: Lookup + Serialize,
+                Ser: Serializer,
+            {
+                vec.serialize(serializer)
+            }
+
+            pub fn deserialize<'de, T, S, D>(deserializer: D) -> Result<LookupVec<T, S>, D::Error>
+            where
+                T: Lookup + Deserialize<'de>,
+                S: BuildHasher + Default,
+                D: Deserializer<'de>,
+            {
+                deserialize_seq(deserializer, $duplicates)
+            }
+        }
+    };
+}
+
+duplicates_module!(
+    /// Deserializes a sequence in which a later item replaces an earlier item
+    /// with the same key, keeping the earlier item's position. For use with
+    /// `#[serde(with = "lookupvec::serde::last_wins")]`.
+    last_wins,
+    Duplicates::LastWins
+);
+
+duplicates_module!(
+    /// Deserializes a sequence in which later items with the same key as an
+    /// earlier item are ignored. For use with
+    /// `#[serde(with = "lookupvec::serde::first_wins")]`.
+    first_wins,
+    Duplicates::FirstWins
+);
+
+duplicates_module!(
+    /// Like the default `Deserialize` impl, but reads the whole sequence and
+    /// reports every duplicate key in the error. For use with
+    /// `#[serde(with = "lookupvec::serde::all_duplicates")]`.
+    all_duplicates,
+    Duplicates::ReportAll
+);
+
+impl<'de, T, S, E> IntoDeserializer<'de, E> for LookupVec<T, S>
+where
+    T: Lookup + IntoDeserializer<'de, E>,
+    S: BuildHasher,
+    E: DeError,
+{
+    type Deserializer = SeqDeserializer<<Self as IntoIterator>::IntoIter, E>;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        SeqDeserializer::new(self.into_iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde::{Deserialize, Serialize};
+
+    // As of 7/2025 alloc::prelude is nightly-only
+    use alloc::borrow::ToOwned;
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct TestItem {
+        id: String,
+        value: i32,
+    }
+
+    impl Lookup for TestItem {
+        type Key = String;
+        fn key(&self) -> String {
+            self.id.clone()
+        }
+    }
+
+    impl<'de> IntoDeserializer<'de, serde_json::Error> for TestItem {
+        type Deserializer = serde_json::Value;
+
+        fn into_deserializer(self) -> Self::Deserializer {
+            serde_json::to_value(&self).unwrap()
+        }
+    }
+
+    fn create_test_item(id: &str, value: i32) -> TestItem {
+        TestItem {
+            id: id.to_owned(),
+            value,
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct TestItemIntKey {
+        id: u64,
+        value: String,
+    }
+
+    impl Lookup for TestItemIntKey {
+        type Key = u64;
+        fn key(&self) -> u64 {
+            self.id
+        }
+    }
+
+    fn create_test_item_int_key(id: u64, value: &str) -> TestItemIntKey {
+        TestItemIntKey {
+            id,
+            value: value.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_serialize_empty() {
+        let vec: LookupVec<TestItem> = LookupVec::new();
+        let json = serde_json::to_string(&vec).unwrap();
+        assert_eq!(json, "[]");
+    }
+
+    #[test]
+    fn test_deserialize_empty() {
+        let json = "[]";
+        let vec: LookupVec<TestItem> = serde_json::from_str(json).unwrap();
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    fn test_serialize_single_item() {
+        let mut vec = LookupVec::<_>::new();
+        vec.push(create_test_item("test1", 42));
+        
+        let json = serde_json::to_string(&vec).unwrap();
+        assert_eq!(json, r#"[{"id":"test1","value":42}]"#);
+    }
+
+    #[test]
+    fn test_deserialize_single_item() {
+        let json = r#"[{"id":"test1","value":42}]"#;
+        let vec: LookupVec<TestItem> = serde_json::from_str(json).unwrap();
+        
+        assert_eq!(vec.len(), 1);
+        let item = vec.get("test1").unwrap();
+        assert_eq!(item.value, 42);
+    }
+
+    #[test]
+    fn test_serialize_multiple_items() {
+        let mut vec = LookupVec::<_>::new();
+        vec.push(create_test_item("test1", 1));
+        vec.push(create_test_item("test2", 2));
+        vec.push(create_test_item("test3", 3));
+
+        let json = serde_json::to_string(&vec).unwrap();
+        assert_eq!(json, r#"[{"id":"test1","value":1},{"id":"test2","value":2},{"id":"test3","value":3}]"#);
+    }
+
+    #[test]
+    fn test_deserialize_multiple_items() {
+        let json = r#"[
+            {"id":"test1","value":1},
+            {"id":"test2","value":2},
+            {"id":"test3","value":3}
+        ]"#;
+        let vec: LookupVec<TestItem> = serde_json::from_str(json).unwrap();
+
+        assert_eq!(vec.len(), 3);
+        assert_eq!(vec.get("test1").unwrap().value, 1);
+        assert_eq!(vec.get("test2").unwrap().value, 2);
+        assert_eq!(vec.get("test3").unwrap().value, 3);
+    }
+
+    #[test]
+    fn test_roundtrip_serialization() {
+        let mut original = LookupVec::<_>::new();
+        original.push(create_test_item("a", 1));
+        original.push(create_test_item("b", 2));
+
+        let json = serde_json::to_string(&original).unwrap();
+        let deserialized: LookupVec<TestItem> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(original.len(), deserialized.len());
+        assert_eq!(original.get("a").unwrap().value, deserialized.get("a").unwrap().value);
+        assert_eq!(original.get("b").unwrap().value, deserialized.get("b").unwrap().value);
+    }
+
+    #[test]
+    fn test_roundtrip_serialization_int_key() {
+        let mut original = LookupVec::<_>::new();
+        original.push(create_test_item_int_key(10, "a"));
+        original.push(create_test_item_int_key(20, "b"));
+
+        let json = serde_json::to_string(&original).unwrap();
+        assert_eq!(json, r#"[{"id":10,"value":"a"},{"id":20,"value":"b"}]"#);
+
+        let deserialized: LookupVec<TestItemIntKey> = serde_json::from_str(&json).unwrap();
+        assert_eq!(original.len(), deserialized.len());
+        assert_eq!(original.get(&10).unwrap().value, deserialized.get(&10).unwrap().value);
+        assert_eq!(original.get(&20).unwrap().value, deserialized.get(&20).unwrap().value);
+    }
+
+    #[test]
+    fn test_deserialize_invalid_json() {
+        let json = r#"[{"id":"test1","value":1},]"#; // Invalid trailing comma
+        let result: Result<LookupVec<TestItem>, _> = serde_json::from_str(json);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_order_preservation() {
+        let json = r#"[
+            {"id":"c","value":3},
+            {"id":"a","value":1},
+            {"id":"b","value":2}
+        ]"#;
+        let vec: LookupVec<TestItem> = serde_json::from_str(json).unwrap();
+
+        let keys: Vec<_> = vec.keys().collect();
+        assert_eq!(keys, vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn test_duplicate_keys_fail() {
+        let json = r#"[
+            {"id":"a","value":1},
+            {"id":"b","value":2},
+            {"id":"b","value":2}
+        ]"#;
+        let result: Result<LookupVec<TestItem>, _> = serde_json::from_str(json);
+        assert!(result.is_err());
+        let err_string = result.unwrap_err().to_string();
+        assert!(
+            err_string.contains(r#"Found duplicate key "b" at indices 1, 2"#),
+            "Unexpected error string: {}",
+            err_string,
+        );
+    }
+
+    #[test]
+    fn test_duplicate_modes() {
+        #[derive(Debug, Deserialize)]
+        struct Modes {
+            #[serde(with = "crate::serde::last_wins")]
+            last: LookupVec<TestItem>,
+            #[serde(with = "crate::serde::first_wins")]
+            first: LookupVec<TestItem>,
+        }
+
+        let items = r#"[
+            {"id":"a","value":1},
+            {"id":"b","value":2},
+            {"id":"a","value":3},
+            {"id":"c","value":4},
+            {"id":"b","value":5}
+        ]"#;
+        let json = format!(r#"{{"last":{items},"first":{items}}}"#);
+        let modes: Modes = serde_json::from_str(&json).unwrap();
+        fn values(vec: &LookupVec<TestItem>) -> Vec<(&str, i32)> {
+            vec.iter().map(|item| (item.id.as_str(), item.value)).collect()
+        }
+        assert_eq!(values(&modes.last), vec![("a", 3), ("b", 5), ("c", 4)]);
+        assert_eq!(values(&modes.first), vec![("a", 1), ("b", 2), ("c", 4)]);
+
+        #[derive(Debug, Deserialize)]
+        struct All(#[serde(with = "crate::serde::all_duplicates")] LookupVec<TestItem>);
+
+        let all: All = serde_json::from_str(r#"[{"id":"a","value":1},{"id":"b","value":2}]"#).unwrap();
+        assert_eq!(all.0.len(), 2);
+        let err = serde_json::from_str::<All>(items).unwrap_err().to_string();
+        assert!(
+            err.starts_with(r#"Found 2 duplicate keys: "a" at indices 0, 2; "b" at indices 1, 4"#),
+            "Unexpected error string: {err}",
+        );
+        let items = r#"[{"id":"a","value":1},{"id":"x","value":1},{"id":"x","value":2},{"id":"x","value":3}]"#;
+        let err = serde_json::from_str::<All>(items).unwrap_err().to_string();
+        assert!(
+            err.starts_with(r#"Found duplicate key "x" at indices 1, 2, 3"#),
+            "Unexpected error string: {err}",
+        );
+    }
+
+    #[test]
+    fn test_cautious_capacity() {
+        assert_eq!(cautious_capacity::<TestItem>(None), 0);
+        assert_eq!(cautious_capacity::<TestItem>(Some(10)), 10);
+        let max = cautious_capacity::<TestItem>(Some(usize::MAX));
+        assert!(max > 1000 && max * size_of::<TestItem>() <= 1024 * 1024, "{max}");
+    }
+
+    #[test]
+    fn test_into_deserializer() {
+        let mut vec = LookupVec::<_>::new();
+        vec.push(create_test_item("test1", 42));
+        vec.push(create_test_item("test2", 84));
+
+        let deserializer = vec.into_deserializer();
+        let deserialized: Vec<TestItem> = Vec::deserialize(deserializer).unwrap();
+
+        assert_eq!(deserialized.len(), 2);
+        assert_eq!(deserialized[0].id, "test1");
+        assert_eq!(deserialized[0].value, 42);
+        assert_eq!(deserialized[1].id, "test2");
+        assert_eq!(deserialized[1].value, 84);
+    }
+
+    #[test]
+    fn test_deserialize_in_place() {
+        let mut vec: LookupVec<TestItem> = LookupVec::new();
+        vec.push(create_test_item("old", 1));
+        vec.reserve(16);
+        let capacity = vec.capacity();
+
+        let json = r#"[{"id":"a","value":1},{"id":"b","value":2}]"#;
+        Deserialize::deserialize_in_place(&mut serde_json::Deserializer::from_str(json), &mut vec).unwrap();
+        assert_eq!(vec.iter().map(|item| item.id.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(vec.get("b").unwrap().value, 2);
+        assert!(!vec.contains_key("old"));
+        assert_eq!(vec.capacity(), capacity);
+
+        let json = r#"[{"id":"a","value":1},{"id":"a","value":2}]"#;
+        let result = Deserialize::deserialize_in_place(&mut serde_json::Deserializer::from_str(json), &mut vec);
+        assert!(result.unwrap_err().to_string().starts_with(r#"Found duplicate key "a" at indices 0, 1"#));
+    }
+
+    #[test]
+    fn test_postcard() {
+        let vec: LookupVec<TestItemIntKey> = LookupVec::from_iter([
+            create_test_item_int_key(7, "a"),
+            create_test_item_int_key(3, "b"),
+        ]);
+        let bytes = postcard::to_allocvec(&vec).unwrap();
+        let items: Vec<&TestItemIntKey> = vec.iter().collect();
+        assert_eq!(bytes, postcard::to_allocvec(&items).unwrap());
+
+        let decoded: LookupVec<TestItemIntKey> = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, vec);
+
+        let duplicated = vec![create_test_item_int_key(7, "a"), create_test_item_int_key(7, "b")];
+        let bytes = postcard::to_allocvec(&duplicated).unwrap();
+        let err = postcard::from_bytes::<LookupVec<TestItemIntKey>>(&bytes).unwrap_err();
+        assert_eq!(err, postcard::Error::SerdeDeCustom);
+    }
+
+    #[test]
+    fn test_bincode() {
+        let config = bincode::config::standard();
+        let vec: LookupVec<TestItem> = LookupVec::from_iter([
+            create_test_item("x", 1),
+            create_test_item("y", 2),
+        ]);
+        let bytes = bincode::serde::encode_to_vec(&vec, config).unwrap();
+        let items: Vec<&TestItem> = vec.iter().collect();
+        assert_eq!(bytes, bincode::serde::encode_to_vec(&items, config).unwrap());
+
+        let (decoded, len): (LookupVec<TestItem>, usize) = bincode::serde::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(len, bytes.len());
+        assert_eq!(decoded, vec);
+
+        let duplicated = vec![create_test_item("x", 1), create_test_item("x", 2)];
+        let bytes = bincode::serde::encode_to_vec(&duplicated, config).unwrap();
+        let err = bincode::serde::decode_from_slice::<LookupVec<TestItem>, _>(&bytes, config).unwrap_err();
+        assert!(matches!(
+            err,
+            bincode::error::DecodeError::OtherString(ref message)
+                if message == r#"Found duplicate key "x" at indices 0, 1"#
+        ), "{err:?}");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,340 @@
+//! Deserializes a layer of items on top of an existing `LookupVec`.
+
+use crate::core::KeyField;
+use crate::core::Lookup;
+use crate::core::with_key;
+use crate::merge::Merge;
+use crate::merge::MergeStrategy;
+use crate::serde::as_map::InjectKey;
+use crate::serde::content::Content;
+use crate::serde::content::ContentDeserializer;
+use crate::LookupVec;
+
+use serde::de;
+use serde::de::DeserializeSeed;
+use serde::de::IntoDeserializer;
+use serde::de::MapAccess;
+use serde::de::SeqAccess;
+use serde::de::Visitor;
+use serde::Deserialize;
+use serde::Deserializer;
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::BuildHasher;
+use core::marker::PhantomData;
+
+/// Name of the field that marks an entry as a deletion.
+pub const DELETE_FIELD: &str = "$delete";
+
+/// Deserializes a sequence or map of items on top of `vec`.
+///
+/// An item with a new key is appended, and an item with the key of an
+/// existing item replaces it in place. An entry with `"$delete": true`, such
+/// as `{"id": "x", "$delete": true}`, removes the item with its key.
+///
+/// A map is read like `as_map::omit_key`: entries may leave out the key field, which is
+/// filled in from the map key. This needs a self-describing format.
+///
+/// ***Fails*** without changing `vec` if any entry is invalid.
+pub fn overlay<'de, T, S, D>(deserializer: D, vec: &mut LookupVec<T, S>) -> Result<(), D::Error>
+where
+    T: KeyField + Deserialize<'de>,
+    T::Key: Deserialize<'de> + IntoDeserializer<'de, de::value::Error>,
+    S: BuildHasher,
+    D: Deserializer<'de>,
+{
+    overlay_with(deserializer, vec, Merge::KeepRight)
+}
+
+/// Like `overlay`, but merges each item with the existing item with its key
+/// using `strategy`.
+///
+/// ***Fails*** without changing `vec` if `strategy` rejects any item.
+pub fn overlay_with<'de, T, S, D, M>(
+    deserializer: D,
+    vec: &mut LookupVec<T, S>,
+    strategy: M,
+) -> Result<(), D::Error>
+where
+    T: KeyField + Deserialize<'de>,
+    T::Key: Deserialize<'de> + IntoDeserializer<'de, de::value::Error>,
+    S: BuildHasher,
+    D: Deserializer<'de>,
+    M: MergeStrategy<T>,
+{
+    deserializer.deserialize_any(OverlayVisitor { vec, strategy, marker: PhantomData })
+}
+
+/// An entry of the overlay, parsed before any entry is applied.
+enum Op<T: Lookup> {
+    Delete(T::Key),
+    Upsert(T),
+}
+
+struct OverlayVisitor<'a, T: Lookup, S, M> {
+    vec: &'a mut LookupVec<T, S>,
+    strategy: M,
+    marker: PhantomData<T>,
+}
+
+impl<'de, T, S, M> OverlayVisitor<'_, T, S, M>
+where
+    T: KeyField + Deserialize<'de>,
+    T::Key: Deserialize<'de> + IntoDeserializer<'de, de::value::Error>,
+    S: BuildHasher,
+    M: MergeStrategy<T>,
+{
+    /// Parses one entry, with the key from its map entry if there is one.
+    fn parse<E: de::Error>(&self, mut entry: Content, map_key: Option<T::Key>) -> Result<Op<T>, E> {
+        let delete = match entry.remove(DELETE_FIELD) {
+            None | Some(Content::Bool(false)) => false,
+            Some(Content::Bool(true)) => true,
+            Some(_) => return Err(E::custom(format_args!("expected a bool for `{DELETE_FIELD}`"))),
+        };
+
+        if delete {
+            let key = match (entry.remove(T::KEY_FIELD), map_key) {
+                (Some(key), map_key) => {
+                    let key: T::Key = key.deserialize_into()?;
+                    match map_key {
+                        Some(map_key) if map_key != key => return Err(key_mismatch(&map_key, &key)),
+                        _ => key,
+                    }
+                },
+                (None, Some(map_key)) => map_key,
+                (None, None) => return Err(E::missing_field(T::KEY_FIELD)),
+            };
+            return Ok(Op::Delete(key));
+        }
+        let Some(map_key) = map_key else {
+            return Ok(Op::Upsert(entry.deserialize_into()?));
+        };
+        let item = InjectKey::<T>::new(map_key.clone()).deserialize(ContentDeserializer::new(entry))?;
+        if !with_key(&item, |key| *key == map_key) {
+            return Err(key_mismatch(&map_key, &item.key()));
+        }
+        Ok(Op::Upsert(item))
+    }
+
+    /// Applies `ops` in order, after checking that the strategy accepts every
+    /// item it would merge.
+    fn apply<E: de::Error>(mut self, ops: Vec<Op<T>>) -> Result<(), E> {
+        // Replays the ops on references to the items, so no item is merged
+        // before all are checked
+        let mut view: LookupVec<&T> = self.vec.iter().collect();
+        for op in &ops {
+            match op {
+                Op::Delete(key) => {
+                    view.shift_remove(key);
+                },
+                Op::Upsert(item) => match with_key(item, |key| view.get_index_of(key)) {
+                    Some(index) if !self.strategy.accepts(view[index], item) => {
+                        return Err(E::custom(format_args!(
+                            "duplicate key {:?} of item at index {index}",
+                            item.key(),
+                        )));
+                    },
+                    Some(_) => {},
+                    None => {
+                        view.push(item);
+                    },
+                },
+            }
+        }
+
+        for op in ops {
+            match op {
+                Op::Delete(key) => {
+                    self.vec.shift_remove(&key);
+                },
+                Op::Upsert(item) => {
+                    let Some(mut existing) = with_key(&item, |key| self.vec.get_mut(key)) else {
+                        self.vec.push(item);
+                        continue;
+                    };
+                    self.strategy.merge(&mut existing, item);
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+fn key_mismatch<K: fmt::Debug, E: de::Error>(map_key: &K, key: &K) -> E {
+    E::custom(format_args!("entry {map_key:?} has a different key {key:?} in its value"))
+}
+
+impl<'de, T, S, M> Visitor<'de> for OverlayVisitor<'_, T, S, M>
+where
+    T: KeyField + Deserialize<'de>,
+    T::Key: Deserialize<'de> + IntoDeserializer<'de, de::value::Error>,
+    S: BuildHasher,
+    M: MergeStrategy<T>,
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence or map of items")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut ops = Vec::new();
+        while let Some(entry) = seq.next_element()? {
+            ops.push(self.parse(entry, None)?);
+        }
+        self.apply(ops)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<(), A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut ops = Vec::new();
+        while let Some(key) = map.next_key()? {
+            let entry = map.next_value()?;
+            ops.push(self.parse(entry, Some(key))?);
+        }
+        self.apply(ops)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use lookupvec_derive::Lookup;
+
+    // As of 7/2025 alloc::prelude is nightly-only
+    use alloc::borrow::ToOwned;
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    #[derive(Debug, Clone, PartialEq, Deserialize, Lookup)]
+    struct Service {
+        #[lookup_key]
+        id: String,
+        port: u16,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+
+    fn service(id: &str, port: u16) -> Service {
+        Service { id: id.to_owned(), port, tags: Vec::new() }
+    }
+
+    fn base() -> LookupVec<Service> {
+        LookupVec::from([service("web", 80), service("db", 5432), service("cache", 6379)])
+    }
+
+    fn ports(vec: &LookupVec<Service>) -> Vec<(&str, u16)> {
+        vec.iter().map(|item| (item.id.as_str(), item.port)).collect()
+    }
+
+    #[test]
+    fn test_overlay_seq() {
+        let mut vec = base();
+        let json = r#"[
+            {"id": "db", "port": 5433},
+            {"id": "cache", "$delete": true},
+            {"id": "queue", "port": 5672},
+            {"id": "missing", "$delete": true}
+        ]"#;
+        overlay(&mut serde_json::Deserializer::from_str(json), &mut vec).unwrap();
+        assert_eq!(ports(&vec), vec![("web", 80), ("db", 5433), ("queue", 5672)]);
+    }
+
+    #[test]
+    fn test_overlay_map() {
+        let mut vec = base();
+        let json = r#"{
+            "web": {"port": 8080, "$delete": false},
+            "db": {"$delete": true},
+            "queue": {"id": "queue", "port": 5672}
+        }"#;
+        overlay(&mut serde_json::Deserializer::from_str(json), &mut vec).unwrap();
+        assert_eq!(ports(&vec), vec![("web", 8080), ("cache", 6379), ("queue", 5672)]);
+
+        let json = r#"{"web": {"id": "www", "port": 80}}"#;
+        let err = overlay(&mut serde_json::Deserializer::from_str(json), &mut vec).unwrap_err();
+        assert!(err.to_string().starts_with(r#"entry "web" has a different key "www""#), "{err}");
+    }
+
+    #[test]
+    fn test_overlay_map_non_string_keys() {
+        #[derive(Debug, Clone, PartialEq, Deserialize, Lookup)]
+        struct Versioned {
+            #[lookup_key]
+            id: u32,
+            v: u32,
+        }
+
+        let mut vec = LookupVec::from([Versioned { id: 4, v: 0 }, Versioned { id: 5, v: 0 }]);
+        let json = r#"{"5": {"v": 1}, "4": {"$delete": true}, "6": {"id": 6, "v": 2}}"#;
+        overlay(&mut serde_json::Deserializer::from_str(json), &mut vec).unwrap();
+        assert_eq!(vec, LookupVec::from([Versioned { id: 5, v: 1 }, Versioned { id: 6, v: 2 }]));
+
+        let json = r#"{"5": {"id": 7, "$delete": true}}"#;
+        let err = overlay(&mut serde_json::Deserializer::from_str(json), &mut vec).unwrap_err();
+        assert!(err.to_string().starts_with("entry 5 has a different key 7"), "{err}");
+        let json = r#"{"five": {"v": 1}}"#;
+        overlay(&mut serde_json::Deserializer::from_str(json), &mut vec).unwrap_err();
+    }
+
+    #[test]
+    fn test_overlay_with() {
+        let mut vec = base();
+        vec.get_mut("web").unwrap().tags.push("public".to_owned());
+        let json = r#"[{"id": "web", "port": 443, "tags": ["tls"]}]"#;
+        let merge = |existing: &mut Service, incoming: Service| {
+            existing.port = incoming.port;
+            existing.tags.extend(incoming.tags);
+        };
+        overlay_with(&mut serde_json::Deserializer::from_str(json), &mut vec, merge).unwrap();
+        assert_eq!(vec.get("web").unwrap().tags, vec!["public", "tls"]);
+        assert_eq!(vec.get("web").unwrap().port, 443);
+
+        let err = overlay_with(&mut serde_json::Deserializer::from_str(json), &mut vec, Merge::Error)
+            .unwrap_err();
+        assert!(err.to_string().starts_with(r#"duplicate key "web" of item at index 0"#), "{err}");
+    }
+
+    #[test]
+    fn test_overlay_invalid_entry() {
+        let mut vec = base();
+        let json = r#"[{"id": "web", "port": "eighty"}]"#;
+        let err = overlay(&mut serde_json::Deserializer::from_str(json), &mut vec).unwrap_err();
+        assert!(err.to_string().contains("invalid type"), "{err}");
+
+        let json = r#"[{"id": "web", "$delete": "yes"}]"#;
+        let err = overlay(&mut serde_json::Deserializer::from_str(json), &mut vec).unwrap_err();
+        assert!(err.to_string().starts_with("expected a bool for `$delete`"), "{err}");
+        assert_eq!(vec, base());
+    }
+
+    #[test]
+    fn test_overlay_fails_unchanged() {
+        let mut vec = base();
+        let json = r#"[{"id": "web", "port": 8080}, {"id": "db", "port": "eighty"}]"#;
+        overlay(&mut serde_json::Deserializer::from_str(json), &mut vec).unwrap_err();
+        assert_eq!(vec, base());
+
+        let json = r#"[
+            {"id": "queue", "port": 5672},
+            {"id": "web", "$delete": true},
+            {"id": "web", "port": 8080},
+            {"id": "queue", "port": 5673}
+        ]"#;
+        let err = overlay_with(&mut serde_json::Deserializer::from_str(json), &mut vec, Merge::Error)
+            .unwrap_err();
+        assert!(err.to_string().starts_with(r#"duplicate key "queue" of item at index 2"#), "{err}");
+        assert_eq!(vec, base());
+
+        overlay(&mut serde_json::Deserializer::from_str(json), &mut vec).unwrap();
+        assert_eq!(ports(&vec), vec![("db", 5432), ("cache", 6379), ("queue", 5673), ("web", 8080)]);
+    }
+}
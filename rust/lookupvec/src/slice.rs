@@ -1,23 +1,434 @@
-// This code is currently unused. A `Slice` struct that wraps
-// `indexmap::map::Slice` would enable implementing Index for range types on
-// LookupVec.
+use crate::backend::Backend;
+use crate::backend::Indexed;
+use crate::backend::store::RawSlice;
 use crate::core::Lookup;
-//use crate::iter::*;
+use crate::core::with_key;
+use crate::guard::index_range;
+use crate::guard::simplify_range;
+use crate::iter::*;
 
-use indexmap::map::Slice as InnerSlice;
+use indexmap::Equivalent;
+use delegate::delegate;
 use ref_cast::RefCast;
 
+use alloc::boxed::Box;
+use core::cmp::Ordering;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::ops::Bound;
 use core::ops::Index;
+use core::ops::IndexMut;
+use core::ops::Range;
 use core::ops::RangeBounds;
+use core::ops::RangeFrom;
+use core::ops::RangeFull;
+use core::ops::RangeInclusive;
+use core::ops::RangeTo;
+use core::ops::RangeToInclusive;
 
+/// A dynamically-sized slice of items in a `LookupVec`.
+///
+/// A slice has no hash table of its own, so key lookups on a slice are
+/// linear scans. Use the `LookupVec` itself for O(1) lookup.
 #[derive(RefCast)]
 #[repr(transparent)]
-pub struct Slice<T: Lookup> ( InnerSlice<T::Key, T> );
+pub struct Slice<T: Lookup, B: Backend = Indexed> (pub(crate) B::Slice<T>);
 
-impl<I: RangeBounds<usize>, T: Lookup> Index<I> for Slice<T> {
-    type Output = Slice<T>;
+impl<T: Lookup, B: Backend> Slice<T, B> {
+    pub(crate) fn from_boxed(inner: Box<B::Slice<T>>) -> Box<Self> {
+        // SAFETY: `Slice` is a `repr(transparent)` wrapper around `B::Slice`
+        unsafe { Box::from_raw(Box::into_raw(inner) as *mut Self) }
+    }
+
+    pub fn new<'a>() -> &'a Self {
+        Self::ref_cast(B::Slice::empty())
+    }
+
+    pub fn new_mut<'a>() -> &'a mut Self {
+        Self::ref_cast_mut(B::Slice::empty_mut())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<&T> {
+        self.0.get(index)
+    }
+
+    pub fn get_index_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.0.get_mut(index)
+    }
+
+    pub fn get_range<R: RangeBounds<usize>>(&self, range: R) -> Option<&Self> {
+        let range = simplify_range(range, self.len())?;
+        Some(Self::ref_cast(self.0.range(range)))
+    }
+
+    pub fn get_range_mut<R: RangeBounds<usize>>(&mut self, range: R) -> Option<&mut Self> {
+        let range = simplify_range(range, self.len())?;
+        Some(Self::ref_cast_mut(self.0.range_mut(range)))
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.get_index(0)
+    }
+
+    pub fn first_mut(&mut self) -> Option<&mut T> {
+        self.get_index_mut(0)
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.get_index(self.len().checked_sub(1)?)
+    }
+
+    pub fn last_mut(&mut self) -> Option<&mut T> {
+        self.get_index_mut(self.len().checked_sub(1)?)
+    }
+
+    /// ***Panics*** if `index > len`.
+    pub fn split_at(&self, index: usize) -> (&Self, &Self) {
+        let (first, second) = self.0.split_at(index);
+        (Self::ref_cast(first), Self::ref_cast(second))
+    }
+
+    /// ***Panics*** if `index > len`.
+    pub fn split_at_mut(&mut self, index: usize) -> (&mut Self, &mut Self) {
+        let (first, second) = self.0.split_at_mut(index);
+        (Self::ref_cast_mut(first), Self::ref_cast_mut(second))
+    }
+
+    pub fn split_first(&self) -> Option<(&T, &Self)> {
+        let first = self.first()?;
+        Some((first, &self[1..]))
+    }
+
+    pub fn split_last(&self) -> Option<(&T, &Self)> {
+        let last = self.last()?;
+        Some((last, &self[..self.len() - 1]))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, B> {
+        Iter(self.0.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, B> {
+        IterMut(self.0.iter_mut())
+    }
+
+    pub fn keys(&self) -> Keys<'_, T, B> {
+        Keys(self.0.keys())
+    }
+
+    pub fn into_keys(self: Box<Self>) -> IntoKeys<T, B> {
+        IntoKeys(self.into_inner().into_keys())
+    }
+
+    /// Returns the position of the item with the given key.
+    ///
+    /// Computes in **O(n)** time, since a slice has no hash table.
+    pub fn get_index_of<Q>(&self, key: &Q) -> Option<usize>
+    where Q: ?Sized + Equivalent<T::Key> {
+        self.keys().position(|k| key.equivalent(&k))
+    }
+
+    /// Computes in **O(n)** time, since a slice has no hash table.
+    pub fn get<Q>(&self, key: &Q) -> Option<&T>
+    where Q: ?Sized + Equivalent<T::Key> {
+        self.get_index_of(key).and_then(|i| self.get_index(i))
+    }
+
+    /// Computes in **O(n)** time, since a slice has no hash table.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut T>
+    where Q: ?Sized + Equivalent<T::Key> {
+        self.get_index_of(key).and_then(|i| self.get_index_mut(i))
+    }
+
+    /// Computes in **O(n)** time, since a slice has no hash table.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where Q: ?Sized + Equivalent<T::Key> {
+        self.get_index_of(key).is_some()
+    }
+
+    /// Binary searches a slice sorted by key. See `slice::binary_search`.
+    pub fn binary_search_keys(&self, key: &T::Key) -> Result<usize, usize>
+    where T::Key: Ord {
+        self.binary_search_by(|item| with_key(item, |k| k.cmp(key)))
+    }
+
+    /// Binary searches a slice sorted in the order of `f`. See
+    /// `slice::binary_search_by`.
+    pub fn binary_search_by<F>(&self, f: F) -> Result<usize, usize>
+    where F: FnMut(&T) -> Ordering {
+        self.0.binary_search_by(f)
+    }
+
+    /// See `slice::binary_search_by_key`.
+    pub fn binary_search_by_key<Q, F>(&self, q: &Q, mut f: F) -> Result<usize, usize>
+    where F: FnMut(&T) -> Q, Q: Ord {
+        self.binary_search_by(|item| f(item).cmp(q))
+    }
+
+    /// Returns the index of the first item for which `pred` is false, in a
+    /// slice partitioned by `pred`. See `slice::partition_point`.
+    pub fn partition_point<P>(&self, pred: P) -> usize
+    where P: FnMut(&T) -> bool {
+        self.0.partition_point(pred)
+    }
+
+    fn into_inner(self: Box<Self>) -> Box<B::Slice<T>> {
+        // SAFETY: `Slice` is a `repr(transparent)` wrapper around `B::Slice`
+        unsafe { Box::from_raw(Box::into_raw(self) as *mut B::Slice<T>) }
+    }
+}
+
+impl<T: Lookup + fmt::Debug, B: Backend> fmt::Debug for Slice<T, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Lookup, B: Backend> Default for &Slice<T, B> {
+    fn default() -> Self {
+        Slice::new()
+    }
+}
+
+impl<T: Lookup, B: Backend> Default for &mut Slice<T, B> {
+    fn default() -> Self {
+        Slice::new_mut()
+    }
+}
+
+/// Mutable iterator over a `Slice`. Keys are checked by whoever handed out
+/// the slice, not per item.
+pub struct IterMut<'a, T: Lookup + 'a, B: Backend = Indexed> (B::SliceIterMut<'a, T>);
+impl<'a, T: Lookup, B: Backend> Iterator for IterMut<'a, T, B> {
+    type Item = &'a mut T;
+
+    delegate![
+        to self.0 {
+            fn next(&mut self) -> Option<&'a mut T>;
+            fn last(self) -> Option<&'a mut T>;
+            fn nth(&mut self, n: usize) -> Option<&'a mut T>;
+            fn size_hint(&self) -> (usize, Option<usize>);
+            fn count(self) -> usize;
+        }
+    ];
+}
+impl<'a, T: Lookup, B: Backend> DoubleEndedIterator for IterMut<'a, T, B> {
+    delegate![
+        to self.0 {
+            fn next_back(&mut self) -> Option<&'a mut T>;
+            fn nth_back(&mut self, n: usize) -> Option<&'a mut T>;
+        }
+    ];
+}
+impl<T: Lookup, B: Backend> ExactSizeIterator for IterMut<'_, T, B> {
+    fn len(&self) -> usize { self.0.len() }
+}
+impl<T: Lookup, B: Backend> FusedIterator for IterMut<'_, T, B> {}
+
+impl<'a, T: Lookup, B: Backend> IntoIterator for &'a Slice<T, B> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, B>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T: Lookup, B: Backend> IntoIterator for &'a mut Slice<T, B> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T, B>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T: Lookup, B: Backend> IntoIterator for Box<Slice<T, B>> {
+    type Item = T;
+    type IntoIter = IntoIter<T, B>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self.into_inner().into_iter())
+    }
+}
+
+impl<T: Lookup, B: Backend> Index<usize> for Slice<T, B> {
+    type Output = T;
+
+    /// ***Panics*** if `index` is out of bounds.
+    fn index(&self, index: usize) -> &T {
+        let len = self.len();
+        self.get_index(index)
+            .unwrap_or_else(|| panic!("index out of bounds: the len is {len} but the index is {index}"))
+    }
+}
+
+impl<T: Lookup, B: Backend> IndexMut<usize> for Slice<T, B> {
+    /// ***Panics*** if `index` is out of bounds.
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        let len = self.len();
+        self.get_index_mut(index)
+            .unwrap_or_else(|| panic!("index out of bounds: the len is {len} but the index is {index}"))
+    }
+}
+
+// A blanket `impl<I: RangeBounds<usize>> Index<I>` would conflict with
+// `Index<usize>`, so like indexmap we repeat the impls for each of the core
+// range types.
+macro_rules! impl_index {
+    ($($range:ty),*) => {$(
+        impl<T: Lookup, B: Backend> Index<$range> for Slice<T, B> {
+            type Output = Slice<T, B>;
+
+            /// ***Panics*** if `range` is out of bounds.
+            fn index(&self, range: $range) -> &Slice<T, B> {
+                Slice::ref_cast(self.0.range(index_range(range, self.len())))
+            }
+        }
+
+        impl<T: Lookup, B: Backend> IndexMut<$range> for Slice<T, B> {
+            /// ***Panics*** if `range` is out of bounds.
+            fn index_mut(&mut self, range: $range) -> &mut Slice<T, B> {
+                let range = index_range(range, self.len());
+                Slice::ref_cast_mut(self.0.range_mut(range))
+            }
+        }
+    )*}
+}
+impl_index!(
+    Range<usize>,
+    RangeFrom<usize>,
+    RangeFull,
+    RangeInclusive<usize>,
+    RangeTo<usize>,
+    RangeToInclusive<usize>,
+    (Bound<usize>, Bound<usize>)
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::*;
+    use pretty_assertions::assert_eq;
+
+    // As of 7/2025 alloc::prelude is nightly-only
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    fn keys(slice: &Slice<TestItem>) -> Vec<&str> {
+        slice.keys().map(|k| k.as_str()).collect()
+    }
+
+    #[test]
+    fn test_range_index() {
+        let vec = test_vec();
+        assert_eq!(keys(&vec[1..3]), vec!["b", "c"]);
+        assert_eq!(keys(&vec[..]), vec!["a", "b", "c", "d"]);
+        assert_eq!(keys(&vec[2..]), vec!["c", "d"]);
+        assert_eq!(keys(&vec[..2]), vec!["a", "b"]);
+        assert_eq!(keys(&vec[..=2]), vec!["a", "b", "c"]);
+        assert_eq!(keys(&vec[1..=1]), vec!["b"]);
+        assert_eq!(keys(&vec[(Bound::Excluded(0), Bound::Unbounded)]), vec!["b", "c", "d"]);
+        assert_eq!(keys(&vec[1..3][1..]), vec!["c"]);
+        assert!(vec[4..].is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_range_index_out_of_bounds() {
+        let vec = test_vec();
+        let _ = &vec[2..5];
+    }
+
+    #[test]
+    fn test_range_index_mut() {
+        let mut vec = test_vec();
+        for item in &mut vec[1..3] {
+            item.value *= 10;
+        }
+        vec[3..][0].value = 40;
+
+        let values: Vec<i32> = vec.iter().map(|item| item.value).collect();
+        assert_eq!(values, vec![1, 20, 30, 40]);
+    }
+
+    #[test]
+    fn test_get_range() {
+        let mut vec = test_vec();
+        assert_eq!(keys(vec.get_range(1..3).unwrap()), vec!["b", "c"]);
+        assert!(vec.get_range(3..5).is_none());
+
+        vec.get_range_mut(..1).unwrap()[0].value = 10;
+        assert_eq!(vec[0].value, 10);
+    }
+
+    #[test]
+    fn test_as_slice() {
+        let mut vec = test_vec();
+        assert_eq!(vec.as_slice().len(), 4);
+        assert_eq!(vec.as_slice().first().unwrap().id, "a");
+        assert_eq!(vec.as_slice().last().unwrap().id, "d");
+
+        vec.as_mut_slice().last_mut().unwrap().value = 40;
+        assert_eq!(vec.get("d").unwrap().value, 40);
+    }
+
+    #[test]
+    fn test_into_boxed_slice() {
+        let boxed = test_vec().into_boxed_slice();
+        assert_eq!(keys(&boxed), vec!["a", "b", "c", "d"]);
+
+        let items: Vec<TestItem> = boxed.into_iter().collect();
+        assert_eq!(items, vec![item("a", 1), item("b", 2), item("c", 3), item("d", 4)]);
+
+        let keys: Vec<String> = test_vec().into_boxed_slice().into_keys().collect();
+        assert_eq!(keys, vec!["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_slice_key_lookup() {
+        let mut vec = test_vec();
+        let slice = &vec[1..3];
+        assert_eq!(slice.get("c").unwrap().value, 3);
+        assert_eq!(slice.get_index_of("c"), Some(1));
+        assert!(slice.contains_key("b"));
+        assert!(!slice.contains_key("a"));
+        assert!(slice.get("d").is_none());
+
+        vec[1..3].get_mut("b").unwrap().value = 20;
+        assert_eq!(vec.get("b").unwrap().value, 20);
+    }
+
+    #[test]
+    fn test_slice_split() {
+        let vec = test_vec();
+        let (left, right) = vec[..].split_at(1);
+        assert_eq!(keys(left), vec!["a"]);
+        assert_eq!(keys(right), vec!["b", "c", "d"]);
+
+        let (first, rest) = vec[..].split_first().unwrap();
+        assert_eq!(first.id, "a");
+        assert_eq!(keys(rest), vec!["b", "c", "d"]);
+
+        let (last, rest) = vec[..].split_last().unwrap();
+        assert_eq!(last.id, "d");
+        assert_eq!(keys(rest), vec!["a", "b", "c"]);
+
+        assert!(Slice::<TestItem>::new().split_first().is_none());
+    }
 
-    fn index(&self, index: I) -> &Slice<T> {
-        Slice::<T>::ref_cast(self.0.index((index.start_bound().cloned(), index.end_bound().cloned())))
+    #[test]
+    fn test_slice_iteration() {
+        let vec = test_vec();
+        let values: Vec<i32> = vec[1..].iter().rev().map(|item| item.value).collect();
+        assert_eq!(values, vec![4, 3, 2]);
     }
 }
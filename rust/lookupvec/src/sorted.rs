@@ -0,0 +1,520 @@
+use crate::core::Lookup;
+use crate::core::with_key;
+use crate::guard::KeyPolicy;
+use crate::iter::*;
+use crate::slice::Slice;
+use crate::vec::LookupVec;
+
+use ahash::random_state::RandomState;
+use delegate::delegate;
+use indexmap::Equivalent;
+
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::BuildHasher;
+use core::hash::Hash;
+use core::mem;
+use core::ops::Bound;
+use core::ops::Deref;
+use core::ops::DerefMut;
+use core::ops::Index;
+use core::ops::RangeBounds;
+
+/// The order a `SortedLookupVec` keeps its keys in.
+///
+/// Implemented by `Ascending` for keys that are `Ord`, and by closures
+/// `Fn(&K, &K) -> Ordering`.
+pub trait Comparator<K: ?Sized> {
+    fn compare(&self, a: &K, b: &K) -> Ordering;
+}
+
+/// Orders keys by their `Ord` impl. The default for `SortedLookupVec`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Ascending;
+
+impl<K: ?Sized + Ord> Comparator<K> for Ascending {
+    fn compare(&self, a: &K, b: &K) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+impl<K: ?Sized, F: Fn(&K, &K) -> Ordering> Comparator<K> for F {
+    fn compare(&self, a: &K, b: &K) -> Ordering {
+        self(a, b)
+    }
+}
+
+/// A `LookupVec` that keeps its items sorted by key, in the order given by
+/// the comparator `C`.
+///
+/// Items are still found by key in O(1), and the key order additionally
+/// allows binary searches and range queries. Inserting or removing an item
+/// shifts the items after it, so both are O(n).
+#[derive(Clone)]
+pub struct SortedLookupVec<T: Lookup, S = RandomState, C = Ascending> {
+    vec: LookupVec<T, S>,
+    comparator: C,
+}
+
+impl<T: Lookup, S: Default> SortedLookupVec<T, S> {
+    pub fn new() -> Self {
+        SortedLookupVec { vec: LookupVec::new(), comparator: Ascending }
+    }
+
+    pub fn with_capacity(n: usize) -> Self {
+        SortedLookupVec { vec: LookupVec::with_capacity(n), comparator: Ascending }
+    }
+}
+
+impl<T: Lookup, S> SortedLookupVec<T, S> {
+    pub const fn with_hasher(hasher: S) -> Self {
+        SortedLookupVec { vec: LookupVec::with_hasher(hasher), comparator: Ascending }
+    }
+
+    pub fn with_capacity_and_hasher(n: usize, hasher: S) -> Self {
+        SortedLookupVec { vec: LookupVec::with_capacity_and_hasher(n, hasher), comparator: Ascending }
+    }
+}
+
+impl<T: Lookup, S, C> SortedLookupVec<T, S, C> {
+    /// Sets what happens when a mutable borrow changes an item's key.
+    pub fn with_key_policy(mut self, policy: KeyPolicy) -> Self {
+        self.vec = self.vec.with_key_policy(policy);
+        self
+    }
+
+    pub fn comparator(&self) -> &C {
+        &self.comparator
+    }
+
+    pub fn as_lookup_vec(&self) -> &LookupVec<T, S> {
+        &self.vec
+    }
+
+    pub fn into_lookup_vec(self) -> LookupVec<T, S> {
+        self.vec
+    }
+
+    delegate![
+        to self.vec {
+            pub fn key_policy(&self) -> KeyPolicy;
+            pub fn len(&self) -> usize;
+            pub fn is_empty(&self) -> bool;
+            pub fn truncate(&mut self, len: usize);
+            pub fn hasher(&self) -> &S;
+            pub fn capacity(&self) -> usize;
+            pub fn clear(&mut self);
+            pub fn get_index(&self, index: usize) -> Option<&T>;
+            pub fn first(&self) -> Option<&T>;
+            pub fn last(&self) -> Option<&T>;
+            pub fn get_range<R: RangeBounds<usize>>(&self, range: R) -> Option<&Slice<T>>;
+            pub fn as_slice(&self) -> &Slice<T>;
+            pub fn iter(&self) -> Iter<'_, T>;
+            pub fn keys(&self) -> Keys<'_, T>;
+            pub fn into_keys(self) -> IntoKeys<T>;
+        }
+    ];
+
+    delegate![
+        to self.vec.as_slice() {
+            /// Binary searches the items with a comparator consistent with
+            /// the key order. See `slice::binary_search_by`.
+            pub fn binary_search_by<F>(&self, f: F) -> Result<usize, usize>
+            where F: FnMut(&T) -> Ordering;
+            pub fn binary_search_by_key<B, F>(&self, b: &B, f: F) -> Result<usize, usize>
+            where F: FnMut(&T) -> B, B: Ord;
+            pub fn partition_point<P>(&self, pred: P) -> usize
+            where P: FnMut(&T) -> bool;
+        }
+    ];
+}
+
+impl<T: Lookup, S: BuildHasher, C> SortedLookupVec<T, S, C> {
+    delegate![
+        to self.vec {
+            pub fn reserve(&mut self, additional: usize);
+            pub fn shrink_to_fit(&mut self);
+            pub fn get<Q>(&self, key: &Q) -> Option<&T>
+            where Q: ?Sized + Hash + Equivalent<T::Key>;
+            pub fn get_index_of<Q>(&self, key: &Q) -> Option<usize>
+            where Q: ?Sized + Hash + Equivalent<T::Key>;
+            pub fn contains_key<Q>(&self, key: &Q) -> bool
+            where Q: ?Sized + Hash + Equivalent<T::Key>;
+            pub fn contains(&self, value: &T) -> bool;
+            pub fn shift_remove<Q>(&mut self, key: &Q) -> Option<T>
+            where Q: ?Sized + Hash + Equivalent<T::Key>;
+            pub fn pop(&mut self) -> Option<T>;
+            pub fn drain<R>(&mut self, range: R) -> Drain<'_, T>
+            where R: RangeBounds<usize>;
+            pub fn shift_remove_index(&mut self, index: usize) -> Option<T>;
+            pub fn retain<F>(&mut self, keep: F)
+            where F: FnMut(&T) -> bool;
+        }
+    ];
+
+    /// Re-sorts the items by `comparator` and keeps them in that order from
+    /// now on.
+    ///
+    /// Closures need their argument types spelled out, e.g.
+    /// `|a: &String, b: &String| b.cmp(a)`.
+    pub fn with_comparator<C2>(self, comparator: C2) -> SortedLookupVec<T, S, C2>
+    where C2: Comparator<T::Key> {
+        let mut vec = SortedLookupVec { vec: self.vec, comparator };
+        vec.sort();
+        vec
+    }
+}
+
+impl<T: Lookup, S: BuildHasher> SortedLookupVec<T, S>
+where T::Key: Ord {
+    /// Sorts `vec` by key and wraps it.
+    pub fn from_lookup_vec(vec: LookupVec<T, S>) -> Self {
+        let mut vec = SortedLookupVec { vec, comparator: Ascending };
+        vec.sort();
+        vec
+    }
+}
+
+impl<T: Lookup, S: BuildHasher, C: Comparator<T::Key>> SortedLookupVec<T, S, C> {
+    fn sort(&mut self) {
+        let comparator = &self.comparator;
+        self.vec.sort_keys_by(|a, b| comparator.compare(a, b));
+    }
+
+    /// Inserts the item at its position in key order, or replaces the item
+    /// whose key the comparator finds equal in place. Returns the item's index
+    /// and the replaced item.
+    pub fn insert_sorted(&mut self, value: T) -> (usize, Option<T>) {
+        let search = with_key(&value, |key| {
+            self.binary_search_keys(key)
+                .map(|index| (index, self.vec.as_slice().0.get_index(index).unwrap().0 == key))
+        });
+        match search {
+            Ok((index, true)) => {
+                let old = mem::replace(self.vec.index_mut_unchecked(index), value);
+                (index, Some(old))
+            },
+            Ok((index, false)) => {
+                // The comparator finds the keys equal, but the index needs the
+                // new key
+                let old = self.vec.shift_remove_index(index);
+                self.vec.shift_insert(index, value);
+                (index, old)
+            },
+            Err(index) => {
+                self.vec.shift_insert(index, value);
+                (index, None)
+            },
+        }
+    }
+
+    /// Returns `Ok` with the index of `key`, or `Err` with the index it would
+    /// be inserted at.
+    pub fn binary_search_keys(&self, key: &T::Key) -> Result<usize, usize> {
+        self.vec.as_slice().0.binary_search_by(|k, _| self.comparator.compare(k, key))
+    }
+
+    /// Returns the items whose keys are in `range`, compared as `Q` by the
+    /// comparator.
+    pub fn range_by_key<Q, R>(&self, range: R) -> &Slice<T>
+    where Q: ?Sized, T::Key: Borrow<Q>, C: Comparator<Q>, R: RangeBounds<Q> {
+        let slice = self.vec.as_slice();
+        let cmp = |k: &T::Key, q: &Q| self.comparator.compare(k.borrow(), q);
+        let start = match range.start_bound() {
+            Bound::Included(q) => slice.0.partition_point(|k, _| cmp(k, q).is_lt()),
+            Bound::Excluded(q) => slice.0.partition_point(|k, _| cmp(k, q).is_le()),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(q) => slice.0.partition_point(|k, _| cmp(k, q).is_le()),
+            Bound::Excluded(q) => slice.0.partition_point(|k, _| cmp(k, q).is_lt()),
+            Bound::Unbounded => slice.len(),
+        };
+        &slice[start..end.max(start)]
+    }
+
+    /// Returns a mutable reference to the item with key `key`. The item is
+    /// moved back into key order when the reference is dropped.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<RefMut<'_, T, S, C>>
+    where Q: ?Sized + Hash + Equivalent<T::Key> {
+        let index = self.get_index_of(key)?;
+        Some(RefMut { vec: self, index })
+    }
+
+    pub fn get_index_mut(&mut self, index: usize) -> Option<RefMut<'_, T, S, C>> {
+        if index < self.len() {
+            Some(RefMut { vec: self, index })
+        } else {
+            None
+        }
+    }
+
+    pub fn first_mut(&mut self) -> Option<RefMut<'_, T, S, C>> {
+        self.get_index_mut(0)
+    }
+
+    pub fn last_mut(&mut self) -> Option<RefMut<'_, T, S, C>> {
+        self.get_index_mut(self.len().checked_sub(1)?)
+    }
+
+    /// Moves the item at `index` to its position in key order, in case its
+    /// key has changed.
+    fn resort_index(&mut self, index: usize) {
+        let slice = &self.vec.as_slice().0;
+        let key = slice.get_index(index).unwrap().0;
+        let before = |k: &T::Key| self.comparator.compare(k, key).is_lt();
+        let to = if slice[..index].last().is_some_and(|(prev, _)| !before(prev)) {
+            slice[..index].partition_point(|k, _| before(k))
+        } else if slice[index + 1..].first().is_some_and(|(next, _)| before(next)) {
+            index + slice[index + 1..].partition_point(|k, _| before(k))
+        } else {
+            return;
+        };
+        self.vec.move_index(index, to);
+    }
+}
+
+impl<'a, T: Lookup, S, C> IntoIterator for &'a SortedLookupVec<T, S, C> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Lookup, S, C> IntoIterator for SortedLookupVec<T, S, C> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.vec.into_iter()
+    }
+}
+
+impl<T: Lookup, S: BuildHasher + Default> FromIterator<T> for SortedLookupVec<T, S>
+where T::Key: Ord {
+    fn from_iter<I: IntoIterator<Item = T>>(iterable: I) -> Self {
+        Self::from_lookup_vec(LookupVec::from_iter(iterable))
+    }
+}
+
+impl<T: Lookup, const N: usize> From<[T; N]> for SortedLookupVec<T>
+where T::Key: Ord {
+    fn from(arr: [T; N]) -> Self {
+        Self::from_iter(arr)
+    }
+}
+
+impl<T: Lookup, S: BuildHasher> From<LookupVec<T, S>> for SortedLookupVec<T, S>
+where T::Key: Ord {
+    fn from(vec: LookupVec<T, S>) -> Self {
+        Self::from_lookup_vec(vec)
+    }
+}
+
+impl<T: Lookup + fmt::Debug, S, C> fmt::Debug for SortedLookupVec<T, S, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SortedLookupVec").field("vec", &self.vec).finish_non_exhaustive()
+    }
+}
+
+impl<T: Lookup, S: Default> Default for SortedLookupVec<T, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Lookup, S: BuildHasher, C: Comparator<T::Key>> Extend<T> for SortedLookupVec<T, S, C> {
+    /// Appends the new items and sorts once, rather than inserting each item
+    /// at its position.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iterable: I) {
+        let len = self.len();
+        self.vec.extend(iterable);
+        let sorted = self.vec.as_slice().0[len.saturating_sub(1)..]
+            .keys()
+            .is_sorted_by(|a, b| self.comparator.compare(a, b).is_le());
+        if !sorted {
+            self.sort();
+        }
+    }
+}
+
+impl<T: Lookup, S, C> Index<usize> for SortedLookupVec<T, S, C> {
+    type Output = T;
+
+    /// ***Panics*** if `index` is out of bounds.
+    fn index(&self, index: usize) -> &T {
+        &self.vec[index]
+    }
+}
+
+/// Mutable reference to an item in a `SortedLookupVec`. When dropped, the
+/// item's key is re-checked according to the vec's `KeyPolicy` and the item
+/// is moved back into key order.
+pub struct RefMut<'a, T: Lookup, S: BuildHasher = RandomState, C: Comparator<T::Key> = Ascending> {
+    vec: &'a mut SortedLookupVec<T, S, C>,
+    index: usize,
+}
+
+impl<T: Lookup, S: BuildHasher, C: Comparator<T::Key>> RefMut<'_, T, S, C> {
+    /// Returns the index of the item, before it is moved back into order.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<T: Lookup, S: BuildHasher, C: Comparator<T::Key>> Deref for RefMut<'_, T, S, C> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.vec.vec[self.index]
+    }
+}
+
+impl<T: Lookup, S: BuildHasher, C: Comparator<T::Key>> DerefMut for RefMut<'_, T, S, C> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.vec.vec.index_mut_unchecked(self.index)
+    }
+}
+
+impl<T: Lookup, S: BuildHasher, C: Comparator<T::Key>> Drop for RefMut<'_, T, S, C> {
+    fn drop(&mut self) {
+        self.vec.vec.check_index(self.index, None);
+        self.vec.resort_index(self.index);
+    }
+}
+
+impl<T: Lookup + fmt::Debug, S: BuildHasher, C: Comparator<T::Key>> fmt::Debug for RefMut<'_, T, S, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use crate::test_util::Valued;
+    use crate::test_util::valued;
+
+    use alloc::vec::Vec;
+
+    fn test_vec() -> SortedLookupVec<Valued> {
+        SortedLookupVec::from([
+            valued("m", 1),
+            valued("c", 2),
+            valued("x", 3),
+            valued("a", 4),
+        ])
+    }
+
+    fn keys(slice: &Slice<Valued>) -> Vec<&str> {
+        slice.keys().copied().collect()
+    }
+
+    #[test]
+    fn test_insert_sorted() {
+        let mut vec = test_vec();
+        assert_eq!(keys(vec.as_slice()), vec!["a", "c", "m", "x"]);
+
+        assert_eq!(vec.insert_sorted(valued("d", 5)), (2, None));
+        assert_eq!(vec.insert_sorted(valued("z", 6)), (5, None));
+        assert_eq!(vec.insert_sorted(valued("c", 7)), (1, Some(valued("c", 2))));
+        assert_eq!(keys(vec.as_slice()), vec!["a", "c", "d", "m", "x", "z"]);
+        assert_eq!(vec.get("c").unwrap().value, 7);
+        assert_eq!(vec.get_index_of("m"), Some(3));
+    }
+
+    #[test]
+    fn test_insert_sorted_equal_keys() {
+        let mut vec = test_vec().with_comparator(|a: &&str, b: &&str| {
+            a.to_ascii_lowercase().cmp(&b.to_ascii_lowercase())
+        });
+        assert_eq!(vec.insert_sorted(valued("C", 5)), (1, Some(valued("c", 2))));
+        assert_eq!(keys(vec.as_slice()), vec!["a", "C", "m", "x"]);
+        assert_eq!(vec.get("C").unwrap().value, 5);
+        assert_eq!(vec.get("c"), None);
+        assert_eq!(vec.get_index_of("m"), Some(2));
+        assert!(vec.as_slice().iter().all(|item| vec.get(item.id) == Some(item)));
+    }
+
+    #[test]
+    fn test_binary_search() {
+        let vec = test_vec();
+        assert_eq!(vec.binary_search_keys(&"c"), Ok(1));
+        assert_eq!(vec.binary_search_keys(&"b"), Err(1));
+        assert_eq!(vec.binary_search_keys(&"y"), Err(4));
+        assert_eq!(vec.binary_search_by(|item| item.id.cmp("x")), Ok(3));
+        assert_eq!(vec.binary_search_by_key(&"m", |item| item.id), Ok(2));
+        assert_eq!(vec.partition_point(|item| item.id < "l"), 2);
+    }
+
+    #[test]
+    fn test_range_by_key() {
+        let vec = test_vec();
+        assert_eq!(keys(vec.range_by_key("a".."m")), vec!["a", "c"]);
+        assert_eq!(keys(vec.range_by_key("b"..="m")), vec!["c", "m"]);
+        assert_eq!(keys(vec.range_by_key("d"..)), vec!["m", "x"]);
+        assert_eq!(keys(vec.range_by_key(..="c")), vec!["a", "c"]);
+        assert_eq!(keys(vec.range_by_key::<&str, _>(..)), vec!["a", "c", "m", "x"]);
+        assert!(vec.range_by_key("n".."p").is_empty());
+        assert!(vec.range_by_key("p".."n").is_empty());
+    }
+
+    #[test]
+    fn test_comparator() {
+        let mut vec = test_vec().with_comparator(|a: &&str, b: &&str| b.cmp(a));
+        assert_eq!(keys(vec.as_slice()), vec!["x", "m", "c", "a"]);
+
+        assert_eq!(vec.insert_sorted(valued("d", 5)), (2, None));
+        assert_eq!(vec.insert_sorted(valued("z", 6)), (0, None));
+        assert_eq!(vec.insert_sorted(valued("c", 7)), (4, Some(valued("c", 2))));
+        assert_eq!(keys(vec.as_slice()), vec!["z", "x", "m", "d", "c", "a"]);
+        assert_eq!(vec.binary_search_keys(&"b"), Err(5));
+
+        assert_eq!(keys(vec.range_by_key("x".."c")), vec!["x", "m", "d"]);
+        assert_eq!(keys(vec.range_by_key("n"..="c")), vec!["m", "d", "c"]);
+        assert_eq!(keys(vec.range_by_key(.."m")), vec!["z", "x"]);
+        assert!(vec.range_by_key("c".."x").is_empty());
+
+        vec.get_mut("a").unwrap().id = "y";
+        vec.extend([valued("b", 8)]);
+        assert_eq!(keys(vec.as_slice()), vec!["z", "y", "x", "m", "d", "c", "b"]);
+    }
+
+    #[test]
+    fn test_get_mut_resorts() {
+        let mut vec = test_vec();
+        vec.get_mut("a").unwrap().id = "n";
+        assert_eq!(keys(vec.as_slice()), vec!["c", "m", "n", "x"]);
+
+        vec.get_mut("x").unwrap().id = "b";
+        assert_eq!(keys(vec.as_slice()), vec!["b", "c", "m", "n"]);
+
+        vec.get_mut("m").unwrap().value = 10;
+        assert_eq!(keys(vec.as_slice()), vec!["b", "c", "m", "n"]);
+        assert_eq!(vec.get("n").unwrap().value, 4);
+        assert_eq!(vec.binary_search_keys(&"b"), Ok(0));
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut vec = test_vec();
+        vec.extend([valued("y", 5), valued("z", 6)]);
+        assert_eq!(keys(vec.as_slice()), vec!["a", "c", "m", "x", "y", "z"]);
+
+        vec.extend([valued("b", 7), valued("m", 8)]);
+        assert_eq!(keys(vec.as_slice()), vec!["a", "b", "c", "m", "x", "y", "z"]);
+        assert_eq!(vec.get("m").unwrap().value, 8);
+    }
+
+    #[test]
+    fn test_from_lookup_vec() {
+        let mut vec: SortedLookupVec<Valued> = lookupvec![valued("b", 1), valued("a", 2)].into();
+        assert_eq!(keys(vec.as_slice()), vec!["a", "b"]);
+        assert_eq!(vec.shift_remove("a"), Some(valued("a", 2)));
+        assert_eq!(vec.into_lookup_vec().len(), 1);
+    }
+}
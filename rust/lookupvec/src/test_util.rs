@@ -0,0 +1,52 @@
+//! Items and assertions shared by the unit tests.
+
+use crate::LookupVec;
+use lookupvec_derive::Lookup;
+
+// As of 7/2025 alloc::prelude is nightly-only
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+
+#[derive(Debug, Clone, PartialEq, Lookup)]
+pub(crate) struct TestItem {
+    #[lookup_key]
+    pub(crate) id: String,
+    pub(crate) value: i32,
+}
+
+pub(crate) fn item(id: &str, value: i32) -> TestItem {
+    TestItem { id: id.to_owned(), value }
+}
+
+/// Items `"a"` to `"d"`, with values 1 to 4.
+pub(crate) fn test_vec() -> LookupVec<TestItem> {
+    LookupVec::from([item("a", 1), item("b", 2), item("c", 3), item("d", 4)])
+}
+
+/// An item with a `&'static str` key, which is `Copy` and `Ord`.
+#[derive(Debug, Clone, PartialEq, Lookup)]
+pub(crate) struct Valued {
+    #[lookup_key]
+    pub(crate) id: &'static str,
+    pub(crate) value: i32,
+}
+
+pub(crate) fn valued(id: &'static str, value: i32) -> Valued {
+    Valued { id, value }
+}
+
+/// Asserts that the keys of a vec with `String` keys are `$key`s, in order,
+/// and that each is found at its index.
+macro_rules! assert_keys_eq {
+    ($vec:expr, $($key:expr),* $(,)?) => {
+        let keys: alloc::vec::Vec<alloc::string::String> = $vec.keys()
+            .map(|key| alloc::borrow::ToOwned::to_owned(key.as_str()))
+            .collect();
+        let expected: &[&str] = &[$($key),*];
+        assert_eq!(keys, expected);
+        for (index, key) in expected.iter().enumerate() {
+            assert_eq!($vec.get_index_of(*key), Some(index));
+        }
+    };
+}
+pub(crate) use assert_keys_eq;
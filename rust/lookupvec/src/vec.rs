@@ -1,66 +1,212 @@
+use crate::backend::Backend;
+use crate::backend::Indexed;
+use crate::backend::store::RawSlice;
+use crate::backend::store::Store;
 use crate::core::Lookup;
+use crate::core::Contains;
+use crate::core::with_key;
+use crate::diff::Diff;
+use crate::duplicate::DuplicatePolicy;
+use crate::duplicate::DuplicateKeyError;
+use crate::entry::Entry;
+use crate::entry::IndexedEntry;
+use crate::guard::ItemMut;
+use crate::guard::KeyCheck;
+use crate::guard::KeyPolicy;
+use crate::guard::RefMut;
+use crate::guard::SliceMut;
+use crate::guard::index_range;
+use crate::guard::panicking;
+use crate::guard::simplify_range;
 use crate::iter::*;
-//use crate::slice::Slice;
+use crate::merge::MergePosition;
+use crate::merge::MergeStrategy;
+use crate::merge::check_merge;
+#[cfg(feature = "rayon")]
+use crate::rayon::*;
+use crate::slice::Slice;
 
 use ahash::random_state::RandomState;
 use delegate::delegate;
+use hashbrown::HashTable;
 use indexmap::IndexMap;
 use indexmap::Equivalent;
-//use ref_cast::RefCast;
-
+#[cfg(feature = "rayon")]
+use rayon::iter::FromParallelIterator;
+#[cfg(feature = "rayon")]
+use rayon::iter::IntoParallelIterator;
+#[cfg(feature = "rayon")]
+use rayon::iter::ParallelExtend;
+#[cfg(feature = "rayon")]
+use rayon::iter::ParallelIterator;
+use ref_cast::RefCast;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
 use core::cmp::Ordering;
+use core::fmt;
 use core::hash::BuildHasher;
 use core::hash::Hash;
+use core::hash::Hasher;
+use core::ops::Bound;
 use core::ops::Index;
 use core::ops::IndexMut;
+use core::ops::Range;
 use core::ops::RangeBounds;
-
-#[derive(Debug, Clone)]
-pub struct LookupVec<T: Lookup, S = RandomState> {
-    map: IndexMap<T::Key, T, S>,
+use core::ops::RangeFrom;
+use core::ops::RangeFull;
+use core::ops::RangeInclusive;
+use core::ops::RangeTo;
+use core::ops::RangeToInclusive;
+
+/// A `Vec` of items that can also be looked up by key. `B` picks how the
+/// items are stored; see `crate::backend`.
+pub struct LookupVec<T: Lookup, S = RandomState, B: Backend = Indexed> {
+    pub(crate) store: B::Store<T, S>,
+    pub(crate) check: KeyCheck,
+    pub(crate) duplicates: DuplicatePolicy,
 }
 
-impl<T: Lookup, S: Default> LookupVec<T, S> {
-    pub fn new() -> Self {
+impl<T: Lookup + Clone, S: Clone, B: Backend> Clone for LookupVec<T, S, B> {
+    fn clone(&self) -> Self {
         LookupVec {
-            map: IndexMap::with_hasher(S::default()),
+            store: self.store.clone_store(),
+            check: self.check.clone(),
+            duplicates: self.duplicates,
         }
     }
+}
+
+impl<T: Lookup, S: Default, B: Backend> LookupVec<T, S, B> {
+    pub fn new() -> Self {
+        Self::with_capacity_and_hasher(0, S::default())
+    }
 
     pub fn with_capacity(n: usize) -> Self {
-        LookupVec {
-            map: IndexMap::with_capacity_and_hasher(n, S::default()),
-        }
+        Self::with_capacity_and_hasher(n, S::default())
     }
 }
 
 impl<T: Lookup, S> LookupVec<T, S> {
     pub const fn with_hasher(hasher: S) -> Self {
         LookupVec {
-            map: IndexMap::with_hasher(hasher),
+            store: IndexMap::with_hasher(hasher),
+            check: KeyCheck::new(KeyPolicy::Rekey),
+            duplicates: DuplicatePolicy::Replace,
         }
     }
+}
 
+impl<T: Lookup, S, B: Backend> LookupVec<T, S, B> {
     pub fn with_capacity_and_hasher(n: usize, hasher: S) -> Self {
         LookupVec {
-            map: IndexMap::with_capacity_and_hasher(n, hasher),
+            store: B::Store::with_capacity_and_hasher(n, hasher),
+            check: KeyCheck::default(),
+            duplicates: DuplicatePolicy::Replace,
         }
     }
 
+    /// Sets what happens when a mutable borrow changes an item's key.
+    pub fn with_key_policy(mut self, policy: KeyPolicy) -> Self {
+        self.check.policy = policy;
+        self
+    }
+
+    pub fn key_policy(&self) -> KeyPolicy {
+        self.check.policy
+    }
+
+    /// Sets what `push` and the methods built on it do with an item whose key
+    /// is already present.
+    pub fn with_duplicate_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicates = policy;
+        self
+    }
+
+    pub fn duplicate_policy(&self) -> DuplicatePolicy {
+        self.duplicates
+    }
+
+    pub fn set_duplicate_policy(&mut self, policy: DuplicatePolicy) {
+        self.duplicates = policy;
+    }
+
     delegate![
-        to self.map {
+        to self.store {
             pub fn len(&self) -> usize;
-            pub fn is_empty(&self) -> bool;
-
-            pub fn move_index(&mut self, from: usize, to: usize);
-            pub fn swap_indices(&mut self, a: usize, b: usize);
 
-            pub fn reverse(&mut self);
-            pub fn clear(&mut self);
             pub fn truncate(&mut self, len: usize);
 
             pub fn hasher(&self) -> &S;
             pub fn capacity(&self) -> usize;
+        }
+    ];
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.check.take_stale(0);
+        self.store.clear()
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<&T> {
+        self.store.as_slice().get(index)
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.get_index(0)
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.get_index(self.len().checked_sub(1)?)
+    }
+
+    pub fn get_range<R: RangeBounds<usize>>(&self, range: R) -> Option<&Slice<T, B>> {
+        let range = simplify_range(range, self.len())?;
+        Some(Slice::ref_cast(self.store.as_slice().range(range)))
+    }
+
+    pub fn as_slice(&self) -> &Slice<T, B> {
+        Slice::ref_cast(self.store.as_slice())
+    }
+
+    /// Converts into a boxed slice, dropping the hash table and any excess
+    /// capacity.
+    pub fn into_boxed_slice(self) -> Box<Slice<T, B>> {
+        Slice::from_boxed(self.store.into_boxed_slice())
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, B> {
+        Iter(self.store.as_slice().iter())
+    }
+
+    /// Returns the items' keys.
+    ///
+    /// With the `Indexed` backend, an item with a computed key that was
+    /// changed through `IndexMut` or an entry, and not re-keyed yet, reports
+    /// the key it is stored under.
+    pub fn keys(&self) -> Keys<'_, T, B> {
+        Keys(self.store.as_slice().keys())
+    }
+
+    pub fn into_keys(self) -> IntoKeys<T, B> {
+        IntoKeys(self.store.into_keys())
+    }
+
+    pub(crate) fn index_mut_unchecked(&mut self, index: usize) -> &mut T {
+        self.store.as_mut_slice().get_mut(index).unwrap()
+    }
+
+    pub(crate) fn range_mut_unchecked(&mut self, range: Range<usize>) -> &mut Slice<T, B> {
+        Slice::ref_cast_mut(self.store.as_mut_slice().range_mut(range))
+    }
+}
+
+impl<T: Lookup, S: BuildHasher, B: Backend> LookupVec<T, S, B> {
+    delegate![
+        to self.store {
             pub fn reserve(&mut self, additional: usize);
             pub fn reserve_exact(&mut self, additional: usize);
             pub fn shrink_to(&mut self, min_capacity: usize);
@@ -68,172 +214,754 @@ impl<T: Lookup, S> LookupVec<T, S> {
         }
     ];
 
-    pub fn get_index(&self, index: usize) -> Option<&T> {
-        self.map.get_index(index).map(|v| v.1)
+    /// Each item's key is checked when its `ItemMut` is dropped.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, S, B> {
+        self.repair();
+        IterMut::new(self.store.iter_mut(), &self.check)
     }
 
-    pub fn get_index_mut(&mut self, index: usize) -> Option<&mut T> {
-        self.map.get_index_mut(index).map(|v| v.1)
+    /// Returns the indices of items whose key no longer matches the key they
+    /// are stored under.
+    pub fn validate(&self) -> Vec<usize> {
+        (0..self.len()).filter(|&index| !self.store.is_stored(index)).collect()
     }
 
-    pub fn first(&self) -> Option<&T> {
-        self.map.first().map(|v| v.1)
+    /// Finds the index of `key`, accounting for items whose key may have
+    /// changed since the hash table was last repaired.
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where Q: ?Sized + Hash + Equivalent<T::Key> {
+        let found = self.store.get_index_of(key);
+        match self.check.stale_range(self.len()) {
+            None => found,
+            Some(stale) => match found {
+                Some(index) if !stale.contains(&index) => Some(index),
+                _ => stale.into_iter().find(|&index| with_key(&self[index], |k| key.equivalent(k))),
+            },
+        }
     }
 
-    pub fn first_mut(&mut self) -> Option<&mut T> {
-        self.map.first_mut().map(|v| v.1)
+    /// Applies the `KeyPolicy` to items that were handed out as `&mut T`
+    /// since the last repair.
+    fn repair(&mut self) {
+        let Some(stale) = self.check.take_stale(self.len()) else {
+            return;
+        };
+        let changed: Vec<usize> = stale.filter(|&index| !self.store.is_stored(index)).collect();
+        match changed.as_slice() {
+            [] => (),
+            &[index] => self.check_index(index, None),
+            [index, ..] => match self.check.policy {
+                KeyPolicy::Rekey => {
+                    self.rekey_all();
+                },
+                _ => self.check_index(*index, None),
+            },
+        }
     }
 
-    pub fn last(&self) -> Option<&T> {
-        self.map.last().map(|v| v.1)
+    /// Applies the `KeyPolicy` to the items in `range`, which were handed out
+    /// mutably.
+    pub(crate) fn check_range(&mut self, range: Range<usize>) {
+        self.check.mark(range);
+        if !panicking() {
+            self.repair();
+        }
     }
 
-    pub fn last_mut(&mut self) -> Option<&mut T> {
-        self.map.last_mut().map(|v| v.1)
+    /// Applies the `KeyPolicy` to the item at `index`, whose `slot_hash` was
+    /// `hash` before it was handed out. While unwinding, only records the
+    /// item for the next repair.
+    pub(crate) fn check_index(&mut self, index: usize, hash: Option<u64>) {
+        if !self.check.policy.is_checked() || self.store.is_stored(index) {
+            return;
+        }
+        if panicking() {
+            self.check.mark(index..=index);
+            return;
+        }
+        let new_key = self[index].key();
+        match self.check.policy {
+            KeyPolicy::Rekey => {
+                if self.store.get_index_of(&new_key).is_some_and(|i| i != index) {
+                    panic!("duplicate key {new_key:?} after changing key of item at index {index}");
+                }
+                self.store.rekey_index(index, new_key, hash);
+            },
+            KeyPolicy::Panic | KeyPolicy::DebugAssert => match self.store.stored_key(index) {
+                Some(old_key) => panic!("key of item at index {index} changed from {old_key:?} to {new_key:?}"),
+                None => panic!("key of item at index {index} changed to {new_key:?}"),
+            },
+        }
     }
 
-    pub fn iter(&self) -> Iter<'_, T> {
-        Iter(self.map.values())
+    /// Re-keys every item whose key no longer matches the key it is stored
+    /// under, regardless of the `KeyPolicy`. Returns the number of items that
+    /// were re-keyed.
+    ///
+    /// ***Panics*** if two items now have the same key.
+    pub fn rekey_all(&mut self) -> usize {
+        let changed = self.validate().len();
+        if changed > 0 {
+            // Finds every key and checks for duplicates before changing the
+            // hash table, so that a panic leaves every item in place
+            let hasher = self.store.hasher();
+            let keys: Vec<(u64, T::Key)> = self.iter()
+                .map(|item| {
+                    let key = item.key();
+                    (hasher.hash_one(&key), key)
+                })
+                .collect();
+            let mut seen = HashTable::<usize>::with_capacity(keys.len());
+            for (index, (hash, key)) in keys.iter().enumerate() {
+                if seen.find(*hash, |&i| keys[i].1 == *key).is_some() {
+                    panic!("duplicate key {key:?} after changing item keys");
+                }
+                seen.insert_unique(*hash, index, |&i| keys[i].0);
+            }
+            self.store.rebuild(keys);
+        }
+        self.check.take_stale(0);
+        changed
     }
-    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
-        IterMut(self.map.values_mut())
+
+    pub fn set_key_policy(&mut self, policy: KeyPolicy) {
+        self.repair();
+        self.check.policy = policy;
     }
 
-    pub fn keys(&self) -> Keys<'_, T> {
-        Keys(self.map.keys())
+    /// ***Panics*** if `from` or `to` is out of bounds.
+    pub fn move_index(&mut self, from: usize, to: usize) {
+        self.repair();
+        self.store.move_index(from, to)
     }
 
-    pub fn into_keys(self) -> IntoKeys<T> {
-        IntoKeys(self.map.into_keys())
+    /// ***Panics*** if `a` or `b` is out of bounds.
+    pub fn swap_indices(&mut self, a: usize, b: usize) {
+        self.repair();
+        self.store.swap_indices(a, b)
     }
 
-    pub fn drain<R>(&mut self, range: R) -> Drain<'_, T>
+    pub fn reverse(&mut self) {
+        self.repair();
+        self.store.reverse()
+    }
+
+    /// Returns a mutable slice whose items' keys are checked when it is
+    /// dropped.
+    pub fn get_range_mut<R: RangeBounds<usize>>(&mut self, range: R) -> Option<SliceMut<'_, T, S, B>> {
+        self.repair();
+        let range = simplify_range(range, self.len())?;
+        Some(SliceMut::new(self, range))
+    }
+
+    /// Returns a mutable slice whose items' keys are checked when it is
+    /// dropped.
+    pub fn as_mut_slice(&mut self) -> SliceMut<'_, T, S, B> {
+        self.repair();
+        let len = self.len();
+        SliceMut::new(self, 0..len)
+    }
+
+    pub fn get_index_entry(&mut self, index: usize) -> Option<IndexedEntry<'_, T, S, B>> {
+        self.repair();
+        (index < self.len()).then(|| IndexedEntry::new(self, index))
+    }
+
+    pub fn first_entry(&mut self) -> Option<IndexedEntry<'_, T, S, B>> {
+        self.get_index_entry(0)
+    }
+
+    pub fn last_entry(&mut self) -> Option<IndexedEntry<'_, T, S, B>> {
+        self.get_index_entry(self.len().checked_sub(1)?)
+    }
+
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_, T, B>
     where R: RangeBounds<usize> {
-        Drain(self.map.drain(range))
+        self.repair();
+        Drain(self.store.drain(range))
     }
 
     pub fn split_off(&mut self, at: usize) -> Self
     where S: Clone {
+        self.repair();
         LookupVec {
-            map: self.map.split_off(at),
+            store: self.store.split_off(at),
+            check: KeyCheck::new(self.check.policy),
+            duplicates: self.duplicates,
         }
     }
 
     pub fn shift_remove_index(&mut self, index: usize) -> Option<T> {
-        self.map.shift_remove_index(index).map(|v| v.1)
+        self.repair();
+        self.store.shift_remove_index(index)
     }
 
     pub fn swap_remove_index(&mut self, index: usize) -> Option<T> {
-        self.map.swap_remove_index(index).map(|v| v.1)
+        self.repair();
+        self.store.swap_remove_index(index)
     }
-}
 
-impl<T: Lookup, S: BuildHasher> LookupVec<T, S> {
-    delegate![
-        to self.map {
-            pub fn get<Q>(&self, key: &Q) -> Option<&T> where Q: ?Sized + Hash + Equivalent<T::Key>;
-            pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut T> where Q: ?Sized + Hash + Equivalent<T::Key>;
-            pub fn get_index_of<Q>(&mut self, key: &Q) -> Option<usize> where Q: ?Sized + Hash + Equivalent<T::Key>;
-            pub fn contains_key<Q>(&self, key: &Q) -> bool where Q: ?Sized + Hash + Equivalent<T::Key>;
-            pub fn shift_remove<Q>(&mut self, key: &Q) -> Option<T> where Q: ?Sized + Hash + Equivalent<T::Key>;
-            pub fn swap_remove<Q>(&mut self, key: &Q) -> Option<T> where Q: ?Sized + Hash + Equivalent<T::Key>;
+    /// Keeps only the items for which `keep` returns true, in order.
+    pub fn retain<F>(&mut self, mut keep: F)
+    where F: FnMut(&T) -> bool {
+        self.repair();
+        self.store.retain(|item| keep(item));
+    }
+
+    /// Keeps only the items whose key is in `keys`, in order.
+    pub fn retain_keys<C>(&mut self, keys: &C)
+    where C: Contains<T::Key> + ?Sized {
+        self.retain(|item| with_key(item, |key| keys.contains(key)))
+    }
+
+    /// Removes the items in `range` for which `pred` returns true, and returns
+    /// them in order. If the iterator is dropped early, the remaining items are
+    /// kept.
+    ///
+    /// The keys of kept items are checked as they are visited, like those
+    /// handed out by `iter_mut`.
+    ///
+    /// ***Panics*** if the range is out of bounds.
+    pub fn extract_if<'a, F, R>(&'a mut self, range: R, pred: F) -> impl Iterator<Item = T> + 'a
+    where
+        F: FnMut(&mut T) -> bool + 'a,
+        R: RangeBounds<usize> + 'a,
+    {
+        self.repair();
+        let range = index_range(range, self.len());
+        self.store.extract_if(range, &mut self.check, pred)
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&T>
+    where Q: ?Sized + Hash + Equivalent<T::Key> {
+        self.find(key).map(|index| &self[index])
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<RefMut<'_, T, S, B>>
+    where Q: ?Sized + Hash + Equivalent<T::Key> {
+        self.repair();
+        let index = self.store.get_index_of(key)?;
+        Some(RefMut::new(self, index))
+    }
+
+    pub fn get_index_of<Q>(&self, key: &Q) -> Option<usize>
+    where Q: ?Sized + Hash + Equivalent<T::Key> {
+        self.find(key)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where Q: ?Sized + Hash + Equivalent<T::Key> {
+        self.find(key).is_some()
+    }
+
+    pub fn shift_remove<Q>(&mut self, key: &Q) -> Option<T>
+    where Q: ?Sized + Hash + Equivalent<T::Key> {
+        self.repair();
+        let index = self.store.get_index_of(key)?;
+        self.store.shift_remove_index(index)
+    }
+
+    pub fn swap_remove<Q>(&mut self, key: &Q) -> Option<T>
+    where Q: ?Sized + Hash + Equivalent<T::Key> {
+        self.repair();
+        let index = self.store.get_index_of(key)?;
+        self.store.swap_remove_index(index)
+    }
+
+    pub fn get_index_mut(&mut self, index: usize) -> Option<RefMut<'_, T, S, B>> {
+        self.repair();
+        if index < self.len() {
+            Some(RefMut::new(self, index))
+        } else {
+            None
         }
-    ];
+    }
+
+    pub fn first_mut(&mut self) -> Option<RefMut<'_, T, S, B>> {
+        self.get_index_mut(0)
+    }
 
+    pub fn last_mut(&mut self) -> Option<RefMut<'_, T, S, B>> {
+        self.get_index_mut(self.len().checked_sub(1)?)
+    }
+
+    /// Gets the entry for the given key, for in-place manipulation. Items
+    /// inserted through the entry must have the same key.
+    ///
+    /// `Entry::insert_entry` handles an existing item according to the
+    /// `DuplicatePolicy`.
+    pub fn entry(&mut self, key: T::Key) -> Entry<'_, T, S, B> {
+        self.repair();
+        Entry::new(self, key)
+    }
+
+    /// Pushes the item, handling an existing item with the same key according
+    /// to the `DuplicatePolicy`. Returns the item that is no longer in the
+    /// vec, if any: the replaced item, or the new one under `KeepFirst`.
+    ///
+    /// ***Panics*** if the key is already present and the policy is `Error`.
     pub fn push(&mut self, value: T) -> Option<T> {
-        self.map.insert(value.key(), value)
+        self.push_full(value).1
     }
 
+    /// Like `push`, but also returns the index of the item with this key.
+    ///
+    /// ***Panics*** if the key is already present and the policy is `Error`.
     pub fn push_full(&mut self, value: T) -> (usize, Option<T>) {
-        self.map.insert_full(value.key(), value)
+        self.push_with(value, self.duplicates).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like `push_full`, but with the given `DuplicatePolicy` instead of the
+    /// vec's.
+    pub fn push_with(
+        &mut self,
+        value: T,
+        policy: DuplicatePolicy,
+    ) -> Result<(usize, Option<T>), DuplicateKeyError<T>> {
+        self.repair();
+        let (hash, owned_key, found) = self.probe(&value);
+        let Some(index) = found else {
+            let index = self.len();
+            self.store.push_unique(hash, owned_key, value);
+            return Ok((index, None));
+        };
+        match policy {
+            DuplicatePolicy::Replace => {
+                Ok((index, Some(core::mem::replace(self.index_mut_unchecked(index), value))))
+            },
+            DuplicatePolicy::KeepFirst => Ok((index, Some(value))),
+            DuplicatePolicy::MoveToEnd => {
+                let replaced = core::mem::replace(self.index_mut_unchecked(index), value);
+                let last = self.len() - 1;
+                self.move_index(index, last);
+                Ok((last, Some(replaced)))
+            },
+            DuplicatePolicy::Error => Err(DuplicateKeyError::new(value, index)),
+        }
+    }
+
+    /// Pushes the item if its key is new, regardless of the
+    /// `DuplicatePolicy`. Returns the item's index.
+    pub fn try_push(&mut self, value: T) -> Result<usize, DuplicateKeyError<T>> {
+        self.push_with(value, DuplicatePolicy::Error).map(|(index, _)| index)
+    }
+
+    /// Pushes every item if all of their keys are new and distinct, regardless
+    /// of the `DuplicatePolicy`. Otherwise leaves the vec unchanged and returns
+    /// the first rejected item with every duplicated key.
+    ///
+    /// When a key is repeated within `iterable`, the error's index is where
+    /// its first occurrence would have been pushed.
+    pub fn try_extend<I>(&mut self, iterable: I) -> Result<(), DuplicateKeyError<T>>
+    where I: IntoIterator<Item = T> {
+        self.repair();
+        let mut items: Vec<T> = iterable.into_iter().collect();
+        let hasher = self.store.hasher();
+        let hash_item = |item: &T| with_key(item, |key| hasher.hash_one(key));
+
+        // Positions in `items` of the new keys seen so far, and the index
+        // each would be pushed at
+        let mut batch = HashTable::<(usize, usize)>::with_capacity(items.len());
+        let mut first_duplicate = None;
+        let mut keys = Vec::new();
+        // Which indices already had their key reported, so that a key
+        // repeated several times is only listed once
+        let mut reported = alloc::vec![false; self.len() + items.len()];
+        for (position, item) in items.iter().enumerate() {
+            let existing = with_key(item, |key| {
+                self.store.get_index_of(key).or_else(|| {
+                    let same_key = |&(other, _): &(usize, usize)| with_key(&items[other], |k| k == key);
+                    batch.find(hash_item(item), same_key).map(|&(_, index)| index)
+                })
+            });
+            match existing {
+                Some(index) => {
+                    first_duplicate.get_or_insert((position, index));
+                    if !core::mem::replace(&mut reported[index], true) {
+                        keys.push(item.key());
+                    }
+                },
+                None => {
+                    let index = self.len() + batch.len();
+                    batch.insert_unique(hash_item(item), (position, index), |&(other, _)| hash_item(&items[other]));
+                },
+            }
+        }
+
+        if let Some((position, index)) = first_duplicate {
+            let item = items.swap_remove(position);
+            return Err(DuplicateKeyError::with_keys(item, index, keys));
+        }
+        self.reserve(items.len());
+        for item in items {
+            self.push(item);
+        }
+        Ok(())
+    }
+
+    /// Collects the items into a new vec if all of their keys are distinct.
+    /// See `try_extend`.
+    pub fn try_from_iter<I>(iterable: I) -> Result<Self, DuplicateKeyError<T>>
+    where
+        I: IntoIterator<Item = T>,
+        S: Default,
+    {
+        let mut vec = Self::with_capacity_and_hasher(0, S::default());
+        vec.try_extend(iterable)?;
+        Ok(vec)
+    }
+
+    /// Probes for the item's key, borrowing it if possible so that it is only
+    /// cloned when the item is new. Returns the key's hash, the computed key
+    /// if the item can't borrow its key, and the index of the item with this
+    /// key, if any.
+    fn probe(&self, value: &T) -> (u64, Option<T::Key>, Option<usize>) {
+        let owned_key = value.borrow_key().is_none().then(|| value.key());
+        let key = owned_key.as_ref().or(value.borrow_key()).unwrap();
+        let hash = self.store.hasher().hash_one(key);
+        let found = self.store.find_hashed(hash, key);
+        (hash, owned_key, found)
     }
 
+    /// Inserts the item before `index`. If an item with its key exists, the
+    /// `DuplicatePolicy` decides: `Replace` and `MoveToEnd` replace it and move
+    /// it to `index`, and `KeepFirst` leaves the vec unchanged. Returns the
+    /// item's index and the item that is no longer in the vec, if any.
+    ///
+    /// ***Panics*** if `index > len`, or if the key is already present and the
+    /// policy is `Error`.
     pub fn insert(&mut self, index: usize, value: T) -> (usize, Option<T>) {
-        self.map.insert_before(index, value.key(), value)
+        self.repair();
+        let len = self.len();
+        assert!(index <= len, "index out of bounds: the len is {len} but the index is {index}");
+        let (hash, owned_key, found) = self.probe(&value);
+        match found {
+            Some(existing) => {
+                match self.duplicates {
+                    DuplicatePolicy::KeepFirst => return (existing, Some(value)),
+                    DuplicatePolicy::Error => panic!("{}", DuplicateKeyError::new(value, existing)),
+                    DuplicatePolicy::Replace | DuplicatePolicy::MoveToEnd => {},
+                }
+                // Moving the item up shifts the ones after it down
+                let index = if index > existing { index - 1 } else { index };
+                let replaced = core::mem::replace(self.index_mut_unchecked(existing), value);
+                self.store.move_index(existing, index);
+                (index, Some(replaced))
+            },
+            None => {
+                self.store.push_unique(hash, owned_key, value);
+                self.store.move_index(len, index);
+                (index, None)
+            },
+        }
     }
 
+    /// Inserts the item at `index`. If an item with its key exists, the
+    /// `DuplicatePolicy` decides as for `insert`. Returns the item that is no
+    /// longer in the vec, if any.
+    ///
+    /// ***Panics*** if `index` is out of bounds for the resulting vec, or if
+    /// the key is already present and the policy is `Error`.
     pub fn shift_insert(&mut self, index: usize, value: T) -> Option<T> {
-        self.map.shift_insert(index, value.key(), value)
+        self.repair();
+        let len = self.len();
+        let (hash, owned_key, found) = self.probe(&value);
+        match found {
+            Some(existing) => {
+                assert!(index < len, "index out of bounds: the len is {len} but the index is {index}");
+                match self.duplicates {
+                    DuplicatePolicy::KeepFirst => return Some(value),
+                    DuplicatePolicy::Error => panic!("{}", DuplicateKeyError::new(value, existing)),
+                    DuplicatePolicy::Replace | DuplicatePolicy::MoveToEnd => {},
+                }
+                let replaced = core::mem::replace(self.index_mut_unchecked(existing), value);
+                self.store.move_index(existing, index);
+                Some(replaced)
+            },
+            None => {
+                assert!(index <= len, "index out of bounds: the len is {len} but the index is {index}");
+                self.store.push_unique(hash, owned_key, value);
+                self.store.move_index(len, index);
+                None
+            },
+        }
     }
 
     pub fn pop(&mut self) -> Option<T> {
-        self.map.pop().map(|v| v.1)
+        self.store.swap_remove_index(self.len().checked_sub(1)?)
     }
 
-    pub fn append<S2>(&mut self, other: &mut LookupVec<T, S2>) {
-        self.map.append(&mut other.map)
+    /// Moves the items of `other` to the end of `self`, handling each item
+    /// whose key is already present according to the `DuplicatePolicy`.
+    pub fn append<S2>(&mut self, other: &mut LookupVec<T, S2, B>) {
+        // Keys are recomputed as the items are pushed, so stale keys in
+        // `other` don't matter
+        other.check.take_stale(0);
+        self.extend(other.store.drain(..));
+    }
+
+    /// Appends the items of `other`, merging each item whose key is already
+    /// present into the existing item with `strategy`. Merged items keep their
+    /// position.
+    ///
+    /// ***Fails*** without changing the vec if `strategy` rejects any item.
+    pub fn merge_with<I, M>(&mut self, other: I, strategy: M) -> Result<(), DuplicateKeyError<T>>
+    where I: IntoIterator<Item = T>, M: MergeStrategy<T> {
+        self.merge_with_position(other, MergePosition::Existing, strategy)
+    }
+
+    /// Like `merge_with`, but `position` decides where merged items end up.
+    pub fn merge_with_position<I, M>(
+        &mut self,
+        other: I,
+        position: MergePosition,
+        mut strategy: M,
+    ) -> Result<(), DuplicateKeyError<T>>
+    where I: IntoIterator<Item = T>, M: MergeStrategy<T> {
+        self.repair();
+        let mut items: Vec<T> = other.into_iter().collect();
+        let find = |item: &T| with_key(item, |key| self.store.get_index_of(key)).map(|index| (index, &self[index]));
+        check_merge(&items, self.len(), self.store.hasher(), &mut strategy, find)
+            .map_err(|(position, index)| DuplicateKeyError::new(items.swap_remove(position), index))?;
+        for item in items {
+            let (hash, owned_key, found) = self.probe(&item);
+            let Some(index) = found else {
+                self.store.push_unique(hash, owned_key, item);
+                continue;
+            };
+            let hash = self.store.slot_hash(index);
+            strategy.merge(self.index_mut_unchecked(index), item);
+            self.check_index(index, hash);
+            if position == MergePosition::Incoming {
+                self.move_index(index, self.len() - 1);
+            }
+        }
+        Ok(())
     }
 
     pub fn contains(&self, value: &T) -> bool {
-        self.map.contains_key(&value.key())
+        with_key(&value, |key| self.find(key).is_some())
+    }
+
+    /// Returns the items whose key is not in `other`, in order.
+    pub fn difference<'a, S2>(&'a self, other: &'a LookupVec<T, S2, B>) -> Difference<'a, T, S2, B>
+    where S2: BuildHasher {
+        Difference { iter: self.iter(), other }
+    }
+
+    /// Returns the items whose key is also in `other`, in order.
+    pub fn intersection<'a, S2>(&'a self, other: &'a LookupVec<T, S2, B>) -> Intersection<'a, T, S2, B>
+    where S2: BuildHasher {
+        Intersection { iter: self.iter(), other }
+    }
+
+    /// Returns the items of `self` whose key is not in `other`, followed by
+    /// the items of `other` whose key is not in `self`.
+    pub fn symmetric_difference<'a, S2>(
+        &'a self,
+        other: &'a LookupVec<T, S2, B>,
+    ) -> SymmetricDifference<'a, T, S, S2, B>
+    where S2: BuildHasher {
+        SymmetricDifference(self.difference(other).chain(other.difference(self)))
+    }
+
+    /// Returns every item of `self`, followed by the items of `other` whose
+    /// key is not in `self`.
+    pub fn union<'a, S2>(&'a self, other: &'a LookupVec<T, S2, B>) -> Union<'a, T, S, B>
+    where S2: BuildHasher {
+        Union(self.iter().chain(other.difference(self)))
+    }
+
+    /// Returns true if no key is in both `self` and `other`.
+    pub fn is_disjoint<S2: BuildHasher>(&self, other: &LookupVec<T, S2, B>) -> bool {
+        if self.len() <= other.len() {
+            self.iter().all(|item| !other.contains(item))
+        } else {
+            other.iter().all(|item| !self.contains(item))
+        }
+    }
+
+    /// Returns true if every key in `self` is also in `other`.
+    pub fn is_subset<S2: BuildHasher>(&self, other: &LookupVec<T, S2, B>) -> bool {
+        self.len() <= other.len() && self.iter().all(|item| other.contains(item))
+    }
+
+    /// Returns true if every key in `other` is also in `self`.
+    pub fn is_superset<S2: BuildHasher>(&self, other: &LookupVec<T, S2, B>) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Returns true if both vecs have the same items, in any order.
+    pub fn eq_unordered<S2: BuildHasher>(&self, other: &LookupVec<T, S2, B>) -> bool
+    where T: PartialEq {
+        self.len() == other.len()
+            && self.iter().all(|item| with_key(item, |key| other.get(key)) == Some(item))
+    }
+
+    /// Returns the changes that turn `self` into `other`. Items with the same
+    /// key are compared with `PartialEq`.
+    pub fn diff<S2: BuildHasher>(&self, other: &LookupVec<T, S2, B>) -> Diff<T>
+    where T: Clone + PartialEq {
+        Diff::between(self, other, T::eq)
+    }
+
+    /// Like `diff`, but items with the same key are compared with `eq`.
+    pub fn diff_by<S2, F>(&self, other: &LookupVec<T, S2, B>, eq: F) -> Diff<T>
+    where T: Clone, S2: BuildHasher, F: FnMut(&T, &T) -> bool {
+        Diff::between(self, other, eq)
+    }
+
+    /// Like `retain`, but `keep` may mutate the items. Kept items whose key
+    /// changed are handled according to the `KeyPolicy`.
+    pub fn retain_mut<F>(&mut self, keep: F)
+    where F: FnMut(&mut T) -> bool {
+        self.repair();
+        self.store.retain(keep);
+        self.check_range(0..self.len());
     }
 
 }
 
-impl<T: Lookup, S: BuildHasher> LookupVec<T, S>
+impl<T: Lookup, S: BuildHasher, B: Backend> LookupVec<T, S, B>
 where T::Key: Ord {
     pub fn sort(&mut self) {
+        self.repair();
         // We use unstable for performance since there should never be duplicate
         // keys
-        self.map.sort_unstable_keys()
+        self.store.sort_keys_by(T::Key::cmp)
+    }
+
+    pub fn sorted(mut self) -> IntoIter<T, B> {
+        self.sort();
+        self.into_iter()
     }
+}
 
-    pub fn sort_by<F>(&mut self, mut cmp: F)
+impl<T: Lookup, S: BuildHasher, B: Backend> LookupVec<T, S, B> {
+    pub fn sort_by<F>(&mut self, cmp: F)
         where F: FnMut(&T, &T) -> Ordering {
-        self.map.sort_by(|_, v1, _, v2| cmp(v1, v2))
+        self.repair();
+        self.store.sort_by(cmp, true)
     }
 
-    pub fn sort_unstable_by<F>(&mut self, mut cmp: F)
+    pub fn sort_unstable_by<F>(&mut self, cmp: F)
         where F: FnMut(&T, &T) -> Ordering {
-        self.map.sort_unstable_by(|_, v1, _, v2| cmp(v1, v2))
+        self.repair();
+        self.store.sort_by(cmp, false)
     }
 
-    pub fn sorted(mut self) -> IntoIter<T> {
-        self.sort();
-        self.into_iter()
+    /// Sorts by key, with a comparator for keys that needn't be `Ord`.
+    pub(crate) fn sort_keys_by<F>(&mut self, cmp: F)
+        where F: FnMut(&T::Key, &T::Key) -> Ordering {
+        self.repair();
+        self.store.sort_keys_by(cmp)
     }
 
-    pub fn sorted_by<F>(mut self, cmp: F) -> IntoIter<T>
+    pub fn sorted_by<F>(mut self, cmp: F) -> IntoIter<T, B>
         where F: FnMut(&T, &T) -> Ordering {
         self.sort_by(cmp);
         self.into_iter()
     }
 
-    pub fn sorted_unstable_by<F>(mut self, cmp: F) -> IntoIter<T>
+    pub fn sorted_unstable_by<F>(mut self, cmp: F) -> IntoIter<T, B>
         where F: FnMut(&T, &T) -> Ordering {
         self.sort_unstable_by(cmp);
         self.into_iter()
     }
 }
 
-impl<'a, T: Lookup, S> IntoIterator for &'a LookupVec<T, S> {
+#[cfg(feature = "rayon")]
+impl<T: Lookup, S, B: Backend> LookupVec<T, S, B> {
+    pub fn par_iter(&self) -> ParIter<'_, T, B>
+    where T: Sync, T::Key: Sync {
+        ParIter(self.store.par_iter())
+    }
+
+    /// Returns the items' keys, like `keys`.
+    pub fn par_keys(&self) -> ParKeys<'_, T, B>
+    where T: Sync, T::Key: Send + Sync {
+        ParKeys(self.store.par_keys())
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T: Lookup + Send, S: BuildHasher, B: Backend> LookupVec<T, S, B>
+where T::Key: Send {
+    /// Each item's key is checked when its `ItemMut` is dropped.
+    pub fn par_iter_mut(&mut self) -> ParIterMut<'_, T, S, B>
+    where T::Key: Sync, S: Sync {
+        self.repair();
+        ParIterMut { iter: self.store.par_iter_mut(), check: &self.check }
+    }
+
+    pub fn par_sort_by<F>(&mut self, cmp: F)
+        where F: Fn(&T, &T) -> Ordering + Sync {
+        self.repair();
+        self.store.par_sort_by(cmp)
+    }
+}
+
+impl<'a, T: Lookup, S, B: Backend> IntoIterator for &'a LookupVec<T, S, B> {
     type Item = &'a T;
-    type IntoIter = Iter<'a, T>;
+    type IntoIter = Iter<'a, T, B>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.iter()
     }
 }
 
-impl<'a, T: Lookup, S> IntoIterator for &'a mut LookupVec<T, S> {
-    type Item = &'a mut T;
-    type IntoIter = IterMut<'a, T>;
+impl<'a, T: Lookup, S: BuildHasher, B: Backend> IntoIterator for &'a mut LookupVec<T, S, B> {
+    type Item = ItemMut<'a, T, S, B>;
+    type IntoIter = IterMut<'a, T, S, B>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.iter_mut()
     }
 }
 
-impl<T: Lookup, S> IntoIterator for LookupVec<T, S> {
+impl<T: Lookup, S, B: Backend> IntoIterator for LookupVec<T, S, B> {
     type Item = T;
-    type IntoIter = IntoIter<T>;
+    type IntoIter = IntoIter<T, B>;
 
     fn into_iter(self) -> Self::IntoIter {
-        IntoIter(self.map.into_values())
+        IntoIter(self.store.into_iter())
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T: Lookup + Sync, S, B: Backend> IntoParallelIterator for &'a LookupVec<T, S, B>
+where T::Key: Sync {
+    type Item = &'a T;
+    type Iter = ParIter<'a, T, B>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.par_iter()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T: Lookup + Send, S: BuildHasher + Sync, B: Backend> IntoParallelIterator for &'a mut LookupVec<T, S, B>
+where T::Key: Sync + Send, B::Slot<'a, T, S>: Send {
+    type Item = ItemMut<'a, T, S, B>;
+    type Iter = ParIterMut<'a, T, S, B>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.par_iter_mut()
     }
 }
 
-impl<T: Lookup, S: BuildHasher + Default> FromIterator<T> for LookupVec<T, S> {
+#[cfg(feature = "rayon")]
+impl<T: Lookup + Send, S, B: Backend> IntoParallelIterator for LookupVec<T, S, B>
+where T::Key: Send {
+    type Item = T;
+    type Iter = IntoParIter<T, B>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        IntoParIter(self.store.into_par_iter())
+    }
+}
+
+impl<T: Lookup, S: BuildHasher + Default, B: Backend> FromIterator<T> for LookupVec<T, S, B> {
     fn from_iter<I: IntoIterator<Item = T>>(iterable: I) -> Self {
         let iter = iterable.into_iter();
         let (low, _) = iter.size_hint();
@@ -249,13 +977,50 @@ impl<T: Lookup, const N: usize> From<[T; N]> for LookupVec<T> {
     }
 }
 
-impl<T: Lookup, S: Default> Default for LookupVec<T, S> {
+/// Compares the items in order, like `Vec`. See `eq_unordered()` to ignore
+/// the order.
+impl<T, S1, S2, B1, B2> PartialEq<LookupVec<T, S2, B2>> for LookupVec<T, S1, B1>
+where T: Lookup + PartialEq, B1: Backend, B2: Backend {
+    fn eq(&self, other: &LookupVec<T, S2, B2>) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Lookup + Eq, S, B: Backend> Eq for LookupVec<T, S, B> {}
+
+impl<T: Lookup + PartialOrd, S, B: Backend> PartialOrd for LookupVec<T, S, B> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<T: Lookup + Ord, S, B: Backend> Ord for LookupVec<T, S, B> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<T: Lookup + Hash, S, B: Backend> Hash for LookupVec<T, S, B> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Hash like a slice: the length, then each item
+        state.write_usize(self.len());
+        self.iter().for_each(|item| item.hash(state));
+    }
+}
+
+impl<T: Lookup + fmt::Debug, S, B: Backend> fmt::Debug for LookupVec<T, S, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Lookup, S: Default, B: Backend> Default for LookupVec<T, S, B> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<T: Lookup, S: BuildHasher> Extend<T> for LookupVec<T, S> {
+impl<T: Lookup, S: BuildHasher, B: Backend> Extend<T> for LookupVec<T, S, B> {
     fn extend<I: IntoIterator<Item = T>>(&mut self, iterable: I) {
         // (Note: this is a copy of `std`/`hashbrown`'s reservation logic.)
         // Keys may be already present or show multiple times in the iterator.
@@ -266,7 +1031,7 @@ impl<T: Lookup, S: BuildHasher> Extend<T> for LookupVec<T, S> {
         let reserve = if self.is_empty() {
             iter.size_hint().0
         } else {
-            (iter.size_hint().0 + 1) / 2
+            iter.size_hint().0.div_ceil(2)
         };
         self.reserve(reserve);
         iter.for_each(move |t| {
@@ -275,20 +1040,40 @@ impl<T: Lookup, S: BuildHasher> Extend<T> for LookupVec<T, S> {
     }
 }
 
-impl<'a, T, S> Extend<&'a T> for LookupVec<T, S>
+impl<'a, T, S, B> Extend<&'a T> for LookupVec<T, S, B>
 where
     T: Lookup + Copy,
     S: BuildHasher,
+    B: Backend,
 {
     /// Extend the map with all items pairs in the iterable.
     ///
     /// See the first extend method for more details.
     fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iterable: I) {
-        self.extend(iterable.into_iter().map(|&item| item));
+        self.extend(iterable.into_iter().copied());
     }
 }
 
-impl<T: Lookup, S> Index<usize> for LookupVec<T, S> {
+#[cfg(feature = "rayon")]
+impl<T: Lookup + Send, S: BuildHasher + Default, B: Backend> FromParallelIterator<T> for LookupVec<T, S, B> {
+    fn from_par_iter<I: IntoParallelIterator<Item = T>>(iterable: I) -> Self {
+        let mut vec = Self::with_capacity_and_hasher(0, S::default());
+        vec.par_extend(iterable);
+        vec
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T: Lookup + Send, S: BuildHasher, B: Backend> ParallelExtend<T> for LookupVec<T, S, B> {
+    /// Produces the items in parallel, then pushes them in order, so that
+    /// duplicates are handled as by `extend`.
+    fn par_extend<I: IntoParallelIterator<Item = T>>(&mut self, iterable: I) {
+        let items: Vec<T> = iterable.into_par_iter().collect();
+        self.extend(items);
+    }
+}
+
+impl<T: Lookup, S, B: Backend> Index<usize> for LookupVec<T, S, B> {
     type Output = T;
 
     /// Returns a reference to the value at the supplied `index`.
@@ -305,36 +1090,73 @@ impl<T: Lookup, S> Index<usize> for LookupVec<T, S> {
     }
 }
 
-impl<T: Lookup, S> IndexMut<usize> for LookupVec<T, S> {
-    /// Returns a mutable reference to the value at the supplied `index`.
+impl<T: Lookup, S: BuildHasher, B: Backend> IndexMut<usize> for LookupVec<T, S, B> {
+    /// Returns a mutable reference to the value at the supplied `index`. If
+    /// the item's key is changed, it is handled according to the `KeyPolicy`
+    /// on the next call that takes `&mut self`. Use `get_index_mut` to check
+    /// it right away.
     ///
     /// ***Panics*** if `index` is out of bounds.
     fn index_mut(&mut self, index: usize) -> &mut T {
         let len: usize = self.len();
-        self.get_index_mut(index)
+        self.repair();
+        self.check.mark(index..=index);
+        self.store.as_mut_slice()
+            .get_mut(index)
             .unwrap_or_else(|| {
                 panic!("index out of bounds: the len is {len} but the index is {index}");
             })
     }
 }
 
-// This conflicts with the impl for usize. To get this behavior, we need to impl
-// Index for each of the standard ranges, like indexmap does (see
+// A blanket `impl<I: RangeBounds<usize>> Index<I>` would conflict with the impl
+// for usize, so like indexmap we impl Index for each of the standard ranges (see
 // https://docs.rs/indexmap/2.7.1/src/indexmap/map/slice.rs.html#382-424).
-//
-//impl<I: RangeBounds<usize>, T: Lookup, S> Index<I> for LookupVec<T, S> {
-//    type Output = Slice<T>;
-//
-//    fn index(&self, index: I) -> &Slice<T> {
-//        Slice::<T>::ref_cast(self.map.index((index.start_bound().cloned(), index.end_bound().cloned())))
-//    }
-//}
+macro_rules! impl_index {
+    ($($range:ty),*) => {$(
+        impl<T: Lookup, S, B: Backend> Index<$range> for LookupVec<T, S, B> {
+            type Output = Slice<T, B>;
+
+            /// ***Panics*** if `range` is out of bounds.
+            fn index(&self, range: $range) -> &Slice<T, B> {
+                &self.as_slice()[range]
+            }
+        }
+
+        impl<T: Lookup, S: BuildHasher, B: Backend> IndexMut<$range> for LookupVec<T, S, B> {
+            /// Keys changed through the slice are handled according to the
+            /// `KeyPolicy` on the next call that takes `&mut self`. Use
+            /// `get_range_mut` to check them when the slice is dropped.
+            ///
+            /// ***Panics*** if `range` is out of bounds.
+            fn index_mut(&mut self, range: $range) -> &mut Slice<T, B> {
+                self.repair();
+                let range = index_range(range, self.len());
+                self.check.mark(range.clone());
+                self.range_mut_unchecked(range)
+            }
+        }
+    )*}
+}
+impl_index!(
+    Range<usize>,
+    RangeFrom<usize>,
+    RangeFull,
+    RangeInclusive<usize>,
+    RangeTo<usize>,
+    RangeToInclusive<usize>,
+    (Bound<usize>, Bound<usize>)
+);
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
     use lookupvec_derive::Lookup;
+    use crate::merge::Merge;
+    use crate::test_util::Valued;
+    use crate::test_util::assert_keys_eq;
+    use crate::test_util::valued;
 
     // As of 7/2025 alloc::prelude is nightly-only
     use alloc::borrow::ToOwned;
@@ -358,12 +1180,6 @@ mod tests {
         id: u64,
     }
 
-    macro_rules! assert_keys_eq {
-        ($vec:expr, $($key:expr),+) => {
-            assert_eq!($vec.keys().collect::<Vec<&String>>(), vec![$($key),+]);
-        };
-    }
-
     #[test]
     fn test_new_and_capacity() {
         let vec = LookupVec::<TestItem>::new();
@@ -602,6 +1418,65 @@ mod tests {
 
         item_mut.id = "foo".to_owned();
         assert_eq!(vec[0].id, "foo");
+        assert_eq!(vec.get("foo").unwrap().id, "foo");
+        assert!(vec.get("item1").is_none());
+    }
+
+    #[test]
+    fn test_push_replaces_in_place() {
+        let mut vec = lookupvec![
+            item1(),
+            item2(),
+        ];
+
+        assert_eq!(vec.push_full(item1()), (0, Some(item1())));
+        assert_eq!(vec.push_full(item3()), (2, None));
+        assert_keys_eq!(vec, "item1", "item2", "item3");
+        assert!(vec.contains(&item3()));
+    }
+
+    #[test]
+    fn test_borrowed_key() {
+        let item = item1();
+        assert_eq!(item.borrow_key().unwrap(), "item1");
+        assert_eq!(Rc::new(item).borrow_key().unwrap(), "item1");
+    }
+
+    #[test]
+    fn test_replacing_borrows_key() {
+        use core::sync::atomic::AtomicUsize;
+        use core::sync::atomic::Ordering;
+
+        static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Debug, PartialEq, Eq, Hash)]
+        struct CountedKey(u32);
+        impl Clone for CountedKey {
+            fn clone(&self) -> Self {
+                CLONES.fetch_add(1, Ordering::Relaxed);
+                CountedKey(self.0)
+            }
+        }
+
+        #[derive(Debug, PartialEq, Lookup)]
+        struct Keyed {
+            #[lookup_key]
+            id: CountedKey,
+            value: u32,
+        }
+        let keyed = |id, value| Keyed { id: CountedKey(id), value };
+
+        // New items clone their key once, to store it in the map...
+        let mut vec = LookupVec::<Keyed>::new();
+        vec.extend([keyed(0, 0), keyed(1, 0), keyed(2, 0)]);
+        assert_eq!(CLONES.load(Ordering::Relaxed), 3);
+
+        // ...and replacing an item doesn't clone it at all
+        vec.push(keyed(1, 1));
+        assert_eq!(vec.insert(0, keyed(2, 2)), (0, Some(keyed(2, 0))));
+        assert_eq!(vec.shift_insert(2, keyed(1, 3)), Some(keyed(1, 1)));
+        assert_eq!(CLONES.load(Ordering::Relaxed), 3);
+        assert_eq!(vec.iter().map(|item| (item.id.0, item.value)).collect::<Vec<_>>(), [(2, 2), (0, 0), (1, 3)]);
     }
 
     #[test]
@@ -614,4 +1489,449 @@ mod tests {
         assert_eq!(vec[1].id, "item2");
     }
 
+    #[test]
+    fn test_duplicate_policy() {
+        let base = || LookupVec::from([valued("a", 1), valued("b", 2), valued("c", 3)]);
+
+        let mut vec = base();
+        assert_eq!(vec.duplicate_policy(), DuplicatePolicy::Replace);
+        assert_eq!(vec.push_full(valued("a", 10)), (0, Some(valued("a", 1))));
+        assert_eq!(vec[0], valued("a", 10));
+
+        let mut vec = base().with_duplicate_policy(DuplicatePolicy::KeepFirst);
+        assert_eq!(vec.push_full(valued("a", 10)), (0, Some(valued("a", 10))));
+        assert_eq!(vec[0], valued("a", 1));
+
+        let mut vec = base().with_duplicate_policy(DuplicatePolicy::MoveToEnd);
+        assert_eq!(vec.push_full(valued("a", 10)), (2, Some(valued("a", 1))));
+        assert_eq!(vec.keys().copied().collect::<Vec<_>>(), vec!["b", "c", "a"]);
+        assert_eq!(vec.get("a").unwrap().value, 10);
+        vec.extend([valued("b", 20), valued("d", 4)]);
+        assert_eq!(vec.keys().copied().collect::<Vec<_>>(), vec!["c", "a", "b", "d"]);
+
+        let mut vec = base();
+        let err = vec.push_with(valued("b", 20), DuplicatePolicy::Error).unwrap_err();
+        assert_eq!(err.index(), 1);
+        assert_eq!(err.keys(), &["b"]);
+        assert_eq!(err.into_item(), valued("b", 20));
+        assert_eq!(vec[1], valued("b", 2));
+        assert_eq!(vec.push_with(valued("d", 4), DuplicatePolicy::Error).unwrap(), (3, None));
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate key \"a\" of item at index 0")]
+    fn test_duplicate_policy_error_panics() {
+        let mut vec = lookupvec![duplicates = DuplicatePolicy::Error; valued("a", 1), valued("b", 2)];
+        vec.push(valued("a", 10));
+    }
+
+    #[test]
+    fn test_insert_duplicate_policy() {
+        let base = |policy| LookupVec::from([valued("a", 1), valued("b", 2), valued("c", 3)]).with_duplicate_policy(policy);
+        let keys = |vec: &LookupVec<Valued>| vec.keys().copied().collect::<Vec<_>>();
+
+        let mut vec = base(DuplicatePolicy::Replace);
+        assert_eq!(vec.insert(3, valued("a", 10)), (2, Some(valued("a", 1))));
+        assert_eq!(vec.shift_insert(0, valued("c", 30)), Some(valued("c", 3)));
+        assert_eq!(keys(&vec), vec!["c", "b", "a"]);
+        assert_eq!(vec.entry("b").insert_entry(valued("b", 20)).index(), 1);
+        assert_eq!(vec[1], valued("b", 20));
+
+        let mut vec = base(DuplicatePolicy::KeepFirst);
+        assert_eq!(vec.insert(3, valued("a", 10)), (0, Some(valued("a", 10))));
+        assert_eq!(vec.shift_insert(0, valued("c", 30)), Some(valued("c", 30)));
+        assert_eq!(vec.entry("b").insert_entry(valued("b", 20)).get(), &valued("b", 2));
+        assert_eq!(vec, base(DuplicatePolicy::KeepFirst));
+
+        let mut vec = base(DuplicatePolicy::MoveToEnd);
+        assert_eq!(vec.insert(0, valued("c", 30)), (0, Some(valued("c", 3))));
+        assert_eq!(vec.entry("a").insert_entry(valued("a", 10)).index(), 2);
+        assert_eq!(keys(&vec), vec!["c", "b", "a"]);
+        assert_eq!(vec.get("a").unwrap().value, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate key \"b\" of item at index 1")]
+    fn test_insert_duplicate_policy_error_panics() {
+        let mut vec = lookupvec![duplicates = DuplicatePolicy::Error; valued("a", 1), valued("b", 2)];
+        vec.insert(0, valued("b", 20));
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate key \"b\" of item at index 1")]
+    fn test_entry_duplicate_policy_error_panics() {
+        let mut vec = lookupvec![duplicates = DuplicatePolicy::Error; valued("a", 1), valued("b", 2)];
+        vec.entry("b").insert_entry(valued("b", 20));
+    }
+
+    #[test]
+    fn test_lookupvec_macro_duplicates() {
+        let vec = lookupvec![duplicates = DuplicatePolicy::KeepFirst; valued("a", 1), valued("a", 2),];
+        assert_eq!(vec.len(), 1);
+        assert_eq!(vec[0].value, 1);
+        assert_eq!(vec.duplicate_policy(), DuplicatePolicy::KeepFirst);
+    }
+
+    #[test]
+    fn test_try_push_and_extend() {
+        let mut vec = LookupVec::from([valued("a", 1), valued("b", 2)]);
+        assert_eq!(vec.try_push(valued("c", 3)).unwrap(), 2);
+        let err = vec.try_push(valued("a", 10)).unwrap_err();
+        assert_eq!((err.index(), err.item()), (0, &valued("a", 10)));
+
+        let err = vec.try_extend([valued("d", 4), valued("b", 20), valued("e", 5), valued("d", 40)]).unwrap_err();
+        assert_eq!(err.item(), &valued("b", 20));
+        assert_eq!(err.index(), 1);
+        assert_eq!(err.keys(), &["b", "d"]);
+        assert_eq!(vec.len(), 3);
+
+        let err = vec.try_extend([valued("d", 4), valued("e", 5), valued("e", 50)]).unwrap_err();
+        assert_eq!((err.index(), err.item()), (4, &valued("e", 50)));
+        assert_eq!(format!("{err}"), "duplicate key \"e\" of item at index 4");
+
+        vec.try_extend([valued("d", 4), valued("e", 5)]).unwrap();
+        assert_eq!(vec.keys().copied().collect::<Vec<_>>(), vec!["a", "b", "c", "d", "e"]);
+
+        let vec = LookupVec::<Valued>::try_from_iter([valued("a", 1), valued("b", 2)]).unwrap();
+        assert_eq!(vec.len(), 2);
+        let err = LookupVec::<Valued>::try_from_iter([valued("a", 1), valued("a", 2), valued("a", 3)]).unwrap_err();
+        assert_eq!(err.keys(), &["a"]);
+        assert_eq!(format!("{err}"), "duplicate key \"a\" of item at index 0");
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut vec = LookupVec::from([valued("a", 1), valued("b", 2), valued("c", 3), valued("d", 4)]);
+        vec.retain(|item| item.value != 2);
+        assert_eq!(vec.keys().copied().collect::<Vec<_>>(), vec!["a", "c", "d"]);
+        assert_eq!(vec.get_index_of("d"), Some(2));
+
+        vec.retain_keys(&["d", "a", "x"]);
+        assert_eq!(vec.keys().copied().collect::<Vec<_>>(), vec!["a", "d"]);
+
+        let keys: alloc::collections::BTreeSet<&str> = ["d"].into();
+        vec.retain_keys(&keys);
+        assert_eq!(vec.keys().copied().collect::<Vec<_>>(), vec!["d"]);
+    }
+
+    #[test]
+    fn test_retain_mut() {
+        let mut vec = LookupVec::from([valued("a", 1), valued("b", 2), valued("c", 3)]);
+        vec.retain_mut(|item| {
+            item.value *= 10;
+            if item.id == "c" {
+                item.id = "x";
+            }
+            item.id != "b"
+        });
+        assert_eq!(vec.iter().cloned().collect::<Vec<_>>(), vec![valued("a", 10), valued("x", 30)]);
+        assert_eq!(vec.get("x").unwrap().value, 30);
+        assert!(vec.validate().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "changed from")]
+    fn test_retain_mut_panic_policy() {
+        let mut vec = LookupVec::from([valued("a", 1), valued("b", 2)])
+            .with_key_policy(KeyPolicy::Panic);
+        vec.retain_mut(|item| {
+            item.id = "x";
+            item.value == 1
+        });
+    }
+
+    #[test]
+    fn test_extract_if() {
+        let mut vec = LookupVec::from([valued("a", 1), valued("b", 2), valued("c", 3), valued("d", 4)]);
+        let extracted: Vec<Valued> = vec.extract_if(1.., |item| item.value % 2 == 0).collect();
+        assert_eq!(extracted, vec![valued("b", 2), valued("d", 4)]);
+        assert_eq!(vec.keys().copied().collect::<Vec<_>>(), vec!["a", "c"]);
+        assert_eq!(vec.get_index_of("c"), Some(1));
+
+        let mut vec = LookupVec::from([valued("a", 1), valued("b", 2), valued("c", 3)]);
+        let extracted: Vec<Valued> = vec.extract_if(.., |item| {
+            item.id = if item.id == "b" { "x" } else { item.id };
+            item.value == 1
+        }).collect();
+        assert_eq!(extracted, vec![valued("a", 1)]);
+        assert_eq!(vec.get("x").unwrap().value, 2);
+        vec.push(valued("d", 4));
+        assert_eq!(vec.keys().copied().collect::<Vec<_>>(), vec!["x", "c", "d"]);
+        assert!(vec.validate().is_empty());
+    }
+
+    #[test]
+    fn test_composite_key() {
+        #[derive(Debug, Clone, PartialEq, Lookup)]
+        struct TenantItem {
+            #[lookup_key]
+            tenant_id: String,
+            #[lookup_key]
+            name: String,
+            value: i32,
+        }
+
+        fn tenant_item(tenant_id: &str, name: &str, value: i32) -> TenantItem {
+            TenantItem { tenant_id: tenant_id.to_owned(), name: name.to_owned(), value }
+        }
+
+        let mut vec = LookupVec::from([
+            tenant_item("acme", "foo", 1),
+            tenant_item("acme", "bar", 2),
+            tenant_item("initech", "foo", 3),
+        ]);
+        assert_eq!(vec.get(&("acme", "foo")).unwrap().value, 1);
+        assert_eq!(vec.get(&("initech", "foo")).unwrap().value, 3);
+        assert_eq!(vec.get(&("initech", "bar")), None);
+        assert_eq!(vec.get_index_of(&("acme", "bar")), Some(1));
+
+        let key = TenantItemKey::from(("acme".to_owned(), "bar".to_owned()));
+        assert_eq!(vec.get(&key).unwrap().value, 2);
+        assert_eq!(vec[1].key(), key);
+
+        assert_eq!(vec.push(tenant_item("acme", "foo", 10)), Some(tenant_item("acme", "foo", 1)));
+        assert_eq!(vec.shift_remove(&("acme", "bar")).unwrap().value, 2);
+        assert_eq!(vec.len(), 2);
+        assert_eq!(<(String, String)>::from(vec[0].key()), ("acme".to_owned(), "foo".to_owned()));
+    }
+
+    #[test]
+    fn test_tuple_struct_key() {
+        #[derive(Debug, Clone, PartialEq, Lookup)]
+        struct Tag(#[lookup_key] String);
+
+        #[derive(Debug, Clone, PartialEq, Lookup)]
+        struct Pair(#[lookup_key] u32, i32, #[lookup_key] u32);
+
+        let vec = LookupVec::from([Tag("foo".to_owned()), Tag("bar".to_owned())]);
+        assert_eq!(vec.get("bar"), Some(&Tag("bar".to_owned())));
+        assert_keys_eq!(vec, "foo", "bar");
+
+        let vec = LookupVec::from([Pair(1, 10, 2), Pair(2, 20, 1)]);
+        assert_eq!(vec.get(&(&2, &1)).unwrap().1, 20);
+        assert_eq!(vec[0].key(), PairKey(1, 2));
+    }
+
+    #[test]
+    fn test_enum_key() {
+        #[derive(Debug, Clone, PartialEq, Lookup)]
+        enum Resource {
+            File {
+                #[lookup_key]
+                id: String,
+                size: u64,
+            },
+            Link(String, #[lookup_key] String),
+        }
+
+        let mut vec = LookupVec::from([
+            Resource::File { id: "a".to_owned(), size: 10 },
+            Resource::Link("https://example.com".to_owned(), "b".to_owned()),
+        ]);
+        assert_eq!(vec.get("a"), Some(&Resource::File { id: "a".to_owned(), size: 10 }));
+        assert!(matches!(vec.get("b"), Some(Resource::Link(..))));
+        assert_keys_eq!(vec, "a", "b");
+
+        vec.push(Resource::Link("https://example.org".to_owned(), "a".to_owned()));
+        assert!(matches!(vec.get("a"), Some(Resource::Link(..))));
+        assert_eq!(vec.len(), 2);
+    }
+
+    #[test]
+    fn test_computed_key() {
+        use alloc::string::ToString;
+
+        fn lowercase(name: &str) -> String {
+            name.to_lowercase()
+        }
+
+        #[derive(Debug, Clone, PartialEq, Lookup)]
+        struct User {
+            #[lookup_key(with = lowercase)]
+            name: String,
+        }
+
+        #[derive(Debug, Clone, PartialEq, Lookup)]
+        #[lookup(key = format!("{}/{}", self.ns, self.name), key_type = String)]
+        struct Resource {
+            ns: String,
+            name: String,
+        }
+
+        #[derive(Debug, Clone, PartialEq, Lookup)]
+        struct Port {
+            #[lookup_key(with = ToString::to_string, key_type = String)]
+            number: u16,
+        }
+
+        let vec = LookupVec::from([User { name: "Alice".to_owned() }]);
+        assert_eq!(vec.get("alice").unwrap().name, "Alice");
+        assert_eq!(vec.get("Alice"), None);
+        assert_eq!(vec[0].borrow_key(), None);
+
+        let vec = LookupVec::from([
+            Resource { ns: "a".to_owned(), name: "x".to_owned() },
+            Resource { ns: "b".to_owned(), name: "x".to_owned() },
+        ]);
+        assert_eq!(vec.get("b/x").unwrap().ns, "b");
+        assert_eq!(vec.get_index_of("a/x"), Some(0));
+
+        let vec = LookupVec::from([Port { number: 80 }, Port { number: 443 }]);
+        assert_eq!(vec.get("443").unwrap().number, 443);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        type OtherHasher = core::hash::BuildHasherDefault<ahash::AHasher>;
+        let a = LookupVec::from([valued("a", 1), valued("b", 2), valued("c", 3)]);
+        let mut b = LookupVec::<Valued, OtherHasher>::default();
+        b.extend([valued("d", 4), valued("c", 30), valued("a", 10)]);
+
+        let ids = |iter: &mut dyn Iterator<Item = &Valued>| iter.map(|item| item.id).collect::<Vec<_>>();
+        assert_eq!(ids(&mut a.difference(&b)), vec!["b"]);
+        assert_eq!(ids(&mut a.intersection(&b)), vec!["a", "c"]);
+        assert_eq!(ids(&mut b.intersection(&a)), vec!["c", "a"]);
+        assert_eq!(ids(&mut a.symmetric_difference(&b)), vec!["b", "d"]);
+        assert_eq!(ids(&mut a.union(&b)), vec!["a", "b", "c", "d"]);
+        assert_eq!(ids(&mut a.union(&b).rev()), vec!["d", "c", "b", "a"]);
+        assert_eq!(a.intersection(&b).map(|item| item.value).collect::<Vec<_>>(), vec![1, 3]);
+
+        assert!(!a.is_disjoint(&b));
+        assert!(a.is_disjoint(&LookupVec::from([valued("x", 0)])));
+        assert!(!a.is_subset(&b));
+        assert!(LookupVec::from([valued("c", 0), valued("a", 0)]).is_subset(&a));
+        assert!(a.is_superset(&LookupVec::from([valued("b", 0)])));
+    }
+
+    #[test]
+    fn test_merge_with() {
+        let base = || LookupVec::from([valued("a", 1), valued("b", 2), valued("c", 3)]);
+        let overrides = || LookupVec::from([valued("b", 20), valued("d", 40)]);
+        let entries = |vec: &LookupVec<Valued>| vec.iter().map(|item| (item.id, item.value)).collect::<Vec<_>>();
+
+        let mut vec = base();
+        vec.merge_with(overrides(), Merge::KeepLeft).unwrap();
+        assert_eq!(entries(&vec), vec![("a", 1), ("b", 2), ("c", 3), ("d", 40)]);
+
+        let mut vec = base();
+        vec.merge_with(overrides(), Merge::KeepRight).unwrap();
+        assert_eq!(entries(&vec), vec![("a", 1), ("b", 20), ("c", 3), ("d", 40)]);
+
+        let mut vec = base();
+        vec.merge_with(overrides(), |existing: &mut Valued, incoming: Valued| {
+            existing.value += incoming.value;
+        }).unwrap();
+        assert_eq!(entries(&vec), vec![("a", 1), ("b", 22), ("c", 3), ("d", 40)]);
+
+        let mut vec = base();
+        vec.merge_with_position(overrides(), MergePosition::Incoming, Merge::KeepRight).unwrap();
+        assert_eq!(entries(&vec), vec![("a", 1), ("c", 3), ("b", 20), ("d", 40)]);
+        assert_eq!(vec.get_index_of("b"), Some(2));
+
+        let mut vec = base();
+        let err = vec.merge_with([valued("e", 5), valued("a", 10), valued("f", 6)], Merge::Error).unwrap_err();
+        assert_eq!(err.index(), 0);
+        assert_eq!(err.into_item(), valued("a", 10));
+        assert_eq!(entries(&vec), base().iter().map(|item| (item.id, item.value)).collect::<Vec<_>>());
+
+        let err = vec.merge_with([valued("e", 5), valued("f", 6), valued("e", 50)], Merge::Error).unwrap_err();
+        assert_eq!(err.index(), 3);
+        assert_eq!(err.into_item(), valued("e", 50));
+        assert_eq!(vec, base());
+    }
+
+    #[test]
+    fn test_merge_with_rejects_before_merging() {
+        struct AddPositive;
+        impl MergeStrategy<Valued> for AddPositive {
+            fn accepts(&mut self, _existing: &Valued, incoming: &Valued) -> bool {
+                incoming.value > 0
+            }
+
+            fn merge(&mut self, existing: &mut Valued, incoming: Valued) {
+                existing.value += incoming.value;
+            }
+        }
+
+        let base = || LookupVec::from([valued("a", 1), valued("b", 2)]);
+        let mut vec = base();
+        let err = vec.merge_with([valued("a", 10), valued("c", 3), valued("b", -2)], AddPositive).unwrap_err();
+        assert_eq!(err.index(), 1);
+        assert_eq!(vec, base());
+
+        vec.merge_with([valued("a", 10), valued("c", 3), valued("c", 30)], AddPositive).unwrap();
+        assert_eq!(vec, LookupVec::from([valued("a", 11), valued("b", 2), valued("c", 33)]));
+    }
+
+    #[test]
+    fn test_eq_ord_hash() {
+        #[derive(Debug, PartialEq)]
+        struct Config {
+            items: LookupVec<Valued>,
+        }
+
+        let a = LookupVec::from([valued("a", 1), valued("b", 2)]);
+        let b = LookupVec::from([valued("b", 2), valued("a", 1)]);
+        assert_eq!(a, a.clone());
+        assert_ne!(a, b);
+        assert!(a.eq_unordered(&b));
+        assert!(!a.eq_unordered(&LookupVec::from([valued("b", 2), valued("a", 10)])));
+        assert!(!a.eq_unordered(&LookupVec::from([valued("a", 1)])));
+        assert_eq!(Config { items: a.clone() }, Config { items: a.clone() });
+
+        let mut other = LookupVec::<Valued, core::hash::BuildHasherDefault<ahash::AHasher>>::default();
+        other.extend([valued("a", 1), valued("b", 2)]);
+        assert_eq!(a, other);
+
+        #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Lookup)]
+        struct Id(#[lookup_key] u32);
+        let ints = |values: &[u32]| values.iter().map(|&v| Id(v)).collect::<LookupVec<Id>>();
+        assert!(ints(&[1, 2]) < ints(&[1, 3]));
+        assert!(ints(&[1, 2]) < ints(&[1, 2, 0]));
+        assert_eq!(ints(&[2]).cmp(&ints(&[1, 5])), Ordering::Greater);
+
+        // The vec's only interior mutability is its key-check bookkeeping,
+        // which doesn't take part in ordering
+        #[allow(clippy::mutable_key_type)]
+        let set: alloc::collections::BTreeSet<_> = [ints(&[2]), ints(&[1]), ints(&[2])].into();
+        assert_eq!(set.len(), 2);
+
+        let hash = |value: &dyn Fn(&mut ahash::AHasher)| {
+            let mut hasher = RandomState::with_seeds(1, 2, 3, 4).build_hasher();
+            value(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&|h| ints(&[1, 2]).hash(h)), hash(&|h| vec![Id(1), Id(2)].hash(h)));
+        assert_ne!(hash(&|h| ints(&[1, 2]).hash(h)), hash(&|h| ints(&[2, 1]).hash(h)));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_rayon() {
+        use rayon::prelude::*;
+
+        // Later duplicates replace earlier ones, as with `collect`
+        let mut vec: LookupVec<TestItemIntKey> = (0..1000u64).into_par_iter()
+            .chain((0..10u64).into_par_iter())
+            .map(|id| TestItemIntKey { id })
+            .collect();
+        assert_eq!(vec.len(), 1000);
+        assert_eq!(vec.par_iter().map(|item| item.id).sum::<u64>(), 499500);
+        assert_eq!(vec.par_keys().copied().max(), Some(999));
+
+        vec.par_iter_mut().for_each(|mut item| item.id += 1000);
+        assert_eq!(vec.get_index_of(&1500), Some(500));
+        assert!(!vec.contains_key(&500));
+
+        vec.par_sort_by(|a, b| b.id.cmp(&a.id));
+        assert_eq!(vec.first().unwrap().id, 1999);
+        assert_eq!(vec.get_index_of(&1999), Some(0));
+
+        vec.par_extend((0..5u64).into_par_iter().map(|id| TestItemIntKey { id }));
+        assert_eq!(vec.len(), 1005);
+        assert_eq!(vec.last().unwrap().id, 4);
+
+        let ids: Vec<u64> = vec.into_par_iter().map(|item| item.id).collect();
+        assert_eq!(&ids[..2], &[1999, 1998]);
+    }
 }
\ No newline at end of file